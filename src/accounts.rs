@@ -0,0 +1,193 @@
+//! Tailing stacks in other accounts by assuming a role in each
+use crate::{client_in, credentials, region_of};
+use rusoto_cloudformation::CloudFormationClient;
+use rusoto_cloudtrail::CloudTrailClient;
+use rusoto_core::{credential::AutoRefreshingProvider, request::HttpClient, Region};
+use rusoto_iam::{Iam, IamClient, ListAccountAliasesRequest};
+use rusoto_logs::CloudWatchLogsClient;
+use rusoto_sts::{StsAssumeRoleSessionCredentialsProvider, StsClient};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+/// A role to assume in an account, like `111111111111:role/Deployer`
+#[derive(Debug, PartialEq, Clone)]
+pub struct AccountRole {
+    account: String,
+    role: String,
+}
+
+impl FromStr for AccountRole {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid account role {}, expected account:role/name", s);
+        let position = s.find(':').ok_or_else(invalid)?;
+        let (account, role) = (&s[..position], &s[position + 1..]);
+        if account.len() != 12
+            || !account.chars().all(|c| c.is_ascii_digit())
+            || !role.starts_with("role/")
+        {
+            return Err(invalid());
+        }
+        Ok(AccountRole {
+            account: account.into(),
+            role: role.into(),
+        })
+    }
+}
+
+impl AccountRole {
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    fn arn(&self) -> String {
+        format!("arn:aws:iam::{}:{}", self.account, self.role)
+    }
+}
+
+/// The account of a stack given its id
+pub fn account_of(stack_name: &str) -> Option<&str> {
+    if !stack_name.starts_with("arn:") {
+        return None;
+    }
+    stack_name.split(':').nth(4)
+}
+
+/// Credentials of an assumed role, refreshed as they expire
+type Assumed = AutoRefreshingProvider<StsAssumeRoleSessionCredentialsProvider>;
+
+fn assumed(role_arn: &str) -> Assumed {
+    let sts = StsClient::new_with(
+        HttpClient::new().expect("failed to create request dispatcher"),
+        credentials(),
+        Region::default(),
+    );
+    AutoRefreshingProvider::new(StsAssumeRoleSessionCredentialsProvider::new(
+        sts,
+        role_arn.into(),
+        "stack-tail".into(),
+        None,
+        None,
+        None,
+        None,
+    ))
+    .expect("failed to create credentials provider")
+}
+
+/// Roles assumed for stacks in each account, by account id. Each role is
+/// assumed once, its credentials shared by every client of its account
+#[derive(Default, Clone)]
+pub struct Accounts {
+    roles: HashMap<String, Arc<Assumed>>,
+}
+
+impl Accounts {
+    pub fn new(roles: &[AccountRole]) -> Self {
+        Accounts {
+            roles: roles
+                .iter()
+                .map(|role| (role.account.clone(), Arc::new(assumed(&role.arn()))))
+                .collect(),
+        }
+    }
+
+    /// A client for a region of an account, assuming the account's role when it has one
+    pub fn client(
+        &self,
+        account: Option<&str>,
+        region: Region,
+    ) -> CloudFormationClient {
+        match account.and_then(|account| self.roles.get(account)) {
+            Some(assumed) => CloudFormationClient::new_with(
+                HttpClient::new().expect("failed to create request dispatcher"),
+                assumed.clone(),
+                region,
+            ),
+            None => client_in(region),
+        }
+    }
+
+    /// A client for a stack's account and region, given its name or id
+    pub fn client_for(
+        &self,
+        stack_name: &str,
+    ) -> CloudFormationClient {
+        self.client(
+            account_of(stack_name),
+            region_of(stack_name).unwrap_or_default(),
+        )
+    }
+
+    /// A CloudWatch Logs client for a stack's account and region, given its name or id
+    pub fn logs_client_for(
+        &self,
+        stack_name: &str,
+    ) -> CloudWatchLogsClient {
+        let dispatcher = HttpClient::new().expect("failed to create request dispatcher");
+        let region = region_of(stack_name).unwrap_or_default();
+        match account_of(stack_name).and_then(|account| self.roles.get(account)) {
+            Some(assumed) => CloudWatchLogsClient::new_with(dispatcher, assumed.clone(), region),
+            None => CloudWatchLogsClient::new_with(dispatcher, credentials(), region),
+        }
+    }
+
+    /// A CloudTrail client for a stack's account and region, given its name or id
+    pub fn trail_client_for(
+        &self,
+        stack_name: &str,
+    ) -> CloudTrailClient {
+        let dispatcher = HttpClient::new().expect("failed to create request dispatcher");
+        let region = region_of(stack_name).unwrap_or_default();
+        match account_of(stack_name).and_then(|account| self.roles.get(account)) {
+            Some(assumed) => CloudTrailClient::new_with(dispatcher, assumed.clone(), region),
+            None => CloudTrailClient::new_with(dispatcher, credentials(), region),
+        }
+    }
+
+    /// Aliases of each account, falling back on account ids for accounts
+    /// without one or whose aliases can't be listed
+    pub fn aliases(&self) -> HashMap<String, String> {
+        self.roles
+            .iter()
+            .map(|(account, assumed)| {
+                let iam = IamClient::new_with(
+                    HttpClient::new().expect("failed to create request dispatcher"),
+                    assumed.clone(),
+                    Region::UsEast1,
+                );
+                let alias = iam
+                    .list_account_aliases(ListAccountAliasesRequest::default())
+                    .sync()
+                    .ok()
+                    .and_then(|response| response.account_aliases.into_iter().next())
+                    .unwrap_or_else(|| account.clone());
+                (account.clone(), alias)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_role_parses_account_and_role() {
+        let role = "111111111111:role/Deployer"
+            .parse::<AccountRole>()
+            .expect("invalid account role");
+        assert_eq!(role.account(), "111111111111");
+        assert_eq!(role.arn(), "arn:aws:iam::111111111111:role/Deployer");
+        assert!("payments:role/Deployer".parse::<AccountRole>().is_err());
+        assert!("111111111111:user/deployer".parse::<AccountRole>().is_err());
+        assert!("111111111111".parse::<AccountRole>().is_err());
+    }
+
+    #[test]
+    fn account_of_reads_stack_ids() {
+        assert_eq!(
+            account_of("arn:aws:cloudformation:us-east-1:111111111111:stack/foo/uuid"),
+            Some("111111111111")
+        );
+        assert_eq!(account_of("foo"), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Resolves the stacks associated with Service Catalog AppRegistry applications
+use crate::{credentials, Error};
+use futures::Future;
+use rusoto_core::{
+    credential::ProvideAwsCredentials,
+    param::{Params, ServiceParams},
+    request::HttpClient,
+    signature::SignedRequest,
+    DispatchSignedRequest, Region,
+};
+use serde::Deserialize;
+
+/// The resource type of associated stacks
+const CFN_STACK: &str = "CFN_STACK";
+
+/// A page of ListAssociatedResources results
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    #[serde(default)]
+    resources: Vec<Resource>,
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Resource {
+    name: String,
+    resource_type: Option<String>,
+}
+
+impl Resource {
+    fn is_stack(&self) -> bool {
+        match self.resource_type.as_deref() {
+            Some(resource_type) => resource_type == CFN_STACK,
+            None => true,
+        }
+    }
+}
+
+/// Lists the names of stacks associated with an application, given its name or id
+///
+/// Rusoto doesn't provide an AppRegistry client so requests are signed and dispatched directly
+pub fn stacks(application: &str) -> Result<Vec<String>, Error> {
+    let region = Region::default();
+    let credentials = credentials().credentials().wait()?;
+    let dispatcher = HttpClient::new().expect("failed to create request dispatcher");
+    let mut names = Vec::new();
+    let mut next_token = None;
+    loop {
+        let mut request = SignedRequest::new(
+            "GET",
+            "servicecatalog",
+            &region,
+            &format!("/applications/{}/resources", application),
+        );
+        request.set_hostname(Some(format!(
+            "servicecatalog-appregistry.{}.amazonaws.com",
+            region.name()
+        )));
+        if let Some(token) = &next_token {
+            let mut params = Params::new();
+            params.put("nextToken", token.as_str());
+            request.set_params(params);
+        }
+        request.sign_with_plus(&credentials, true);
+        let response = dispatcher.dispatch(request, None).wait()?.buffer().wait()?;
+        if !response.status.is_success() {
+            return Err(Error::AppRegistry(
+                String::from_utf8_lossy(&response.body).into(),
+            ));
+        }
+        let page: Page = serde_json::from_slice(&response.body)?;
+        names.extend(
+            page.resources
+                .into_iter()
+                .filter(Resource::is_stack)
+                .map(|resource| resource.name),
+        );
+        match page.next_token {
+            token @ Some(_) => next_token = token,
+            None => break,
+        }
+    }
+    if names.is_empty() {
+        return Err(Error::AppRegistry(format!(
+            "no stacks are associated with application {}",
+            application
+        )));
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_includes_only_stacks() -> Result<(), serde_json::Error> {
+        let page: Page = serde_json::from_str(
+            r#"{
+                "resources": [
+                    { "name": "web", "arn": "arn:aws:cloudformation:us-east-1:123456789012:stack/web/abc", "resourceType": "CFN_STACK" },
+                    { "name": "group", "arn": "arn:aws:resource-groups:us-east-1:123456789012:group/group", "resourceType": "RESOURCE_TAG_VALUE" },
+                    { "name": "db", "arn": "arn:aws:cloudformation:us-east-1:123456789012:stack/db/def" }
+                ],
+                "nextToken": "next"
+            }"#,
+        )?;
+        assert_eq!(page.next_token, Some("next".into()));
+        assert_eq!(
+            page.resources
+                .iter()
+                .filter(|resource| resource.is_stack())
+                .map(|resource| resource.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["web", "db"]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,627 @@
+//! Storage for a history of stack events
+use crate::{credentials, is_failure, notify::Summary, stack_logical_id, Error, ResourceState};
+use chrono::{DateTime, Utc};
+use rusoto_core::{request::HttpClient, Region, RusotoError};
+use rusoto_s3::{
+    GetObjectError, GetObjectRequest, ListObjectsV2Request, PutObjectRequest, S3Client, S3,
+};
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::Mutex,
+};
+
+/// Selects where events are archived
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum Backend {
+    /// One JSON file per stack within a directory
+    Json { path: Option<PathBuf> },
+    /// A single SQLite database
+    Sqlite { path: Option<PathBuf> },
+    /// One JSON object per stack within an S3 bucket, for sharing history across a team
+    S3 {
+        bucket: String,
+        prefix: Option<String>,
+    },
+}
+
+/// A place to persist stack events
+pub trait Store {
+    /// Records events for a stack, skipping those already archived
+    fn save(
+        &self,
+        stack_name: &str,
+        events: &[ResourceState],
+    ) -> Result<(), Error>;
+
+    /// Loads all archived events for a stack, oldest first
+    fn load(
+        &self,
+        stack_name: &str,
+    ) -> Result<Vec<ResourceState>, Error>;
+}
+
+/// Default location for local archives
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("stack-tail")
+}
+
+/// Opens the store for a configured backend
+pub fn open(backend: Backend) -> Result<Box<dyn Store + Send>, Error> {
+    Ok(match backend {
+        Backend::Json { path } => Box::new(Json::open(
+            path.unwrap_or_else(|| data_dir().join("archive")),
+        )?),
+        Backend::Sqlite { path } => Box::new(Sqlite::open(
+            &path.unwrap_or_else(|| data_dir().join("archive.db")),
+        )?),
+        Backend::S3 { bucket, prefix } => Box::new(S3Store {
+            client: s3(),
+            bucket,
+            prefix: prefix.unwrap_or_default(),
+            run: format!(
+                "{}-{}-{:08x}",
+                Utc::now().format("%Y%m%dT%H%M%SZ"),
+                process::id(),
+                rand::random::<u32>()
+            ),
+            saved: Mutex::default(),
+        }),
+    })
+}
+
+fn s3() -> S3Client {
+    S3Client::new_with(
+        HttpClient::new().expect("failed to create request dispatcher"),
+        credentials(),
+        Region::default(),
+    )
+}
+
+/// Where the event logs of runs are uploaded, like `s3://bucket/prefix/`
+#[derive(Debug, PartialEq, Clone)]
+pub struct S3Location {
+    bucket: String,
+    prefix: String,
+}
+
+impl FromStr for S3Location {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("s3://") {
+            return Err(format!("expected an s3://bucket/prefix/ url, not {}", s));
+        }
+        let path = &s["s3://".len()..];
+        let (bucket, prefix) = match path.find('/') {
+            Some(index) => (&path[..index], &path[index + 1..]),
+            None => (path, ""),
+        };
+        if bucket.is_empty() {
+            return Err(format!("no bucket in {}", s));
+        }
+        Ok(S3Location {
+            bucket: bucket.into(),
+            prefix: if prefix.is_empty() || prefix.ends_with('/') {
+                prefix.into()
+            } else {
+                format!("{}/", prefix)
+            },
+        })
+    }
+}
+
+impl S3Location {
+    /// Key of a run's object under one of the location's tables. Each table has
+    /// its own prefix, as Athena reads a table from every object under one,
+    /// and runs are partitioned by date so queries can skip what they don't need
+    fn key(
+        &self,
+        table: &str,
+        run: DateTime<Utc>,
+    ) -> String {
+        format!(
+            "{}{}/date={}/{}.json",
+            self.prefix,
+            table,
+            run.format("%Y-%m-%d"),
+            run.format("%Y%m%dT%H%M%SZ")
+        )
+    }
+}
+
+/// Newline delimited JSON, the row per line Athena reads
+fn lines(rows: &[Value]) -> String {
+    rows.iter().map(|row| format!("{}\n", row)).collect()
+}
+
+/// Each stack's events, labeled with the stack they're of
+fn event_rows(stacks: &BTreeMap<String, Vec<ResourceState>>) -> Result<Vec<Value>, Error> {
+    let mut rows = Vec::new();
+    for (stack_name, events) in stacks {
+        for event in events {
+            let mut row = serde_json::to_value(event)?;
+            row["stack"] = json!(stack_name);
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+/// A summary of each stack's most recent operation
+fn summary_rows(
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    run: DateTime<Utc>,
+) -> Vec<Value> {
+    stacks
+        .iter()
+        .map(|(stack_name, events)| {
+            let summary = Summary::of(stack_name, events);
+            let latest = events.iter().rev().find(|event| {
+                event.is_stack() && event.resource_id == stack_logical_id(stack_name)
+            });
+            json!({
+                "stack": stack_name,
+                "run": run.to_rfc3339(),
+                "status": latest.map(|event| &event.status),
+                "reason": latest.map(|event| &event.reason),
+                "succeeded": latest.map(|event| {
+                    event.complete_or_failed()
+                        && !is_failure(&event.status)
+                        && !event.status.contains("ROLLBACK")
+                }),
+                "duration_seconds": summary.duration.map(|duration| duration.num_seconds()),
+                "root_cause": summary.root_cause,
+                "failed_resources": summary
+                    .failures
+                    .iter()
+                    .map(|event| &event.resource_id)
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect()
+}
+
+/// Uploads the complete event log of a run, and a summary of it, as an audit
+/// trail of deployments
+pub fn upload(
+    location: &S3Location,
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    run: DateTime<Utc>,
+) -> Result<(), Error> {
+    let client = s3();
+    for (table, rows) in &[
+        ("events", event_rows(stacks)?),
+        ("summaries", summary_rows(stacks, run)),
+    ] {
+        client
+            .put_object(PutObjectRequest {
+                bucket: location.bucket.clone(),
+                key: location.key(table, run),
+                body: Some(lines(rows).into_bytes().into()),
+                content_type: Some("application/x-ndjson".into()),
+                ..PutObjectRequest::default()
+            })
+            .sync()?;
+    }
+    Ok(())
+}
+
+/// Merges newly fetched events into previously archived events by event id
+pub fn merge(
+    mut archived: Vec<ResourceState>,
+    events: &[ResourceState],
+) -> Vec<ResourceState> {
+    let seen = archived
+        .iter()
+        .filter_map(|event| event.event_id.clone())
+        .collect::<HashSet<_>>();
+    archived.extend(
+        events
+            .iter()
+            .filter(|event| match &event.event_id {
+                Some(id) => !seen.contains(id),
+                _ => false,
+            })
+            .cloned(),
+    );
+    archived.sort_by_key(|event| event.timestamp);
+    archived
+}
+
+pub struct Json {
+    dir: PathBuf,
+}
+
+impl Json {
+    fn open(dir: PathBuf) -> Result<Self, Error> {
+        fs::create_dir_all(&dir)?;
+        Ok(Json { dir })
+    }
+
+    fn path(
+        &self,
+        stack_name: &str,
+    ) -> PathBuf {
+        self.dir
+            .join(format!("{}.json", stack_name.replace('/', "_")))
+    }
+}
+
+impl Store for Json {
+    fn save(
+        &self,
+        stack_name: &str,
+        events: &[ResourceState],
+    ) -> Result<(), Error> {
+        let archived = self.load(stack_name)?;
+        let count = archived.len();
+        let events = merge(archived, events);
+        if events.len() == count {
+            return Ok(());
+        }
+        fs::write(self.path(stack_name), serde_json::to_vec(&events)?)?;
+        Ok(())
+    }
+
+    fn load(
+        &self,
+        stack_name: &str,
+    ) -> Result<Vec<ResourceState>, Error> {
+        let path = self.path(stack_name);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+pub struct Sqlite(Connection);
+
+impl Sqlite {
+    fn open(path: &Path) -> Result<Self, Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS events (
+                event_id TEXT PRIMARY KEY,
+                stack_name TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                event TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS events_by_stack ON events (stack_name, timestamp);",
+        )?;
+        Ok(Sqlite(conn))
+    }
+}
+
+impl Store for Sqlite {
+    fn save(
+        &self,
+        stack_name: &str,
+        events: &[ResourceState],
+    ) -> Result<(), Error> {
+        let mut insert = self.0.prepare_cached(
+            "INSERT OR IGNORE INTO events (event_id, stack_name, timestamp, event)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for event in events {
+            if let Some(id) = &event.event_id {
+                insert.execute(params![
+                    id,
+                    stack_name,
+                    event.timestamp.to_rfc3339(),
+                    serde_json::to_string(event)?
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load(
+        &self,
+        stack_name: &str,
+    ) -> Result<Vec<ResourceState>, Error> {
+        let mut select = self
+            .0
+            .prepare_cached("SELECT event FROM events WHERE stack_name = ?1 ORDER BY timestamp")?;
+        let rows = select
+            .query_map(params![stack_name], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut events = rows
+            .iter()
+            .map(|row| serde_json::from_str(row))
+            .collect::<Result<Vec<ResourceState>, _>>()?;
+        // rfc3339 strings with differing offsets don't sort lexically
+        events.sort_by_key(|event| event.timestamp);
+        Ok(events)
+    }
+}
+
+/// Events of each stack in objects of their own per run under the stack's
+/// prefix, so that runs sharing a bucket never overwrite each other's events
+pub struct S3Store {
+    client: S3Client,
+    bucket: String,
+    prefix: String,
+    /// Unique id of this run, naming the objects it writes
+    run: String,
+    /// Events this run has written for each stack
+    saved: Mutex<HashMap<String, Vec<ResourceState>>>,
+}
+
+impl S3Store {
+    /// Key of the single object per stack archives were once kept in,
+    /// still read so earlier history isn't lost
+    fn legacy_key(
+        &self,
+        stack_name: &str,
+    ) -> String {
+        format!("{}{}.json", self.prefix, stack_name)
+    }
+
+    /// Prefix of the objects of every run which archived a stack's events
+    fn runs_prefix(
+        &self,
+        stack_name: &str,
+    ) -> String {
+        format!("{}{}/", self.prefix, stack_name)
+    }
+
+    fn run_key(
+        &self,
+        stack_name: &str,
+    ) -> String {
+        format!("{}{}.json", self.runs_prefix(stack_name), self.run)
+    }
+
+    /// Keys of every run's objects for a stack
+    fn run_keys(
+        &self,
+        stack_name: &str,
+    ) -> Result<Vec<String>, Error> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let output = self
+                .client
+                .list_objects_v2(ListObjectsV2Request {
+                    bucket: self.bucket.clone(),
+                    prefix: Some(self.runs_prefix(stack_name)),
+                    continuation_token,
+                    ..ListObjectsV2Request::default()
+                })
+                .sync()?;
+            keys.extend(
+                output
+                    .contents
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|object| object.key),
+            );
+            match output.next_continuation_token {
+                token @ Some(_) => continuation_token = token,
+                None => return Ok(keys),
+            }
+        }
+    }
+
+    /// Events in an object, if it exists
+    fn get(
+        &self,
+        key: String,
+    ) -> Result<Vec<ResourceState>, Error> {
+        let output = match self
+            .client
+            .get_object(GetObjectRequest {
+                bucket: self.bucket.clone(),
+                key,
+                ..GetObjectRequest::default()
+            })
+            .sync()
+        {
+            Err(RusotoError::Service(GetObjectError::NoSuchKey(_))) => return Ok(Vec::new()),
+            result => result?,
+        };
+        let mut body = Vec::new();
+        if let Some(stream) = output.body {
+            stream.into_blocking_read().read_to_end(&mut body)?;
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+impl Store for S3Store {
+    fn save(
+        &self,
+        stack_name: &str,
+        events: &[ResourceState],
+    ) -> Result<(), Error> {
+        let mut saved = match self.saved.lock() {
+            Ok(saved) => saved,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let written = saved.get(stack_name).cloned().unwrap_or_default();
+        let count = written.len();
+        let events = merge(written, events);
+        if events.len() == count {
+            return Ok(());
+        }
+        self.client
+            .put_object(PutObjectRequest {
+                bucket: self.bucket.clone(),
+                key: self.run_key(stack_name),
+                body: Some(serde_json::to_vec(&events)?.into()),
+                content_type: Some("application/json".into()),
+                ..PutObjectRequest::default()
+            })
+            .sync()?;
+        saved.insert(stack_name.into(), events);
+        Ok(())
+    }
+
+    fn load(
+        &self,
+        stack_name: &str,
+    ) -> Result<Vec<ResourceState>, Error> {
+        let mut events = self.get(self.legacy_key(stack_name))?;
+        for key in self.run_keys(stack_name)? {
+            let run = self.get(key)?;
+            events = merge(events, &run);
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn event(
+        id: &str,
+        timestamp: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: Some(id.into()),
+            timestamp: DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp"),
+            ..state("AWS::S3::Bucket", "Bucket", "CREATE_COMPLETE")
+        }
+    }
+
+    #[test]
+    fn s3_location_parses_bucket_and_prefix() {
+        assert_eq!(
+            "s3://bucket/deploys".parse(),
+            Ok(S3Location {
+                bucket: "bucket".into(),
+                prefix: "deploys/".into(),
+            })
+        );
+        assert_eq!(
+            "s3://bucket".parse(),
+            Ok(S3Location {
+                bucket: "bucket".into(),
+                prefix: "".into(),
+            })
+        );
+        assert!("bucket/deploys".parse::<S3Location>().is_err());
+        assert!("s3:///deploys".parse::<S3Location>().is_err());
+    }
+
+    #[test]
+    fn runs_are_keyed_by_table_and_date() -> Result<(), chrono::format::ParseError> {
+        let location = S3Location {
+            bucket: "bucket".into(),
+            prefix: "deploys/".into(),
+        };
+        let run = DateTime::parse_from_rfc3339("2019-01-02T03:04:05Z")?.with_timezone(&Utc);
+        assert_eq!(
+            location.key("events", run),
+            "deploys/events/date=2019-01-02/20190102T030405Z.json"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn summary_rows_describe_each_stack() -> Result<(), chrono::format::ParseError> {
+        let mut stacks = BTreeMap::new();
+        stacks.insert(
+            "stack".to_string(),
+            vec![
+                state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+                state("AWS::SQS::Queue", "queue", "UPDATE_FAILED"),
+                state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_COMPLETE"),
+            ],
+        );
+        let run = DateTime::parse_from_rfc3339("2019-01-02T03:04:05Z")?.with_timezone(&Utc);
+        let rows = summary_rows(&stacks, run);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["status"], "UPDATE_ROLLBACK_COMPLETE");
+        assert_eq!(rows[0]["succeeded"], false);
+        assert_eq!(rows[0]["failed_resources"], json!(["queue"]));
+        assert_eq!(lines(&rows).lines().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn s3_runs_write_objects_of_their_own() {
+        let store = S3Store {
+            client: s3(),
+            bucket: "bucket".into(),
+            prefix: "stack-tail/".into(),
+            run: "20190102T030405Z-42-0000beef".into(),
+            saved: Mutex::default(),
+        };
+        assert_eq!(store.legacy_key("stack"), "stack-tail/stack.json");
+        assert_eq!(
+            store.run_key("stack"),
+            "stack-tail/stack/20190102T030405Z-42-0000beef.json"
+        );
+        assert!(store
+            .run_key("stack")
+            .starts_with(&store.runs_prefix("stack")));
+    }
+
+    #[test]
+    fn merge_skips_archived_events() {
+        let merged = merge(
+            vec![event("b", "2019-01-01T00:00:02Z")],
+            &[
+                event("b", "2019-01-01T00:00:02Z"),
+                event("a", "2019-01-01T00:00:01Z"),
+            ],
+        );
+        assert_eq!(
+            merged
+                .iter()
+                .map(|e| e.event_id.clone().unwrap_or_default())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn sqlite_round_trips_events() -> Result<(), Error> {
+        let store = Sqlite::open(Path::new(":memory:"))?;
+        store.save("stack", &[event("a", "2019-01-01T00:00:01Z")])?;
+        store.save(
+            "stack",
+            &[
+                event("a", "2019-01-01T00:00:01Z"),
+                event("b", "2019-01-01T00:00:02Z"),
+            ],
+        )?;
+        store.save("other", &[event("c", "2019-01-01T00:00:03Z")])?;
+        assert_eq!(store.load("stack")?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+//! Short lived on-disk cache of stack names, sparing the ListStacks api
+//! repeated calls from back to back invocations
+use crate::credentials;
+use chrono::{DateTime, Duration, Utc};
+use futures::Future;
+use rusoto_core::{credential::ProvideAwsCredentials, request::HttpClient, Region};
+use rusoto_sts::{GetCallerIdentityRequest, Sts, StsClient};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How long listed stack names are reused for
+const TTL_SECONDS: i64 = 60;
+
+/// How long the account of an access key is reused for
+const IDENTITY_TTL_SECONDS: i64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Entry<T> {
+    fetched: DateTime<Utc>,
+    value: T,
+}
+
+impl<T> Entry<T> {
+    fn fresh(
+        &self,
+        now: DateTime<Utc>,
+        ttl: Duration,
+    ) -> bool {
+        now - self.fetched < ttl
+    }
+}
+
+fn dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("stack-tail"))
+}
+
+/// Cache file name for a named listing, scoped to an account and region
+fn file_name(
+    account: &str,
+    region: &Region,
+    listing: &str,
+) -> String {
+    format!("{}-{}-{}.json", account, region.name(), listing)
+}
+
+fn write<T: Serialize>(
+    path: &Path,
+    entry: &Entry<T>,
+) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec(entry)?)
+}
+
+/// Returns a value cached at a path within a ttl, fetching and caching it
+/// when the cache is missing or stale
+fn cached<T: Serialize + DeserializeOwned, E>(
+    path: Option<PathBuf>,
+    ttl: Duration,
+    fetch: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let cached = path
+        .as_ref()
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|bytes| serde_json::from_slice::<Entry<T>>(&bytes).ok());
+    match cached {
+        Some(entry) if entry.fresh(Utc::now(), ttl) => Ok(entry.value),
+        _ => {
+            let entry = Entry {
+                fetched: Utc::now(),
+                value: fetch()?,
+            };
+            if let Some(path) = path {
+                // failing to cache only costs a later api call
+                drop(write(&path, &entry));
+            }
+            Ok(entry.value)
+        }
+    }
+}
+
+/// The id of the account the default credentials belong to, or `None` when
+/// it can't be looked up. It's cached by access key, which switching
+/// profiles or exported keys changes
+pub fn account_id() -> Option<String> {
+    let access_key = credentials()
+        .credentials()
+        .wait()
+        .ok()?
+        .aws_access_key_id()
+        .to_string();
+    cached(
+        dir().map(|dir| dir.join(format!("identity-{}.json", access_key))),
+        Duration::seconds(IDENTITY_TTL_SECONDS),
+        || {
+            let sts = StsClient::new_with(
+                HttpClient::new().expect("failed to create request dispatcher"),
+                credentials(),
+                Region::default(),
+            );
+            sts.get_caller_identity(GetCallerIdentityRequest {})
+                .sync()
+                .ok()
+                .and_then(|identity| identity.account)
+                .ok_or(())
+        },
+    )
+    .ok()
+}
+
+/// Returns recently cached stack names for a listing in an account and
+/// region, fetching and caching them when the cache is missing or stale, or
+/// always when `refresh` is set. Listings of the default credentials' account
+/// are scoped by looking it up, and aren't cached when it can't be
+pub fn stack_names<E>(
+    account: Option<&str>,
+    region: &Region,
+    listing: &str,
+    refresh: bool,
+    fetch: impl FnOnce() -> Result<Vec<String>, E>,
+) -> Result<Vec<String>, E> {
+    let account = account.map(String::from).or_else(account_id);
+    let ttl = if refresh {
+        Duration::zero()
+    } else {
+        Duration::seconds(TTL_SECONDS)
+    };
+    cached(
+        account.and_then(|account| dir().map(|dir| dir.join(file_name(&account, region, listing)))),
+        ttl,
+        fetch,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entry_is_fresh_within_ttl() {
+        let now = Utc::now();
+        let ttl = Duration::seconds(TTL_SECONDS);
+        let entry = Entry {
+            fetched: now - Duration::seconds(30),
+            value: vec!["stack".to_string()],
+        };
+        assert!(entry.fresh(now, ttl));
+        assert!(!entry.fresh(now + ttl, ttl));
+        assert!(!entry.fresh(now, Duration::zero()));
+    }
+
+    #[test]
+    fn listings_are_scoped_by_account_and_region() {
+        assert_ne!(
+            file_name("111111111111", &Region::UsEast1, "detectable"),
+            file_name("222222222222", &Region::UsEast1, "detectable")
+        );
+        assert_ne!(
+            file_name("111111111111", &Region::UsEast1, "detectable"),
+            file_name("111111111111", &Region::EuWest1, "detectable")
+        );
+    }
+}
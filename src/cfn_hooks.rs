@@ -0,0 +1,146 @@
+//! Invocations of CloudFormation hooks on resources, as their events describe them
+use crate::{is_hook, ResourceState};
+use std::fmt;
+
+/// Where a hook ran and what its failure does to the operation, so far as
+/// its event's reason names them
+#[derive(Debug, Default, PartialEq)]
+pub struct Invocation<'a> {
+    /// Type name of the hook, like `MyCompany::Testing::MyTestHook`
+    pub hook: Option<&'a str>,
+    /// Point of the operation the hook ran at, like `PRE_PROVISION`
+    pub point: Option<&'a str>,
+    /// `FAIL` when the hook failing fails the operation, `WARN` when it carries on
+    pub failure_mode: Option<&'a str>,
+}
+
+/// Whether a word is a type name, like `AWS::S3::Bucket`
+fn is_type_name(word: &str) -> bool {
+    let parts = word.split("::").collect::<Vec<_>>();
+    parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Whether a word is an invocation point, like `PRE_PROVISION`
+fn is_point(word: &str) -> bool {
+    (word.starts_with("PRE_") || word.starts_with("POST_"))
+        && word.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+}
+
+impl<'a> Invocation<'a> {
+    /// The invocation a hook's event describes, or `None` for the events of
+    /// resources themselves and hooks whose reasons name nothing of it
+    pub fn of(state: &'a ResourceState) -> Option<Self> {
+        if !is_hook(&state.status) {
+            return None;
+        }
+        let words = state
+            .reason
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == ':' || c == '_'))
+            .map(|word| word.trim_matches(':'))
+            .collect::<Vec<_>>();
+        let invocation = Invocation {
+            // the hooked resource's own type may be mentioned too
+            hook: words
+                .iter()
+                .find(|word| is_type_name(word) && **word != state.resource_type)
+                .copied(),
+            point: words.iter().find(|word| is_point(word)).copied(),
+            failure_mode: words
+                .iter()
+                .find(|word| **word == "FAIL" || **word == "WARN")
+                .copied(),
+        };
+        if invocation == Invocation::default() {
+            None
+        } else {
+            Some(invocation)
+        }
+    }
+}
+
+impl fmt::Display for Invocation<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let parts = self
+            .hook
+            .map(String::from)
+            .into_iter()
+            .chain(self.point.map(|point| format!("at {}", point)))
+            .chain(self.failure_mode.map(|mode| format!("in {} mode", mode)))
+            .collect::<Vec<_>>();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn event(
+        status: &str,
+        reason: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: Some("Bucket-HOOK_COMPLETE_FAILED-2023-01-18T20:07:35.254Z".into()),
+            resource_type: "AWS::S3::Bucket".into(),
+            timestamp: DateTime::parse_from_rfc3339("2023-01-18T20:07:35.254Z")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: "Bucket".into(),
+            physical_id: None,
+            reason: reason.into(),
+        }
+    }
+
+    #[test]
+    fn invocations_are_parsed_from_failed_hook_events() {
+        let failed = event(
+            "HOOK_COMPLETE_FAILED",
+            "Hook MyCompany::Testing::MyTestHook failed at PRE_PROVISION in FAIL mode: \
+             Template failed validation, the following rule(s) failed: \
+             [S3_BUCKET_LOGGING_ENABLED] for AWS::S3::Bucket",
+        );
+        let invocation = Invocation::of(&failed).expect("hook invocation");
+        assert_eq!(
+            invocation,
+            Invocation {
+                hook: Some("MyCompany::Testing::MyTestHook"),
+                point: Some("PRE_PROVISION"),
+                failure_mode: Some("FAIL"),
+            }
+        );
+        assert_eq!(
+            invocation.to_string(),
+            "MyCompany::Testing::MyTestHook at PRE_PROVISION in FAIL mode"
+        );
+    }
+
+    #[test]
+    fn invocations_are_only_parsed_from_hook_events() {
+        assert_eq!(
+            Invocation::of(&event(
+                "CREATE_FAILED",
+                "The following hook(s) failed: [MyCompany::Testing::MyTestHook]"
+            )),
+            None
+        );
+        assert_eq!(
+            Invocation::of(&event("HOOK_IN_PROGRESS", "Hook invocation is in progress")),
+            None
+        );
+        assert_eq!(
+            Invocation::of(&event(
+                "HOOK_COMPLETE_SUCCEEDED",
+                "Hook MyCompany::Testing::MyTestHook succeeded at PRE_PROVISION"
+            ))
+            .map(|invocation| invocation.to_string()),
+            Some("MyCompany::Testing::MyTestHook at PRE_PROVISION".into())
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Changes planned by the change set behind an update, telling what about
+//! each modified resource is being updated
+use crate::{accounts::Accounts, metadata, Error, ResourceState};
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, DescribeChangeSetInput, ResourceChange,
+};
+use std::{collections::HashMap, fmt};
+
+/// The attributes and properties a resource change targets, noting those
+/// requiring the resource's replacement
+fn targets(change: &ResourceChange) -> Vec<String> {
+    let mut targets = Vec::new();
+    for target in change
+        .details
+        .iter()
+        .flatten()
+        .filter_map(|detail| detail.target.as_ref())
+    {
+        let path = match (target.attribute.as_deref(), target.name.as_deref()) {
+            (Some("Properties"), Some(name)) => name.to_string(),
+            (Some(attribute), _) => attribute.to_string(),
+            (None, Some(name)) => name.to_string(),
+            (None, None) => continue,
+        };
+        let path = if target.requires_recreation.as_deref() == Some("Always") {
+            format!("{} (replacement)", path)
+        } else {
+            path
+        };
+        if !targets.contains(&path) {
+            targets.push(path);
+        }
+    }
+    targets
+}
+
+/// Targets of each resource a change set modifies, by logical id
+fn planned(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+    change_set_id: &str,
+) -> Result<HashMap<String, Vec<String>>, Error> {
+    let mut planned = HashMap::new();
+    let mut next_token = None;
+    loop {
+        let output = cf
+            .describe_change_set(DescribeChangeSetInput {
+                change_set_name: change_set_id.into(),
+                stack_name: Some(stack_name.into()),
+                next_token: next_token.clone(),
+                ..DescribeChangeSetInput::default()
+            })
+            .sync()?;
+        // change sets which haven't been executed didn't start the update
+        if !output
+            .execution_status
+            .as_deref()
+            .unwrap_or_default()
+            .starts_with("EXECUTE_")
+        {
+            return Ok(HashMap::new());
+        }
+        for change in output
+            .changes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|change| change.resource_change)
+            .filter(|change| change.action.as_deref() == Some("Modify"))
+        {
+            if let Some(logical_id) = &change.logical_resource_id {
+                planned.insert(logical_id.clone(), targets(&change));
+            }
+        }
+        match output.next_token {
+            token @ Some(_) => next_token = token,
+            None => return Ok(planned),
+        }
+    }
+}
+
+/// The changes planned for an update
+struct Update {
+    /// The event starting the update
+    event_id: String,
+    /// When the update started, before which events belong to earlier operations
+    started: DateTime<FixedOffset>,
+    /// Targets of each resource modified, by logical id
+    targets: HashMap<String, Vec<String>>,
+}
+
+/// Changes planned for each stack's current update, fetched once per update
+#[derive(Default)]
+pub struct Planned {
+    updates: HashMap<String, Update>,
+}
+
+impl Planned {
+    /// Fetches the changes planned by the change set which started a stack's
+    /// update, unless they were already fetched. Updates which weren't started
+    /// from a change set have none
+    pub fn fetch(
+        &mut self,
+        accounts: &Accounts,
+        stack_name: &str,
+        start: &ResourceState,
+    ) {
+        let event_id = match &start.event_id {
+            Some(event_id) if start.status == "UPDATE_IN_PROGRESS" => event_id,
+            _ => return,
+        };
+        if self
+            .updates
+            .get(stack_name)
+            .map_or(false, |update| &update.event_id == event_id)
+        {
+            return;
+        }
+        let cf = accounts.client_for(stack_name);
+        let targets = metadata::describe(&cf, stack_name)
+            .and_then(|stack| match stack.and_then(|stack| stack.change_set_id) {
+                Some(change_set_id) => planned(&cf, stack_name, &change_set_id),
+                None => Ok(HashMap::new()),
+            })
+            .unwrap_or_else(|err| {
+                eprintln!("{} {}", "notice:".bold().yellow(), err);
+                HashMap::new()
+            });
+        self.updates.insert(
+            stack_name.into(),
+            Update {
+                event_id: event_id.clone(),
+                started: start.timestamp,
+                targets,
+            },
+        );
+    }
+
+    /// What's planned to change about a resource when an event of its stack's
+    /// current update marks it beginning to change
+    pub fn of(
+        &self,
+        stack_name: &str,
+        state: &ResourceState,
+    ) -> Option<&[String]> {
+        if state.status != "UPDATE_IN_PROGRESS" {
+            return None;
+        }
+        self.updates
+            .get(stack_name)
+            .filter(|update| state.timestamp >= update.started)
+            .and_then(|update| update.targets.get(&state.resource_id))
+            .map(Vec::as_slice)
+            .filter(|targets| !targets.is_empty())
+    }
+}
+
+/// A line of what's changing about a resource, beneath the event of it
+/// beginning to change
+pub struct Changing<'a>(pub &'a [String]);
+
+impl fmt::Display for Changing<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "  {} {}",
+            "changing".bright_black(),
+            self.0.join(", ").bright_black()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudformation::{ResourceChangeDetail, ResourceTargetDefinition};
+
+    fn detail(
+        attribute: &str,
+        name: Option<&str>,
+        requires_recreation: &str,
+    ) -> ResourceChangeDetail {
+        ResourceChangeDetail {
+            target: Some(ResourceTargetDefinition {
+                attribute: Some(attribute.into()),
+                name: name.map(String::from),
+                requires_recreation: Some(requires_recreation.into()),
+            }),
+            ..ResourceChangeDetail::default()
+        }
+    }
+
+    #[test]
+    fn targets_name_changed_properties() {
+        let change = ResourceChange {
+            action: Some("Modify".into()),
+            details: Some(vec![
+                detail("Properties", Some("MemorySize"), "Never"),
+                detail("Properties", Some("MemorySize"), "Never"),
+                detail("Properties", Some("FunctionName"), "Always"),
+                detail("Tags", None, "Never"),
+            ]),
+            ..ResourceChange::default()
+        };
+        assert_eq!(
+            targets(&change),
+            vec!["MemorySize", "FunctionName (replacement)", "Tags"]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! The last event seen of each stack, persisted so that tailing
+//! resumes where it left off after being re-invoked
+use crate::{Error, ResourceState};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Checkpoint {
+    pub event_id: String,
+    pub timestamp: DateTime<FixedOffset>,
+}
+
+impl Checkpoint {
+    /// The latest of a chronological series of events
+    fn of(states: &[ResourceState]) -> Option<Checkpoint> {
+        states.iter().rev().find_map(|state| {
+            state.event_id.as_ref().map(|event_id| Checkpoint {
+                event_id: event_id.clone(),
+                timestamp: state.timestamp,
+            })
+        })
+    }
+
+    /// Events following this one. When this event is no longer among them,
+    /// those which happened after it
+    pub fn unseen<'a>(
+        &self,
+        states: &'a [ResourceState],
+    ) -> &'a [ResourceState] {
+        let start = match states
+            .iter()
+            .position(|state| state.event_id.as_ref() == Some(&self.event_id))
+        {
+            Some(position) => position + 1,
+            None => states
+                .iter()
+                .position(|state| state.timestamp > self.timestamp)
+                .unwrap_or_else(|| states.len()),
+        };
+        &states[start..]
+    }
+}
+
+/// A file of each stack's checkpoint
+pub struct StateFile {
+    path: PathBuf,
+    checkpoints: BTreeMap<String, Checkpoint>,
+}
+
+impl StateFile {
+    /// Loads checkpoints from a file, which needn't exist yet
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let checkpoints = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(StateFile {
+            path: path.into(),
+            checkpoints,
+        })
+    }
+
+    pub fn checkpoints(&self) -> &BTreeMap<String, Checkpoint> {
+        &self.checkpoints
+    }
+
+    /// Records the latest of a stack's events, writing the file when it's new
+    pub fn record(
+        &mut self,
+        stack_name: &str,
+        states: &[ResourceState],
+    ) -> Result<(), Error> {
+        let checkpoint = match Checkpoint::of(states) {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        if self.checkpoints.get(stack_name) == Some(&checkpoint) {
+            return Ok(());
+        }
+        self.checkpoints.insert(stack_name.into(), checkpoint);
+        fs::write(&self.path, serde_json::to_vec_pretty(&self.checkpoints)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn event(
+        id: &str,
+        timestamp: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: Some(id.into()),
+            timestamp: DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp"),
+            ..state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE")
+        }
+    }
+
+    #[test]
+    fn unseen_events_follow_checkpoint() {
+        let states = vec![
+            event("1", "2019-01-01T00:00:00Z"),
+            event("2", "2019-01-01T00:01:00Z"),
+            event("3", "2019-01-01T00:02:00Z"),
+        ];
+        let checkpoint = Checkpoint::of(&states[..2]).expect("no checkpoint");
+        assert_eq!(checkpoint.unseen(&states), &states[2..]);
+        let expired = Checkpoint {
+            event_id: "0".into(),
+            ..checkpoint
+        };
+        assert_eq!(expired.unseen(&states), &states[2..]);
+    }
+}
@@ -0,0 +1,99 @@
+//! User configuration loaded from a TOML file
+use crate::{archive::Backend, hooks::Hook, icons::IconsConfig, theme::ThemeConfig, Error};
+use serde::Deserialize;
+use std::{env, fs, path::PathBuf};
+
+/// Env variable which may point to an alternative config file
+const CONFIG_ENV: &str = "STACK_TAIL_CONFIG";
+
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Where stack events are archived, if anywhere
+    pub archive: Option<Backend>,
+    /// Scripts run in response to matching events
+    pub hooks: Vec<Hook>,
+    /// Colors statuses are displayed in
+    pub theme: ThemeConfig,
+    /// Icons marking statuses
+    pub icons: IconsConfig,
+}
+
+impl Config {
+    /// Path to the config file, `$STACK_TAIL_CONFIG` when set, otherwise
+    /// `stack-tail/config.toml` under the platform's config directory
+    fn path() -> Option<PathBuf> {
+        env::var_os(CONFIG_ENV)
+            .map(PathBuf::from)
+            .or_else(|| dirs::config_dir().map(|dir| dir.join("stack-tail").join("config.toml")))
+    }
+
+    /// Loads config, falling back on defaults when no config file exists
+    pub fn load() -> Result<Config, Error> {
+        match Config::path() {
+            Some(path) if path.exists() => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+            _ => Ok(Config::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_defaults_to_no_archive() -> Result<(), toml::de::Error> {
+        assert_eq!(toml::from_str::<Config>("")?, Config::default());
+        Ok(())
+    }
+
+    #[test]
+    fn config_parses_archive_backends() -> Result<(), toml::de::Error> {
+        assert_eq!(
+            toml::from_str::<Config>(
+                r#"
+                [archive]
+                backend = "s3"
+                bucket = "deployments"
+                prefix = "stack-tail/"
+                "#
+            )?,
+            Config {
+                archive: Some(Backend::S3 {
+                    bucket: "deployments".into(),
+                    prefix: Some("stack-tail/".into()),
+                }),
+                ..Config::default()
+            }
+        );
+        assert_eq!(
+            toml::from_str::<Config>(
+                r#"
+                [archive]
+                backend = "sqlite"
+                "#
+            )?,
+            Config {
+                archive: Some(Backend::Sqlite { path: None }),
+                ..Config::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn config_parses_hooks() -> Result<(), toml::de::Error> {
+        let config = toml::from_str::<Config>(
+            r#"
+            [[hooks]]
+            type = "AWS::RDS::*"
+            status = "*_FAILED"
+            run = "./page-dba.sh"
+            "#,
+        )?;
+        assert_eq!(config.hooks.len(), 1);
+        assert_eq!(config.hooks[0].run, "./page-dba.sh");
+        assert_eq!(config.hooks[0].resource, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+//! Estimating the monthly cost of a template's resources before deploying it
+use crate::{metadata, template, Error};
+use colored::Colorize;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, EstimateTemplateCostInput, Parameter,
+};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+use structopt::StructOpt;
+use tabwriter::TabWriter;
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        long = "template",
+        parse(from_os_str),
+        help = "Template file to estimate rather than a deployed stack's template"
+    )]
+    pub template: Option<PathBuf>,
+    #[structopt(
+        long = "parameters",
+        raw(number_of_values = "1"),
+        help = "A template parameter, like Key=Value, overriding a deployed stack's value. May be repeated"
+    )]
+    pub parameters: Vec<TemplateParameter>,
+    #[structopt(raw(required_unless = r#""template""#))]
+    pub stack_name: Option<String>,
+}
+
+/// A template parameter's value, like `InstanceType=t3.micro`
+#[derive(Debug, PartialEq, Clone)]
+pub struct TemplateParameter {
+    key: String,
+    value: String,
+}
+
+impl FromStr for TemplateParameter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find('=') {
+            Some(position) if position > 0 => Ok(TemplateParameter {
+                key: s[..position].into(),
+                value: s[position + 1..].into(),
+            }),
+            _ => Err(format!("invalid parameter {}, expected Key=Value", s)),
+        }
+    }
+}
+
+/// Parameters of a deployed stack, with the given values overriding theirs
+fn merged(
+    deployed: Vec<Parameter>,
+    overrides: Vec<TemplateParameter>,
+) -> Vec<Parameter> {
+    let mut parameters = deployed
+        .into_iter()
+        .filter_map(|parameter| Some((parameter.parameter_key?, parameter.parameter_value)))
+        .collect::<BTreeMap<_, _>>();
+    for TemplateParameter { key, value } in overrides {
+        parameters.insert(key, Some(value));
+    }
+    parameters
+        .into_iter()
+        .map(|(key, value)| Parameter {
+            parameter_key: Some(key),
+            parameter_value: value,
+            ..Parameter::default()
+        })
+        .collect()
+}
+
+/// Number of resources a template declares of each type, the best breakdown
+/// available without following the calculator link
+fn breakdown(template: &Value) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for resource in template
+        .get("Resources")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|resources| resources.values())
+    {
+        if let Some(resource_type) = resource.get("Type").and_then(Value::as_str) {
+            *counts.entry(resource_type).or_default() += 1;
+        }
+    }
+    counts
+}
+
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+) -> Result<(), Error> {
+    let Options {
+        template,
+        parameters,
+        stack_name,
+    } = options;
+    let (body, parsed, deployed) = match (template, stack_name) {
+        (Some(path), _) => {
+            let body = fs::read_to_string(path)?;
+            // templates using yaml's short form intrinsic functions don't parse,
+            // leaving them without a breakdown
+            let parsed = serde_json::from_str::<Value>(&body)
+                .ok()
+                .or_else(|| serde_yaml::from_str::<Value>(&body).ok());
+            (body, parsed, Vec::new())
+        }
+        (None, Some(stack_name)) => {
+            let (template, _) = template::fetch(&cf, &stack_name)?;
+            let deployed = metadata::describe(&cf, &stack_name)?
+                .and_then(|stack| stack.parameters)
+                .unwrap_or_default();
+            (serde_json::to_string(&template)?, Some(template), deployed)
+        }
+        (None, None) => unreachable!("stack name is required without a template"),
+    };
+    let url = cf
+        .estimate_template_cost(EstimateTemplateCostInput {
+            template_body: Some(body),
+            parameters: Some(merged(deployed, parameters)),
+            ..EstimateTemplateCostInput::default()
+        })
+        .sync()?
+        .url
+        .unwrap_or_default();
+    println!("{} {}", "estimate:".bold(), url);
+    if let Some(template) = parsed {
+        println!();
+        let mut writer = TabWriter::new(io::stdout());
+        for (resource_type, count) in breakdown(&template) {
+            writeln!(writer, "{}\t{}", count, resource_type.bright_black())?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merged_overrides_deployed_parameters() -> Result<(), String> {
+        let deployed = vec![
+            Parameter {
+                parameter_key: Some("InstanceType".into()),
+                parameter_value: Some("t3.micro".into()),
+                ..Parameter::default()
+            },
+            Parameter {
+                parameter_key: Some("Env".into()),
+                parameter_value: Some("prod".into()),
+                ..Parameter::default()
+            },
+        ];
+        let merged = merged(deployed, vec!["InstanceType=m5.large".parse()?]);
+        assert_eq!(
+            merged
+                .iter()
+                .map(|parameter| (
+                    parameter.parameter_key.as_deref(),
+                    parameter.parameter_value.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("Env"), Some("prod")),
+                (Some("InstanceType"), Some("m5.large"))
+            ]
+        );
+        assert!("=m5.large".parse::<TemplateParameter>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn breakdown_counts_resource_types() {
+        let template = json!({
+            "Resources": {
+                "Web": { "Type": "AWS::EC2::Instance" },
+                "Worker": { "Type": "AWS::EC2::Instance" },
+                "Assets": { "Type": "AWS::S3::Bucket" }
+            }
+        });
+        assert_eq!(
+            breakdown(&template).into_iter().collect::<Vec<_>>(),
+            vec![("AWS::EC2::Instance", 2), ("AWS::S3::Bucket", 1)]
+        );
+    }
+}
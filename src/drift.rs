@@ -0,0 +1,386 @@
+//! Bulk drift detection across stacks
+use crate::{cache, lock::Lock, Error};
+use chrono::Utc;
+use colored::Colorize;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, DescribeStackDriftDetectionStatusError,
+    DescribeStackDriftDetectionStatusInput, DescribeStackDriftDetectionStatusOutput,
+    DescribeStackResourceDriftsError, DescribeStackResourceDriftsInput, DetectStackDriftInput,
+    ListStacksError, ListStacksInput, StackResourceDrift,
+};
+use rusoto_core::{Region, RusotoError};
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    thread::sleep,
+    time::Duration,
+};
+use structopt::StructOpt;
+use tabwriter::TabWriter;
+
+/// Stack statuses for which drift detection is supported
+const DETECTABLE: &[&str] = &[
+    "CREATE_COMPLETE",
+    "UPDATE_COMPLETE",
+    "UPDATE_ROLLBACK_COMPLETE",
+    "UPDATE_ROLLBACK_FAILED",
+];
+const IN_PROGRESS: &str = "DETECTION_IN_PROGRESS";
+/// Pause between starting detections to stay well under API rate limits
+const PACING: Duration = Duration::from_millis(250);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(32);
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        short = "a",
+        long = "all",
+        help = "Detect drift for every active stack in the region"
+    )]
+    pub all: bool,
+    #[structopt(
+        short = "o",
+        long = "out",
+        help = "Write a consolidated JSON drift report to the provided path",
+        parse(from_os_str)
+    )]
+    pub out: Option<PathBuf>,
+    #[structopt(
+        long = "no-cache",
+        help = "List stacks for --all afresh rather than reuse those listed by a recent run"
+    )]
+    pub no_cache: bool,
+    #[structopt(
+        raw(required_unless = r#""all""#),
+        help = "Names of stacks to detect drift for when not using --all"
+    )]
+    pub stack_names: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    region: String,
+    generated: String,
+    stacks: Vec<StackDrift>,
+}
+
+#[derive(Serialize)]
+struct StackDrift {
+    stack_name: String,
+    detection_status: String,
+    drift_status: Option<String>,
+    reason: Option<String>,
+    drifted_resources: Vec<ResourceDrift>,
+}
+
+impl StackDrift {
+    fn failed(
+        stack_name: String,
+        reason: String,
+    ) -> Self {
+        StackDrift {
+            stack_name,
+            detection_status: "DETECTION_FAILED".into(),
+            drift_status: None,
+            reason: Some(reason),
+            drifted_resources: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ResourceDrift {
+    resource_id: String,
+    resource_type: String,
+    physical_id: Option<String>,
+    drift_status: String,
+    differences: Vec<Difference>,
+}
+
+#[derive(Serialize)]
+struct Difference {
+    path: String,
+    difference_type: String,
+    expected: String,
+    actual: String,
+}
+
+impl From<StackResourceDrift> for ResourceDrift {
+    fn from(drift: StackResourceDrift) -> Self {
+        ResourceDrift {
+            resource_id: drift.logical_resource_id,
+            resource_type: drift.resource_type,
+            physical_id: drift.physical_resource_id,
+            drift_status: drift.stack_resource_drift_status,
+            differences: drift
+                .property_differences
+                .unwrap_or_default()
+                .into_iter()
+                .map(|diff| Difference {
+                    path: diff.property_path,
+                    difference_type: diff.difference_type,
+                    expected: diff.expected_value,
+                    actual: diff.actual_value,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns true when an api call was rejected due to rate limiting
+fn throttled<E>(err: &RusotoError<E>) -> bool {
+    match err {
+        RusotoError::Unknown(response) => {
+            String::from_utf8_lossy(&response.body).contains("Throttling")
+        }
+        _ => false,
+    }
+}
+
+/// Retries an api call with exponential backoff for as long as it is throttled
+fn with_backoff<T, E>(
+    mut call: impl FnMut() -> Result<T, RusotoError<E>>
+) -> Result<T, RusotoError<E>> {
+    let mut delay = Duration::from_millis(500);
+    loop {
+        match call() {
+            Err(ref err) if throttled(err) && delay <= MAX_BACKOFF => {
+                sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn active_stacks(cf: &CloudFormationClient) -> Result<Vec<String>, RusotoError<ListStacksError>> {
+    let mut names = Vec::new();
+    let mut next_token = None;
+    loop {
+        let output = with_backoff(|| {
+            cf.list_stacks(ListStacksInput {
+                next_token: next_token.clone(),
+                stack_status_filter: Some(DETECTABLE.iter().map(|s| s.to_string()).collect()),
+            })
+            .sync()
+        })?;
+        names.extend(
+            output
+                .stack_summaries
+                .unwrap_or_default()
+                .into_iter()
+                .map(|summary| summary.stack_name),
+        );
+        match output.next_token {
+            token @ Some(_) => next_token = token,
+            None => return Ok(names),
+        }
+    }
+}
+
+fn await_detection(
+    cf: &CloudFormationClient,
+    detection_id: &str,
+) -> Result<
+    DescribeStackDriftDetectionStatusOutput,
+    RusotoError<DescribeStackDriftDetectionStatusError>,
+> {
+    loop {
+        let status = with_backoff(|| {
+            cf.describe_stack_drift_detection_status(DescribeStackDriftDetectionStatusInput {
+                stack_drift_detection_id: detection_id.into(),
+            })
+            .sync()
+        })?;
+        if status.detection_status != IN_PROGRESS {
+            return Ok(status);
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+fn drifted_resources(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<Vec<ResourceDrift>, RusotoError<DescribeStackResourceDriftsError>> {
+    let mut drifts = Vec::new();
+    let mut next_token = None;
+    loop {
+        let output = with_backoff(|| {
+            cf.describe_stack_resource_drifts(DescribeStackResourceDriftsInput {
+                stack_name: stack_name.into(),
+                stack_resource_drift_status_filters: Some(vec![
+                    "MODIFIED".into(),
+                    "DELETED".into(),
+                ]),
+                next_token: next_token.clone(),
+                ..DescribeStackResourceDriftsInput::default()
+            })
+            .sync()
+        })?;
+        drifts.extend(
+            output
+                .stack_resource_drifts
+                .into_iter()
+                .map(ResourceDrift::from),
+        );
+        match output.next_token {
+            token @ Some(_) => next_token = token,
+            None => return Ok(drifts),
+        }
+    }
+}
+
+fn stack_drift(
+    cf: &CloudFormationClient,
+    stack_name: String,
+    detection_id: &str,
+) -> Result<StackDrift, Error> {
+    let status = await_detection(cf, detection_id)?;
+    let drifted_resources = match status.stack_drift_status.as_deref() {
+        Some("DRIFTED") => drifted_resources(cf, &stack_name)?,
+        _ => Vec::new(),
+    };
+    Ok(StackDrift {
+        stack_name,
+        detection_status: status.detection_status,
+        drift_status: status.stack_drift_status,
+        reason: status.detection_status_reason,
+        drifted_resources,
+    })
+}
+
+fn summarize(stacks: &[StackDrift]) -> io::Result<()> {
+    let mut writer = TabWriter::new(io::stdout());
+    for stack in stacks {
+        let status = match stack.drift_status.as_deref() {
+            Some("IN_SYNC") => "IN_SYNC".bright_green().to_string(),
+            Some(drifted @ "DRIFTED") => drifted.bold().bright_red().to_string(),
+            Some(other) => other.to_string(),
+            None => stack.detection_status.bright_red().to_string(),
+        };
+        writeln!(
+            &mut writer,
+            "{}\t{}\t{}\t{}",
+            stack.stack_name.bold(),
+            status,
+            stack.drifted_resources.len(),
+            stack.reason.as_deref().unwrap_or_default().bright_black()
+        )?;
+    }
+    writer.flush()
+}
+
+/// Initiates drift detection for each requested stack, waits for every
+/// detection to finish, then reports on the consolidated results
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+) -> Result<(), Error> {
+    let Options {
+        all,
+        out,
+        no_cache,
+        stack_names,
+    } = options;
+    let stack_names = if all {
+        cache::stack_names(None, &Region::default(), "detectable", no_cache, || {
+            active_stacks(&cf)
+        })?
+    } else {
+        stack_names
+    };
+
+    // start every detection up front so they run concurrently on the AWS side
+    let mut pending = Vec::new();
+    let mut locks = Vec::new();
+    for stack_name in stack_names {
+        match Lock::acquire(&stack_name)? {
+            Ok(lock) => locks.push(lock),
+            Err(path) => {
+                eprintln!(
+                    "{} another stack-tail process is detecting drift for {}, holding {}",
+                    "warning:".bold().yellow(),
+                    stack_name,
+                    path.display()
+                );
+                pending.push((
+                    stack_name,
+                    Err("drift detection already in progress by another stack-tail process".into()),
+                ));
+                continue;
+            }
+        }
+        let detection = with_backoff(|| {
+            cf.detect_stack_drift(DetectStackDriftInput {
+                stack_name: stack_name.clone(),
+                ..DetectStackDriftInput::default()
+            })
+            .sync()
+        })
+        .map_err(|err| err.to_string());
+        pending.push((stack_name, detection));
+        sleep(PACING);
+    }
+
+    let mut stacks = Vec::new();
+    for (stack_name, detection) in pending {
+        // one stack failing to report is no reason to lose the report on the rest
+        let drift = detection.and_then(|output| {
+            stack_drift(&cf, stack_name.clone(), &output.stack_drift_detection_id)
+                .map_err(|err| err.to_string())
+        });
+        stacks.push(match drift {
+            Ok(drift) => drift,
+            Err(reason) => StackDrift::failed(stack_name, reason),
+        });
+    }
+
+    summarize(&stacks)?;
+    if let Some(path) = out {
+        serde_json::to_writer_pretty(
+            File::create(path)?,
+            &Report {
+                region: Region::default().name().into(),
+                generated: Utc::now().to_rfc3339(),
+                stacks,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudformation::PropertyDifference;
+
+    #[test]
+    fn resource_drift_converts_property_differences() {
+        let drift = ResourceDrift::from(StackResourceDrift {
+            logical_resource_id: "Bucket".into(),
+            resource_type: "AWS::S3::Bucket".into(),
+            stack_resource_drift_status: "MODIFIED".into(),
+            property_differences: Some(vec![PropertyDifference {
+                property_path: "/VersioningConfiguration/Status".into(),
+                difference_type: "NOT_EQUAL".into(),
+                expected_value: "Enabled".into(),
+                actual_value: "Suspended".into(),
+            }]),
+            ..StackResourceDrift::default()
+        });
+        assert_eq!(drift.resource_id, "Bucket");
+        assert_eq!(drift.differences.len(), 1);
+        assert_eq!(drift.differences[0].actual, "Suspended");
+    }
+
+    #[test]
+    fn only_unknown_errors_are_throttled() {
+        assert!(!throttled::<ListStacksError>(&RusotoError::Validation(
+            "Throttling".into()
+        )))
+    }
+}
@@ -0,0 +1,196 @@
+//! Estimates of the time remaining in an operation, from how long each of a
+//! stack's resources took in previous runs
+use crate::{current_operation, durations, latest, ResourceState, IN_PROGRESS};
+use chrono::{DateTime, Duration, Utc};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Seconds each resource last took, by stack name and logical id
+type Timings = BTreeMap<String, BTreeMap<String, i64>>;
+
+/// Timings of resources recorded in previous runs
+#[derive(Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    timings: Timings,
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("stack-tail").join("durations.json"))
+}
+
+fn write(
+    path: &Path,
+    timings: &Timings,
+) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec(timings)?)
+}
+
+impl History {
+    /// Loads recorded timings. Missing or unreadable timings only cost estimates
+    pub fn load() -> Self {
+        let path = path();
+        let timings = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        History { path, timings }
+    }
+
+    /// Records how long resources of a stack's current operation took
+    pub fn record(
+        &mut self,
+        stack_name: &str,
+        states: &[ResourceState],
+    ) {
+        let timings = self.timings.entry(stack_name.into()).or_default();
+        let mut changed = false;
+        for (resource_id, duration) in durations(current_operation(stack_name, states)) {
+            let seconds = duration.num_seconds();
+            if timings.insert(resource_id.into(), seconds) != Some(seconds) {
+                changed = true;
+            }
+        }
+        if let (true, Some(path)) = (changed, &self.path) {
+            // failing to record only costs a later estimate
+            drop(write(path, &self.timings));
+        }
+    }
+
+    /// Time left until the slowest in progress resource of a stack's current
+    /// operation finishes, according to how long each took before
+    pub fn remaining(
+        &self,
+        stack_name: &str,
+        states: &[ResourceState],
+        now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        let timings = self.timings.get(stack_name)?;
+        let operation = current_operation(stack_name, states);
+        latest(operation.to_vec())
+            .iter()
+            .filter(|state| state.status.ends_with(IN_PROGRESS) && !state.is_stack())
+            .filter_map(|state| {
+                let typical = Duration::seconds(*timings.get(&state.resource_id)?);
+                let started = operation
+                    .iter()
+                    .find(|event| {
+                        event.resource_id == state.resource_id
+                            && event.status.ends_with(IN_PROGRESS)
+                    })?
+                    .timestamp;
+                Some((typical - now.signed_duration_since(started)).max(Duration::zero()))
+            })
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn at(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+        timestamp: &str,
+    ) -> ResourceState {
+        ResourceState {
+            timestamp: DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp"),
+            ..state(resource_type, resource_id, status)
+        }
+    }
+
+    #[test]
+    fn remaining_follows_slowest_resource() {
+        let mut history = History::default();
+        history.record(
+            "stack",
+            &[
+                at(
+                    STACK_RESOURCE,
+                    "stack",
+                    "UPDATE_IN_PROGRESS",
+                    "2019-01-01T00:00:00Z",
+                ),
+                at(
+                    "AWS::RDS::DBInstance",
+                    "db",
+                    "UPDATE_IN_PROGRESS",
+                    "2019-01-01T00:00:00Z",
+                ),
+                at(
+                    "AWS::RDS::DBInstance",
+                    "db",
+                    "UPDATE_COMPLETE",
+                    "2019-01-01T00:10:00Z",
+                ),
+                at(
+                    "AWS::SQS::Queue",
+                    "queue",
+                    "UPDATE_IN_PROGRESS",
+                    "2019-01-01T00:00:00Z",
+                ),
+                at(
+                    "AWS::SQS::Queue",
+                    "queue",
+                    "UPDATE_COMPLETE",
+                    "2019-01-01T00:01:00Z",
+                ),
+            ],
+        );
+        let states = vec![
+            at(
+                STACK_RESOURCE,
+                "stack",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-02T00:00:00Z",
+            ),
+            at(
+                "AWS::RDS::DBInstance",
+                "db",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-02T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-02T00:00:00Z",
+            ),
+        ];
+        let now = DateTime::parse_from_rfc3339("2019-01-02T00:04:00Z")
+            .expect("invalid timestamp")
+            .with_timezone(&Utc);
+        assert_eq!(
+            history.remaining("stack", &states, now),
+            Some(Duration::minutes(6))
+        );
+        assert_eq!(history.remaining("other", &states, now), None);
+    }
+}
@@ -0,0 +1,254 @@
+//! Explanations of stack statuses, their typical causes and how to recover from them
+use crate::Error;
+use colored::Colorize;
+use std::io;
+use structopt::StructOpt;
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(help = "A stack status, for example UPDATE_ROLLBACK_FAILED")]
+    pub status: String,
+}
+
+struct Explanation {
+    status: &'static str,
+    meaning: &'static str,
+    causes: &'static [&'static str],
+    recovery: &'static [&'static str],
+}
+
+const FOLLOW: &str = "stack-tail -f <stack>";
+
+const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        status: "CREATE_IN_PROGRESS",
+        meaning: "The stack is being created.",
+        causes: &[],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "CREATE_COMPLETE",
+        meaning: "The stack and all of its resources were created.",
+        causes: &[],
+        recovery: &[],
+    },
+    Explanation {
+        status: "CREATE_FAILED",
+        meaning: "The stack failed to create and rollback was disabled, leaving the resources created so far in place.",
+        causes: &[
+            "a resource failed to create, see its _FAILED event's reason",
+            "the stack was created with --disable-rollback or --on-failure DO_NOTHING",
+        ],
+        recovery: &[
+            "stack-tail <stack> to find the first failed resource",
+            "aws cloudformation delete-stack --stack-name <stack>, then create it again once fixed",
+        ],
+    },
+    Explanation {
+        status: "ROLLBACK_IN_PROGRESS",
+        meaning: "Creating the stack failed and CloudFormation is deleting the resources it created.",
+        causes: &["a resource failed to create, see the first _FAILED event's reason"],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "ROLLBACK_COMPLETE",
+        meaning: "Creating the stack failed and its resources were removed. The stack can't be updated, only deleted.",
+        causes: &["a resource failed to create, see the first _FAILED event's reason"],
+        recovery: &[
+            "stack-tail <stack> to find the first failed resource",
+            "aws cloudformation delete-stack --stack-name <stack>, then create it again once fixed",
+        ],
+    },
+    Explanation {
+        status: "ROLLBACK_FAILED",
+        meaning: "Creating the stack failed and CloudFormation couldn't remove some of the resources it created.",
+        causes: &[
+            "a resource couldn't be deleted, for example a non-empty S3 bucket",
+            "permissions needed to delete a resource were missing",
+        ],
+        recovery: &[
+            "stack-tail <stack> to find resources which failed to delete",
+            "remove or fix those resources by hand, then aws cloudformation delete-stack --stack-name <stack>",
+        ],
+    },
+    Explanation {
+        status: "DELETE_IN_PROGRESS",
+        meaning: "The stack and its resources are being deleted.",
+        causes: &[],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "DELETE_COMPLETE",
+        meaning: "The stack and its resources were deleted.",
+        causes: &[],
+        recovery: &[],
+    },
+    Explanation {
+        status: "DELETE_FAILED",
+        meaning: "Some of the stack's resources couldn't be deleted.",
+        causes: &[
+            "a resource couldn't be deleted, for example a non-empty S3 bucket or a security group still in use",
+            "another stack imports one of this stack's exports",
+            "permissions needed to delete a resource were missing",
+        ],
+        recovery: &[
+            "stack-tail <stack> to find resources which failed to delete",
+            "aws cloudformation delete-stack --stack-name <stack> --retain-resources <logical ids> to leave them behind",
+        ],
+    },
+    Explanation {
+        status: "UPDATE_IN_PROGRESS",
+        meaning: "The stack is being updated.",
+        causes: &[],
+        recovery: &[FOLLOW, "aws cloudformation cancel-update-stack --stack-name <stack> to abandon the update"],
+    },
+    Explanation {
+        status: "UPDATE_COMPLETE_CLEANUP_IN_PROGRESS",
+        meaning: "The update succeeded and CloudFormation is removing resources which were replaced or removed.",
+        causes: &[],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "UPDATE_COMPLETE",
+        meaning: "The stack was updated.",
+        causes: &[],
+        recovery: &[],
+    },
+    Explanation {
+        status: "UPDATE_FAILED",
+        meaning: "The update failed and rollback was disabled, leaving resources as they were when the update failed.",
+        causes: &[
+            "a resource failed to update, see its _FAILED event's reason",
+            "the update was made with --disable-rollback",
+        ],
+        recovery: &[
+            "stack-tail <stack> to find the first failed resource",
+            "aws cloudformation rollback-stack --stack-name <stack>, or update again once fixed",
+        ],
+    },
+    Explanation {
+        status: "UPDATE_ROLLBACK_IN_PROGRESS",
+        meaning: "The update failed or was cancelled and CloudFormation is returning resources to their previous state.",
+        causes: &[
+            "a resource failed to update, see the first _FAILED event's reason",
+            "the update was cancelled",
+            "a rollback trigger alarm fired",
+        ],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "UPDATE_ROLLBACK_COMPLETE_CLEANUP_IN_PROGRESS",
+        meaning: "Resources were returned to their previous state and CloudFormation is removing resources created during the failed update.",
+        causes: &[],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "UPDATE_ROLLBACK_COMPLETE",
+        meaning: "The update failed and resources were returned to their previous state. The stack can be updated again.",
+        causes: &["a resource failed to update, see the first _FAILED event's reason"],
+        recovery: &["stack-tail <stack> to find the first failed resource, then update again once fixed"],
+    },
+    Explanation {
+        status: "UPDATE_ROLLBACK_FAILED",
+        meaning: "The update failed and CloudFormation couldn't return some resources to their previous state. The stack can't be updated until the rollback is continued.",
+        causes: &[
+            "a resource was changed or deleted outside of CloudFormation",
+            "permissions needed to restore a resource were missing",
+            "a service limit was reached while restoring a resource",
+        ],
+        recovery: &[
+            "stack-tail <stack> to find resources which failed to roll back",
+            "stack-tail drift <stack> to find resources changed outside of CloudFormation",
+            "fix those resources, then aws cloudformation continue-update-rollback --stack-name <stack>",
+            "or skip them with aws cloudformation continue-update-rollback --stack-name <stack> --resources-to-skip <logical ids>",
+        ],
+    },
+    Explanation {
+        status: "REVIEW_IN_PROGRESS",
+        meaning: "The stack was created by a change set which hasn't been executed yet. It has no resources.",
+        causes: &[],
+        recovery: &["aws cloudformation execute-change-set --stack-name <stack> --change-set-name <change set>"],
+    },
+    Explanation {
+        status: "IMPORT_IN_PROGRESS",
+        meaning: "Existing resources are being imported into the stack.",
+        causes: &[],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "IMPORT_COMPLETE",
+        meaning: "Existing resources were imported into the stack.",
+        causes: &[],
+        recovery: &[],
+    },
+    Explanation {
+        status: "IMPORT_ROLLBACK_IN_PROGRESS",
+        meaning: "Importing resources failed and CloudFormation is returning the stack to its previous template.",
+        causes: &["an imported resource's properties don't match the template"],
+        recovery: &[FOLLOW],
+    },
+    Explanation {
+        status: "IMPORT_ROLLBACK_COMPLETE",
+        meaning: "Importing resources failed and the stack was returned to its previous template.",
+        causes: &["an imported resource's properties don't match the template"],
+        recovery: &["stack-tail <stack> to find the failed import, then import again once fixed"],
+    },
+    Explanation {
+        status: "IMPORT_ROLLBACK_FAILED",
+        meaning: "Importing resources failed and CloudFormation couldn't return the stack to its previous template.",
+        causes: &["an imported resource was changed outside of CloudFormation"],
+        recovery: &[
+            "stack-tail <stack> to find resources which failed to roll back",
+            "fix those resources, then aws cloudformation continue-update-rollback --stack-name <stack>",
+        ],
+    },
+];
+
+fn explanation(status: &str) -> Option<&'static Explanation> {
+    let status = status.to_uppercase();
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.status == status)
+}
+
+/// Prints what a stack status means, its typical causes and how to recover
+pub fn run(options: Options) -> Result<(), Error> {
+    let explanation = explanation(&options.status).ok_or_else(|| {
+        Error::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown stack status {}", options.status),
+        ))
+    })?;
+    println!("{}", explanation.status.bold());
+    println!();
+    println!("{}", explanation.meaning);
+    if !explanation.causes.is_empty() {
+        println!();
+        println!("{}", "typical causes".bold());
+        for cause in explanation.causes {
+            println!("  • {}", cause);
+        }
+    }
+    if !explanation.recovery.is_empty() {
+        println!();
+        println!("{}", "recovery".bold());
+        for step in explanation.recovery {
+            println!("  → {}", step);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explanation_is_case_insensitive() {
+        assert_eq!(
+            explanation("update_rollback_failed").map(|explanation| explanation.status),
+            Some("UPDATE_ROLLBACK_FAILED")
+        );
+        assert!(explanation("SIDEWAYS_IN_PROGRESS").is_none());
+    }
+}
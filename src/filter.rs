@@ -0,0 +1,325 @@
+//! Narrowing which resource states are displayed
+use crate::{ResourceState, FAILED};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer};
+use std::str::FromStr;
+
+/// A pattern matching strings. Either a glob matching whole strings, where `*`
+/// matches any run of characters and `?` any single character, or a regex
+/// written between slashes, matching anywhere within a string unless anchored
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl PartialEq for Pattern {
+    fn eq(
+        &self,
+        other: &Pattern,
+    ) -> bool {
+        match (self, other) {
+            (Pattern::Glob(a), Pattern::Glob(b)) => a == b,
+            (Pattern::Regex(a), Pattern::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('/').and_then(|s| s.strip_suffix('/')) {
+            Some(regex) => Regex::new(regex)
+                .map(Pattern::Regex)
+                .map_err(|err| err.to_string()),
+            None => Ok(Pattern::Glob(s.into())),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Matches text against glob wildcards
+fn glob(
+    pattern: &[char],
+    text: &[char],
+) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| glob(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && glob(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+impl Pattern {
+    pub fn matches(
+        &self,
+        s: &str,
+    ) -> bool {
+        match self {
+            Pattern::Glob(pattern) => glob(
+                &pattern.chars().collect::<Vec<_>>(),
+                &s.chars().collect::<Vec<_>>(),
+            ),
+            Pattern::Regex(regex) => regex.is_match(s),
+        }
+    }
+}
+
+/// A point in time, either an RFC 3339 timestamp or a time ago like `30m`,
+/// in seconds, minutes, hours, days or weeks
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Moment(pub DateTime<FixedOffset>);
+
+impl FromStr for Moment {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Moment(timestamp));
+        }
+        let invalid = || {
+            format!(
+                "invalid time {}, expected an RFC 3339 timestamp or a time ago like 30m",
+                s
+            )
+        };
+        let (amount, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?);
+        let amount = amount.parse::<i64>().map_err(|_| invalid())?;
+        let ago = match unit {
+            "s" => Duration::seconds(amount),
+            "m" => Duration::minutes(amount),
+            "h" => Duration::hours(amount),
+            "d" => Duration::days(amount),
+            "w" => Duration::weeks(amount),
+            _ => return Err(invalid()),
+        };
+        Ok(Moment(
+            (Utc::now() - ago).with_timezone(&FixedOffset::east(0)),
+        ))
+    }
+}
+
+/// Criteria states must meet to be displayed
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub status: Option<Pattern>,
+    /// Resource types, any of which may match
+    pub types: Vec<Pattern>,
+    /// Logical resource ids, any of which may match
+    pub resources: Vec<Pattern>,
+    /// Resource types, none of which may match
+    pub exclude_types: Vec<Pattern>,
+    /// Logical resource ids, none of which may match
+    pub exclude_resources: Vec<Pattern>,
+    /// Only failed states and the stack's own final statuses
+    pub failed_only: bool,
+    /// Earliest time of displayed states
+    pub since: Option<Moment>,
+    /// Latest time of displayed states
+    pub until: Option<Moment>,
+}
+
+/// Returns true when no patterns are given or any of them match
+fn any(
+    patterns: &[Pattern],
+    s: &str,
+) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| pattern.matches(s))
+}
+
+/// Returns true when none of the patterns match
+fn none(
+    patterns: &[Pattern],
+    s: &str,
+) -> bool {
+    !patterns.iter().any(|pattern| pattern.matches(s))
+}
+
+impl Filter {
+    pub fn matches(
+        &self,
+        state: &ResourceState,
+    ) -> bool {
+        self.status
+            .iter()
+            .all(|pattern| pattern.matches(&state.status))
+            && any(&self.types, &state.resource_type)
+            && any(&self.resources, &state.resource_id)
+            && none(&self.exclude_types, &state.resource_type)
+            && none(&self.exclude_resources, &state.resource_id)
+            && (!self.failed_only
+                || state.status.ends_with(FAILED)
+                || (state.is_stack() && state.complete_or_failed()))
+            && self
+                .since
+                .iter()
+                .all(|Moment(since)| state.timestamp >= *since)
+            && self
+                .until
+                .iter()
+                .all(|Moment(until)| state.timestamp <= *until)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn glob_patterns_match_whole_strings() -> Result<(), String> {
+        let pattern = "*_FAILED".parse::<Pattern>()?;
+        assert!(pattern.matches("UPDATE_FAILED"));
+        assert!(!pattern.matches("UPDATE_FAILED_AGAIN"));
+        assert!("UPDATE_?????".parse::<Pattern>()?.matches("UPDATE_AGAIN"));
+        Ok(())
+    }
+
+    #[test]
+    fn slashed_patterns_are_regexes() -> Result<(), String> {
+        let pattern = "/^(CREATE|UPDATE)_/".parse::<Pattern>()?;
+        assert!(pattern.matches("UPDATE_COMPLETE"));
+        assert!(!pattern.matches("DELETE_COMPLETE"));
+        assert!("/FAILED/".parse::<Pattern>()?.matches("UPDATE_FAILED"));
+        assert!("/(/".parse::<Pattern>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn moments_parse_timestamps_and_times_ago() -> Result<(), String> {
+        assert_eq!(
+            "2019-01-01T00:00:00Z".parse::<Moment>()?.0.to_rfc3339(),
+            "2019-01-01T00:00:00+00:00"
+        );
+        let Moment(ago) = "30m".parse()?;
+        assert_eq!((Utc::now() - ago.with_timezone(&Utc)).num_minutes(), 30);
+        assert!("30".parse::<Moment>().is_err());
+        assert!("m".parse::<Moment>().is_err());
+        assert!("30y".parse::<Moment>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn filter_matches_status() -> Result<(), String> {
+        let filter = Filter {
+            status: Some("*_FAILED".parse()?),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&state("AWS::SQS::Queue", "queue", "CREATE_FAILED")));
+        assert!(!filter.matches(&state("AWS::SQS::Queue", "queue", "CREATE_COMPLETE")));
+        assert!(Filter::default().matches(&state("AWS::SQS::Queue", "queue", "CREATE_COMPLETE")));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_matches_any_type() -> Result<(), String> {
+        let filter = Filter {
+            types: vec!["AWS::Lambda::Function".parse()?, "AWS::IAM::*".parse()?],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&state("AWS::IAM::Role", "role", "CREATE_COMPLETE")));
+        assert!(filter.matches(&state("AWS::Lambda::Function", "fn", "CREATE_COMPLETE")));
+        assert!(!filter.matches(&state("AWS::SQS::Queue", "queue", "CREATE_COMPLETE")));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_matches_resource_ids() -> Result<(), String> {
+        let filter = Filter {
+            resources: vec!["Api*".parse()?],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&state(
+            "AWS::ApiGateway::RestApi",
+            "ApiGateway",
+            "CREATE_COMPLETE"
+        )));
+        assert!(!filter.matches(&state("AWS::SQS::Queue", "Queue", "CREATE_COMPLETE")));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_excludes_types_and_resources() -> Result<(), String> {
+        let filter = Filter {
+            exclude_types: vec!["AWS::CloudFormation::WaitConditionHandle".parse()?],
+            exclude_resources: vec!["*LogGroup".parse()?],
+            ..Filter::default()
+        };
+        assert!(!filter.matches(&state(
+            "AWS::CloudFormation::WaitConditionHandle",
+            "Handle",
+            "CREATE_COMPLETE"
+        )));
+        assert!(!filter.matches(&state(
+            "AWS::Logs::LogGroup",
+            "ApiLogGroup",
+            "CREATE_COMPLETE"
+        )));
+        assert!(filter.matches(&state("AWS::SQS::Queue", "Queue", "CREATE_COMPLETE")));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_matches_failures_and_final_stack_statuses() {
+        let filter = Filter {
+            failed_only: true,
+            ..Filter::default()
+        };
+        assert!(filter.matches(&state("AWS::SQS::Queue", "queue", "UPDATE_FAILED")));
+        assert!(!filter.matches(&state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE")));
+        assert!(filter.matches(&state(
+            crate::STACK_RESOURCE,
+            "stack",
+            "UPDATE_ROLLBACK_COMPLETE"
+        )));
+        assert!(!filter.matches(&state(
+            crate::STACK_RESOURCE,
+            "stack",
+            "UPDATE_ROLLBACK_IN_PROGRESS"
+        )));
+    }
+
+    #[test]
+    fn filter_matches_time_window() -> Result<(), String> {
+        let filter = Filter {
+            since: Some("1996-12-19T00:00:00Z".parse()?),
+            until: Some("1996-12-21T00:00:00Z".parse()?),
+            ..Filter::default()
+        };
+        assert!(filter.matches(&state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE")));
+        let filter = Filter {
+            since: Some("1996-12-21T00:00:00Z".parse()?),
+            ..Filter::default()
+        };
+        assert!(!filter.matches(&state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE")));
+        Ok(())
+    }
+}
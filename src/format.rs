@@ -0,0 +1,176 @@
+//! User defined formats for resource state lines
+use crate::{Clock, ResourceState};
+use std::{fmt, str::FromStr};
+
+/// Resource state fields available as `{placeholder}`s
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Field {
+    Time,
+    Id,
+    PhysicalId,
+    Type,
+    Status,
+    Reason,
+}
+
+impl FromStr for Field {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "time" => Ok(Field::Time),
+            "id" => Ok(Field::Id),
+            "physical_id" => Ok(Field::PhysicalId),
+            "type" => Ok(Field::Type),
+            "status" => Ok(Field::Status),
+            "reason" => Ok(Field::Reason),
+            other => Err(format!(
+                "unknown placeholder {{{}}}, expected one of {{time}}, {{id}}, {{physical_id}}, {{type}}, {{status}} or {{reason}}",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// A git log style line format, for example `{time} {id} {status} {reason}`.
+/// Literal braces are written as `{{` and `}}`
+#[derive(PartialEq, Debug, Clone)]
+pub struct LineFormat(Vec<Segment>);
+
+impl FromStr for LineFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed placeholder {{{}", name)),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(literal.split_off(0)));
+                    }
+                    segments.push(Segment::Field(name.parse()?));
+                }
+                '}' => return Err("unmatched }, use }} for a literal brace".into()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(LineFormat(segments))
+    }
+}
+
+/// Provides a means of displaying resource state in a user defined format
+pub struct Custom<'a>(pub &'a LineFormat, pub &'a ResourceState, pub &'a Clock);
+
+impl fmt::Display for Custom<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Custom(LineFormat(segments), state, clock) = self;
+        for segment in segments {
+            match segment {
+                Segment::Literal(literal) => write!(f, "{}", literal)?,
+                Segment::Field(Field::Time) => write!(f, "{}", state.timestamp(clock))?,
+                Segment::Field(Field::Id) => write!(f, "{}", state.resource_id)?,
+                Segment::Field(Field::PhysicalId) => {
+                    write!(f, "{}", state.physical_id.as_deref().unwrap_or_default())?
+                }
+                Segment::Field(Field::Type) => write!(f, "{}", state.resource_type)?,
+                Segment::Field(Field::Status) => write!(f, "{}", state.status)?,
+                Segment::Field(Field::Reason) => write!(f, "{}", state.reason)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn line_format_renders_placeholders() -> Result<(), String> {
+        let format = "{id}: {status} ({reason}) {{{type}}}".parse::<LineFormat>()?;
+        assert_eq!(
+            Custom(
+                &format,
+                &ResourceState {
+                    reason: "Resource creation Initiated".into(),
+                    ..state("AWS::S3::Bucket", "Bucket", "CREATE_IN_PROGRESS")
+                },
+                &Clock::default()
+            )
+            .to_string(),
+            "Bucket: CREATE_IN_PROGRESS (Resource creation Initiated) {AWS::S3::Bucket}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn line_format_renders_physical_ids() -> Result<(), String> {
+        let format = "{id} {physical_id}".parse::<LineFormat>()?;
+        assert_eq!(
+            Custom(
+                &format,
+                &ResourceState {
+                    physical_id: Some("my-stack-bucket-1a2b3c".into()),
+                    ..state("AWS::S3::Bucket", "Bucket", "CREATE_COMPLETE")
+                },
+                &Clock::default()
+            )
+            .to_string(),
+            "Bucket my-stack-bucket-1a2b3c"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn line_format_rejects_invalid_placeholders() {
+        assert!("{nope}".parse::<LineFormat>().is_err());
+        assert!("{id".parse::<LineFormat>().is_err());
+        assert!("id}".parse::<LineFormat>().is_err());
+    }
+}
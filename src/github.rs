@@ -0,0 +1,159 @@
+//! GitHub Actions workflow annotations and job summaries
+use crate::{is_failure, latest, ResourceState, COMPLETE};
+use std::{
+    env, fmt,
+    fs::OpenOptions,
+    io::{self, Write},
+};
+
+/// Returns true when running within a GitHub Actions workflow
+fn enabled() -> bool {
+    env::var("GITHUB_ACTIONS")
+        .map(|value| value == "true")
+        .unwrap_or_default()
+}
+
+/// Escapes workflow command messages
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes workflow command properties
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// An error workflow command for failed resources or a warning
+/// workflow command for rolled back resources
+fn annotation(state: &ResourceState) -> Option<String> {
+    let level = match &state.status[..] {
+        failed if is_failure(failed) => "error",
+        rollback if rollback.contains("ROLLBACK") => "warning",
+        _ => return None,
+    };
+    Some(format!(
+        "::{} title={}::{}",
+        level,
+        escape_property(&format!("{} {}", state.resource_id, state.status)),
+        escape_data(&state.reason)
+    ))
+}
+
+fn escape_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Markdown table of resource states for a job summary
+struct Summary<'a>(&'a str, &'a [ResourceState]);
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Summary(stack_name, states) = self;
+        writeln!(f, "### {}", stack_name)?;
+        writeln!(f)?;
+        writeln!(f, "| | Resource | Type | Status | Reason |")?;
+        writeln!(f, "| --- | --- | --- | --- | --- |")?;
+        for state in states.iter() {
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {} |",
+                match &state.status[..] {
+                    failed if is_failure(failed) => "❌",
+                    complete if complete.ends_with(COMPLETE) => "✅",
+                    _ => "🔄",
+                },
+                escape_cell(&state.resource_id),
+                escape_cell(&state.resource_type),
+                escape_cell(&state.status),
+                escape_cell(&state.reason)
+            )?;
+        }
+        writeln!(f)
+    }
+}
+
+/// When running within GitHub Actions, annotates the workflow run with failed
+/// resources and appends a summary table of resource states to the job summary
+pub fn report(
+    stack_name: &str,
+    states: &[ResourceState],
+) -> io::Result<()> {
+    if !enabled() {
+        return Ok(());
+    }
+    for annotation in states.iter().filter_map(annotation) {
+        println!("{}", annotation);
+    }
+    if let Some(path) = env::var_os("GITHUB_STEP_SUMMARY") {
+        let mut summary = OpenOptions::new().create(true).append(true).open(path)?;
+        write!(summary, "{}", Summary(stack_name, &latest(states.to_vec())))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn annotation_reports_failures_as_errors() {
+        assert_eq!(
+            annotation(&ResourceState {
+                reason: "Bucket already exists\nin us-east-1: 100%".into(),
+                ..state("AWS::S3::Bucket", "Bucket", "CREATE_FAILED")
+            }),
+            Some(
+                "::error title=Bucket CREATE_FAILED::Bucket already exists%0Ain us-east-1: 100%25"
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn annotation_reports_rollbacks_as_warnings() {
+        assert_eq!(
+            annotation(&state(
+                crate::STACK_RESOURCE,
+                "stack",
+                "UPDATE_ROLLBACK_COMPLETE"
+            )),
+            Some("::warning title=stack UPDATE_ROLLBACK_COMPLETE::".into())
+        );
+        assert_eq!(
+            annotation(&state("AWS::S3::Bucket", "Bucket", "CREATE_COMPLETE")),
+            None
+        );
+    }
+
+    #[test]
+    fn summary_renders_markdown_table() {
+        let states = vec![state("AWS::S3::Bucket", "Bucket", "CREATE_COMPLETE")];
+        assert_eq!(
+            Summary("stack", &states).to_string(),
+            "### stack\n\n| | Resource | Type | Status | Reason |\n| --- | --- | --- | --- | --- |\n| ✅ | Bucket | AWS::S3::Bucket | CREATE_COMPLETE |  |\n\n"
+        );
+    }
+}
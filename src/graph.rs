@@ -0,0 +1,407 @@
+//! Resource dependency graphs derived from stack templates
+use crate::{template, Error, ResourceState, COMPLETE, FAILED};
+use rusoto_cloudformation::{CloudFormation, CloudFormationClient, DescribeStackResourcesInput};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+use structopt::StructOpt;
+
+#[derive(PartialEq, Debug)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "mermaid" => Ok(Format::Mermaid),
+            other => Err(format!("unsupported graph format {}", other)),
+        }
+    }
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        short = "f",
+        long = "format",
+        default_value = "dot",
+        raw(possible_values = r#"&["dot", "mermaid"]"#),
+        help = "Graph output format"
+    )]
+    pub format: Format,
+    pub stack_name: String,
+}
+
+/// A stack's resources and the dependencies between them
+#[derive(Default, Debug)]
+struct Graph {
+    /// logical id to resource type
+    nodes: BTreeMap<String, String>,
+    /// logical id to current resource status
+    statuses: BTreeMap<String, String>,
+    /// dependent to dependency pairs
+    edges: BTreeSet<(String, String)>,
+}
+
+impl Graph {
+    /// Builds a graph from a parsed template. YAML short form intrinsic functions
+    /// (`!Ref`, `!GetAtt`, `!Sub`) lose their tags when parsed, so for YAML templates
+    /// bare strings naming another resource are also treated as references
+    fn from_template(
+        template: &Value,
+        yaml: bool,
+    ) -> Self {
+        let mut graph = Graph::default();
+        let resources = match template.get("Resources").and_then(Value::as_object) {
+            Some(resources) => resources,
+            None => return graph,
+        };
+        for (id, resource) in resources {
+            graph.nodes.insert(
+                id.clone(),
+                resource
+                    .get("Type")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .into(),
+            );
+        }
+        for (id, resource) in resources {
+            let mut dependencies = BTreeSet::new();
+            match resource.get("DependsOn") {
+                Some(Value::String(dependency)) => {
+                    dependencies.insert(dependency.clone());
+                }
+                Some(Value::Array(values)) => {
+                    dependencies.extend(values.iter().filter_map(Value::as_str).map(String::from))
+                }
+                _ => (),
+            }
+            if let Some(properties) = resource.get("Properties") {
+                references(properties, &graph.nodes, yaml, &mut dependencies);
+            }
+            for dependency in dependencies {
+                if dependency != *id && graph.nodes.contains_key(&dependency) {
+                    graph.edges.insert((id.clone(), dependency));
+                }
+            }
+        }
+        graph
+    }
+
+    fn with_statuses(
+        mut self,
+        states: Vec<ResourceState>,
+    ) -> Self {
+        for state in states {
+            self.statuses.insert(state.resource_id, state.status);
+        }
+        self
+    }
+}
+
+/// Collects the names of resources referenced within a template value
+fn references(
+    value: &Value,
+    resources: &BTreeMap<String, String>,
+    yaml: bool,
+    found: &mut BTreeSet<String>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("Ref", Value::String(name)) => {
+                        found.insert(name.clone());
+                    }
+                    ("Fn::GetAtt", Value::Array(parts)) => {
+                        found.extend(parts.first().and_then(Value::as_str).map(String::from))
+                    }
+                    ("Fn::GetAtt", Value::String(attr)) => {
+                        found.extend(attr.split('.').next().map(String::from))
+                    }
+                    ("Fn::Sub", Value::String(template)) => substitutions(template, found),
+                    ("Fn::Sub", Value::Array(parts)) => {
+                        if let Some(template) = parts.first().and_then(Value::as_str) {
+                            substitutions(template, found)
+                        }
+                        for part in parts.iter().skip(1) {
+                            references(part, resources, yaml, found)
+                        }
+                    }
+                    _ => references(value, resources, yaml, found),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                references(value, resources, yaml, found)
+            }
+        }
+        Value::String(s) if yaml => {
+            let name = s.split('.').next().unwrap_or_default();
+            if resources.contains_key(name) {
+                found.insert(name.into());
+            }
+            substitutions(s, found)
+        }
+        _ => (),
+    }
+}
+
+/// Collects the names referenced by `${Name}` or `${Name.Attr}` placeholders
+fn substitutions(
+    template: &str,
+    found: &mut BTreeSet<String>,
+) {
+    for placeholder in template.split("${").skip(1) {
+        if let Some(end) = placeholder.find('}') {
+            let name = placeholder[..end].split('.').next().unwrap_or_default();
+            if !name.starts_with('!') && !name.starts_with("AWS::") {
+                found.insert(name.into());
+            }
+        }
+    }
+}
+
+/// Node classes and their fill colors
+const CLASSES: &[(&str, &str)] = &[
+    ("deleted", "#d3d3d3"),
+    ("complete", "#98fb98"),
+    ("failed", "#fa8072"),
+    ("progress", "#f0e68c"),
+    ("absent", "#ffffff"),
+];
+
+fn class(status: Option<&str>) -> &'static str {
+    match status {
+        Some(deleted) if deleted.starts_with("DELETE") && deleted.ends_with(COMPLETE) => "deleted",
+        Some(complete) if complete.ends_with(COMPLETE) => "complete",
+        Some(failed) if failed.ends_with(FAILED) => "failed",
+        Some(_) => "progress",
+        None => "absent",
+    }
+}
+
+fn fill(status: Option<&str>) -> &'static str {
+    let class = class(status);
+    CLASSES
+        .iter()
+        .find(|(name, _)| *name == class)
+        .map(|(_, color)| *color)
+        .unwrap_or_default()
+}
+
+fn escaped(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn quoted(s: &str) -> String {
+    format!("\"{}\"", escaped(s))
+}
+
+/// Graphviz DOT rendering of a graph
+struct Dot<'a>(&'a str, &'a Graph);
+
+impl fmt::Display for Dot<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Dot(stack_name, graph) = self;
+        writeln!(f, "digraph {} {{", quoted(stack_name))?;
+        writeln!(f, "  rankdir=LR;")?;
+        writeln!(
+            f,
+            "  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];"
+        )?;
+        for (id, resource_type) in &graph.nodes {
+            let status = graph.statuses.get(id).map(String::as_str);
+            writeln!(
+                f,
+                "  {} [label=\"{}\\n{}\\n{}\", fillcolor={}];",
+                quoted(id),
+                escaped(id),
+                escaped(resource_type),
+                status.unwrap_or("NOT_CREATED"),
+                quoted(fill(status))
+            )?;
+        }
+        for (dependent, dependency) in &graph.edges {
+            writeln!(f, "  {} -> {};", quoted(dependent), quoted(dependency))?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Mermaid flowchart rendering of a graph, suitable for embedding in Markdown
+struct Mermaid<'a>(&'a Graph);
+
+impl fmt::Display for Mermaid<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Mermaid(graph) = self;
+        writeln!(f, "graph LR")?;
+        for (id, resource_type) in &graph.nodes {
+            let status = graph.statuses.get(id).map(String::as_str);
+            writeln!(
+                f,
+                "  {}[\"{}<br/>{}<br/>{}\"]:::{}",
+                id,
+                id,
+                resource_type,
+                status.unwrap_or("NOT_CREATED"),
+                class(status)
+            )?;
+        }
+        for (dependent, dependency) in &graph.edges {
+            writeln!(f, "  {} --> {}", dependent, dependency)?;
+        }
+        for (name, color) in CLASSES {
+            writeln!(f, "  classDef {} fill:{},color:#000", name, color)?;
+        }
+        Ok(())
+    }
+}
+
+fn graph(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<Graph, Error> {
+    let (template, yaml) = template::fetch(cf, stack_name)?;
+    let graph = Graph::from_template(&template, yaml);
+    let states = cf
+        .describe_stack_resources(DescribeStackResourcesInput {
+            stack_name: Some(stack_name.into()),
+            ..DescribeStackResourcesInput::default()
+        })
+        .sync()?
+        .stack_resources
+        .unwrap_or_default()
+        .into_iter()
+        .map(ResourceState::from)
+        .collect();
+    Ok(graph.with_statuses(states))
+}
+
+/// Prints a stack's resource dependency graph, colored by current resource status
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+) -> Result<(), Error> {
+    let Options { format, stack_name } = options;
+    let graph = graph(&cf, &stack_name)?;
+    match format {
+        Format::Dot => print!("{}", Dot(&stack_name, &graph)),
+        Format::Mermaid => print!("{}", Mermaid(&graph)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = r#"{
+        "Resources": {
+            "Bucket": { "Type": "AWS::S3::Bucket" },
+            "Role": { "Type": "AWS::IAM::Role" },
+            "Function": {
+                "Type": "AWS::Lambda::Function",
+                "DependsOn": "Bucket",
+                "Properties": {
+                    "Role": { "Fn::GetAtt": ["Role", "Arn"] },
+                    "Environment": { "Variables": { "NAME": { "Fn::Sub": "${Bucket}-${AWS::Region}" } } }
+                }
+            },
+            "Permission": {
+                "Type": "AWS::Lambda::Permission",
+                "Properties": { "FunctionName": { "Ref": "Function" }, "Principal": "Bucket" }
+            }
+        }
+    }"#;
+
+    const YAML: &str = r#"
+Resources:
+  Bucket:
+    Type: AWS::S3::Bucket
+  Function:
+    Type: AWS::Lambda::Function
+    Properties:
+      Environment:
+        Variables:
+          BUCKET: !GetAtt Bucket.Arn
+"#;
+
+    fn edges(graph: &Graph) -> Vec<(&str, &str)> {
+        graph
+            .edges
+            .iter()
+            .map(|(a, b)| (a.as_str(), b.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn graph_follows_json_template_references() -> Result<(), serde_json::Error> {
+        let graph = Graph::from_template(&serde_json::from_str(JSON)?, false);
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(
+            edges(&graph),
+            vec![
+                ("Function", "Bucket"),
+                ("Function", "Role"),
+                ("Permission", "Function"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn graph_follows_yaml_short_form_references() -> Result<(), serde_yaml::Error> {
+        let graph = Graph::from_template(&serde_yaml::from_str(YAML)?, true);
+        assert_eq!(edges(&graph), vec![("Function", "Bucket")]);
+        Ok(())
+    }
+
+    #[test]
+    fn dot_colors_nodes_by_status() -> Result<(), serde_json::Error> {
+        let mut graph = Graph::from_template(&serde_json::from_str(JSON)?, false);
+        graph
+            .statuses
+            .insert("Function".into(), "CREATE_FAILED".into());
+        let dot = Dot("stack", &graph).to_string();
+        assert!(dot.starts_with("digraph \"stack\" {"));
+        assert!(dot.contains(
+            "\"Function\" [label=\"Function\\nAWS::Lambda::Function\\nCREATE_FAILED\", fillcolor=\"#fa8072\"];"
+        ));
+        assert!(dot.contains(
+            "\"Bucket\" [label=\"Bucket\\nAWS::S3::Bucket\\nNOT_CREATED\", fillcolor=\"#ffffff\"];"
+        ));
+        assert!(dot.contains("\"Permission\" -> \"Function\";"));
+        Ok(())
+    }
+
+    #[test]
+    fn mermaid_classifies_nodes_by_status() -> Result<(), serde_json::Error> {
+        let mut graph = Graph::from_template(&serde_json::from_str(JSON)?, false);
+        graph
+            .statuses
+            .insert("Bucket".into(), "CREATE_COMPLETE".into());
+        let mermaid = Mermaid(&graph).to_string();
+        assert!(mermaid.starts_with("graph LR\n"));
+        assert!(mermaid
+            .contains("  Bucket[\"Bucket<br/>AWS::S3::Bucket<br/>CREATE_COMPLETE\"]:::complete\n"));
+        assert!(mermaid.contains("  Function --> Role\n"));
+        assert!(mermaid.contains("  classDef failed fill:#fa8072,color:#000\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+//! Ongoing AWS service issues affecting failed resources, from the AWS Health api
+use crate::{credentials, theme::Theme, Error, ResourceState, FAILED};
+use chrono::{TimeZone, Utc};
+use colored::Colorize;
+use futures::Future;
+use rusoto_core::{
+    credential::ProvideAwsCredentials, request::HttpClient, signature::SignedRequest,
+    DispatchSignedRequest, Region,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::BTreeSet, fmt};
+
+/// A page of DescribeEvents results
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    #[serde(default)]
+    events: Vec<Issue>,
+}
+
+/// An open AWS service issue
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Issue {
+    service: String,
+    event_type_code: String,
+    region: Option<String>,
+    /// Seconds since the epoch
+    start_time: Option<f64>,
+}
+
+/// A notice of an open issue, colored by a theme
+pub struct Notice<'a>(pub &'a Issue, pub Theme);
+
+impl fmt::Display for Notice<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Notice(issue, theme) = self;
+        write!(
+            f,
+            "{} ongoing {} issue {}",
+            "AWS Health:".bold().color(theme.warning),
+            issue.service,
+            theme.muted(&issue.event_type_code)
+        )?;
+        if let Some(region) = &issue.region {
+            write!(f, " in {}", region)?;
+        }
+        if let Some(start) = issue.start_time {
+            write!(f, " since {}", Utc.timestamp(start as i64, 0).to_rfc3339())?;
+        }
+        Ok(())
+    }
+}
+
+/// The AWS Health service code for a resource type, for example LAMBDA for `AWS::Lambda::Function`
+fn service(resource_type: &str) -> Option<String> {
+    let namespace = resource_type.strip_prefix("AWS::")?.split("::").next()?;
+    Some(namespace.trim_end_matches("V2").to_uppercase())
+}
+
+/// Services of failed resources
+pub fn failing_services(states: &[ResourceState]) -> BTreeSet<String> {
+    states
+        .iter()
+        .filter(|state| state.status.ends_with(FAILED) && !state.is_stack())
+        .filter_map(|state| service(&state.resource_type))
+        .collect()
+}
+
+/// Lists open issues affecting the given services in the current region
+///
+/// Rusoto doesn't provide an AWS Health client so requests are signed and dispatched directly.
+/// The AWS Health api requires a Business or Enterprise support plan
+pub fn issues(services: &BTreeSet<String>) -> Result<Vec<Issue>, Error> {
+    // AWS Health is a global service served from us-east-1
+    let endpoint = Region::UsEast1;
+    let credentials = credentials().credentials().wait()?;
+    let dispatcher = HttpClient::new().expect("failed to create request dispatcher");
+    let mut request = SignedRequest::new("POST", "health", &endpoint, "/");
+    request.set_content_type("application/x-amz-json-1.1".into());
+    request.add_header("x-amz-target", "AWSHealth_20160804.DescribeEvents");
+    request.set_payload(Some(
+        json!({
+            "filter": {
+                "services": services,
+                "regions": [Region::default().name()],
+                "eventStatusCodes": ["open"],
+                "eventTypeCategories": ["issue"]
+            }
+        })
+        .to_string()
+        .into_bytes(),
+    ));
+    request.sign_with_plus(&credentials, true);
+    let response = dispatcher.dispatch(request, None).wait()?.buffer().wait()?;
+    if !response.status.is_success() {
+        return Err(Error::Health(
+            String::from_utf8_lossy(&response.body).into(),
+        ));
+    }
+    let page: Page = serde_json::from_slice(&response.body)?;
+    Ok(page.events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn service_derives_from_resource_type_namespace() {
+        assert_eq!(service("AWS::Lambda::Function"), Some("LAMBDA".into()));
+        assert_eq!(
+            service("AWS::ElasticLoadBalancingV2::LoadBalancer"),
+            Some("ELASTICLOADBALANCING".into())
+        );
+        assert_eq!(service("Custom::Resource"), None);
+    }
+
+    #[test]
+    fn failing_services_exclude_stacks() {
+        let states = vec![
+            state(crate::STACK_RESOURCE, "stack", "UPDATE_FAILED"),
+            state("AWS::RDS::DBInstance", "db", "UPDATE_FAILED"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE"),
+        ];
+        assert_eq!(
+            failing_services(&states).into_iter().collect::<Vec<_>>(),
+            vec!["RDS".to_string()]
+        );
+    }
+
+    #[test]
+    fn page_parses_issues() -> Result<(), serde_json::Error> {
+        let page: Page = serde_json::from_str(
+            r#"{
+                "events": [{
+                    "arn": "arn:aws:health:us-east-1::event/RDS/AWS_RDS_OPERATIONAL_ISSUE/abc",
+                    "service": "RDS",
+                    "eventTypeCode": "AWS_RDS_OPERATIONAL_ISSUE",
+                    "eventTypeCategory": "issue",
+                    "region": "us-east-1",
+                    "startTime": 1546300800.0,
+                    "statusCode": "open"
+                }]
+            }"#,
+        )?;
+        assert_eq!(page.events[0].service, "RDS");
+        assert_eq!(page.events[0].start_time, Some(1_546_300_800.0));
+        Ok(())
+    }
+}
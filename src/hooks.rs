@@ -0,0 +1,128 @@
+//! User defined scripts run in response to matching stack events
+use crate::{filter::Pattern, ResourceState};
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+/// A shell command run for each new event matching all of its patterns
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Hook {
+    /// Pattern of resource types, for example `AWS::RDS::*`
+    #[serde(rename = "type")]
+    pub resource_type: Option<Pattern>,
+    /// Pattern of logical resource ids
+    pub resource: Option<Pattern>,
+    /// Pattern of statuses, for example `*_FAILED`
+    pub status: Option<Pattern>,
+    /// Shell command, run with the event's JSON on stdin
+    pub run: String,
+}
+
+impl Hook {
+    fn matches(
+        &self,
+        state: &ResourceState,
+    ) -> bool {
+        [
+            (&self.resource_type, &state.resource_type),
+            (&self.resource, &state.resource_id),
+            (&self.status, &state.status),
+        ]
+        .iter()
+        .all(|(pattern, value)| pattern.iter().all(|pattern| pattern.matches(value)))
+    }
+
+    /// Starts the hook's command without waiting on it to finish
+    fn spawn(
+        &self,
+        stack_name: &str,
+        state: &ResourceState,
+    ) -> io::Result<()> {
+        let event = serde_json::to_vec(state)?;
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.run)
+            .env("STACK_NAME", stack_name)
+            .stdin(Stdio::piped())
+            .spawn()?;
+        let command = self.run.clone();
+        thread::spawn(move || {
+            if let Some(mut stdin) = child.stdin.take() {
+                drop(stdin.write_all(&event));
+            }
+            match child.wait() {
+                Ok(status) if !status.success() => {
+                    eprintln!("hook `{}` exited with {}", command, status)
+                }
+                Err(err) => eprintln!("hook `{}` failed: {}", command, err),
+                _ => (),
+            }
+        });
+        Ok(())
+    }
+}
+
+/// Runs matching hooks for events which are new since a stack's previous events.
+/// Hooks which can't be started are reported without interrupting tailing
+pub fn run(
+    hooks: &[Hook],
+    stack_name: &str,
+    previous: &[ResourceState],
+    current: &[ResourceState],
+) {
+    let seen = previous
+        .iter()
+        .filter_map(|state| state.event_id.as_deref())
+        .collect::<HashSet<_>>();
+    for state in current.iter().filter(|state| match &state.event_id {
+        Some(event_id) => !seen.contains(event_id.as_str()),
+        None => false,
+    }) {
+        for hook in hooks.iter().filter(|hook| hook.matches(state)) {
+            if let Err(err) = hook.spawn(stack_name, state) {
+                eprintln!("hook `{}` failed to start: {}", hook.run, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn hook_matches_all_patterns() -> Result<(), String> {
+        let hook = Hook {
+            resource_type: Some("AWS::RDS::*".parse()?),
+            resource: None,
+            status: Some("*_FAILED".parse()?),
+            run: "./page-dba.sh".into(),
+        };
+        assert!(hook.matches(&state("AWS::RDS::DBInstance", "db", "UPDATE_FAILED")));
+        assert!(!hook.matches(&state("AWS::RDS::DBInstance", "db", "UPDATE_COMPLETE")));
+        assert!(!hook.matches(&state("AWS::SQS::Queue", "queue", "UPDATE_FAILED")));
+        Ok(())
+    }
+}
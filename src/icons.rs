@@ -0,0 +1,88 @@
+//! Icons marking status families, configurable for fonts and terminals
+//! which render the built in set poorly
+use serde::Deserialize;
+
+/// Icons replacing the built in markers of any of the status families
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct IconsConfig {
+    pub complete: Option<String>,
+    pub deleted: Option<String>,
+    pub imported: Option<String>,
+    pub failed: Option<String>,
+    pub in_progress: Option<String>,
+    pub hook: Option<String>,
+}
+
+/// A full set of status icons
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Icons {
+    /// Whether the set builds on ascii rather than emoji markers
+    pub ascii: bool,
+    pub complete: &'static str,
+    pub deleted: &'static str,
+    pub imported: &'static str,
+    pub failed: &'static str,
+    pub in_progress: &'static str,
+    /// Marks CloudFormation hooks running against a resource
+    pub hook: &'static str,
+}
+
+impl Icons {
+    /// Icons of the base set with any configured overrides applied. Icons
+    /// are marked throughout a run, so they're leaked for its duration
+    pub fn configured(
+        base: Icons,
+        config: IconsConfig,
+    ) -> Icons {
+        let icon = |configured: Option<String>, default: &'static str| -> &'static str {
+            configured.map_or(default, |icon| Box::leak(icon.into_boxed_str()))
+        };
+        Icons {
+            complete: icon(config.complete, base.complete),
+            deleted: icon(config.deleted, base.deleted),
+            imported: icon(config.imported, base.imported),
+            failed: icon(config.failed, base.failed),
+            in_progress: icon(config.in_progress, base.in_progress),
+            hook: icon(config.hook, base.hook),
+            ..base
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_icons_override_base() -> Result<(), toml::de::Error> {
+        let config = toml::from_str::<IconsConfig>(
+            r#"
+            failed = "✗"
+            in-progress = ""
+            "#,
+        )?;
+        let base = Icons {
+            ascii: false,
+            complete: "✅",
+            deleted: "⚰️ ",
+            imported: "📥",
+            failed: "❌",
+            in_progress: "🔄",
+            hook: "🪝",
+        };
+        assert_eq!(
+            Icons::configured(base, config),
+            Icons {
+                ascii: false,
+                complete: "✅",
+                deleted: "⚰️ ",
+                imported: "📥",
+                failed: "✗",
+                in_progress: "",
+                hook: "🪝",
+            }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,897 @@
+//! An interactive full-screen interface, with a pane of each resource's latest
+//! status above a scrollable pane of events and a status bar of progress
+use crate::{
+    is_failure, latest, links, progress::Progress, region_of, stack_logical_id, stack_status,
+    Clock, Error, Markers, ResourceState, Update, COMPLETE,
+};
+use futures::{
+    future::{self, Loop},
+    sync::mpsc,
+    Future, Stream,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io, thread,
+};
+use termion::{
+    event::{Event, Key, MouseButton, MouseEvent},
+    input::{MouseTerminal, TermRead},
+    raw::IntoRawMode,
+    screen::AlternateScreen,
+};
+use tokio::runtime::Runtime;
+use tui::{
+    backend::{Backend, TermionBackend},
+    layout::{self, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    terminal::Frame,
+    widgets::{Block, Borders, Gauge, Paragraph, Row, Table, Text, Widget},
+    Terminal,
+};
+
+/// Rows moved by page up and page down
+const PAGE: usize = 10;
+/// Rows moved by a turn of the mouse wheel
+const WHEEL: usize = 3;
+/// Rows tables give their header, which is followed by a blank row
+const HEADER_ROWS: u16 = 2;
+
+/// Keys pressed and mouse buttons clicked, read on a thread of their own since
+/// reading stdin blocks
+pub fn input() -> impl Stream<Item = Update, Error = Error> {
+    let (sender, receiver) = mpsc::unbounded();
+    thread::spawn(move || {
+        for event in io::stdin().events().filter_map(Result::ok) {
+            let update = match event {
+                Event::Key(key) => Update::Key(key),
+                Event::Mouse(mouse) => Update::Mouse(mouse),
+                Event::Unsupported(_) => continue,
+            };
+            if sender.unbounded_send(update).is_err() {
+                return;
+            }
+        }
+    });
+    receiver.map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "keyboard input ended").into())
+}
+
+/// Which way searches look for the next match, from the events pane's bottom
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Direction {
+    Older,
+    Newer,
+}
+
+/// The pane keys navigate
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Pane {
+    Resources,
+    Events,
+}
+
+/// Where the panes were last drawn, to tell which a click lands in
+#[derive(Debug, Default, Clone, Copy)]
+struct Areas {
+    resources: Rect,
+    events: Rect,
+}
+
+/// Whether a rect contains a zero based position
+fn contains(
+    area: Rect,
+    x: u16,
+    y: u16,
+) -> bool {
+    x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height
+}
+
+/// Number of table rows visible inside a pane's borders, beneath its header
+fn visible(area: Rect) -> usize {
+    area.height.saturating_sub(2 + HEADER_ROWS) as usize
+}
+
+/// What's displayed and where navigation left off
+pub struct App {
+    stacks: BTreeMap<String, Vec<ResourceState>>,
+    clock: Clock,
+    markers: Markers,
+    /// Template resource counts of each stack, for progress of creations
+    template_resources: Option<HashMap<String, usize>>,
+    focus: Pane,
+    /// Index of the selected resource
+    selected: usize,
+    /// Number of events scrolled back from the most recent, which the events
+    /// pane follows while zero
+    scroll: usize,
+    /// A search being typed after pressing /
+    search: Option<String>,
+    /// The search events are matched against, once entered
+    query: Option<String>,
+    /// The resource or event whose details are expanded, by pane and index
+    expanded: Option<(Pane, usize)>,
+    areas: Areas,
+    /// What became of the last action, like a console page without a link
+    notice: Option<String>,
+    /// Whether q or Ctrl-C was pressed, the latter since raw mode doesn't raise SIGINT
+    quit: bool,
+}
+
+impl App {
+    pub fn new(
+        clock: Clock,
+        markers: Markers,
+        template_resources: Option<HashMap<String, usize>>,
+    ) -> Self {
+        App {
+            stacks: BTreeMap::new(),
+            clock,
+            markers,
+            template_resources,
+            focus: Pane::Resources,
+            selected: 0,
+            scroll: 0,
+            search: None,
+            query: None,
+            expanded: None,
+            areas: Areas::default(),
+            notice: None,
+            quit: false,
+        }
+    }
+
+    /// Each stack's resources in their latest state
+    fn resources(&self) -> Vec<(&str, ResourceState)> {
+        self.stacks
+            .iter()
+            .flat_map(|(stack_name, states)| {
+                latest(states.clone())
+                    .into_iter()
+                    .map(move |state| (stack_name.as_str(), state))
+            })
+            .collect()
+    }
+
+    /// Events of every stack, oldest first
+    fn events(&self) -> Vec<(&str, &ResourceState)> {
+        let mut events = self
+            .stacks
+            .iter()
+            .flat_map(|(stack_name, states)| {
+                states.iter().map(move |state| (stack_name.as_str(), state))
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|(_, state)| state.timestamp);
+        events
+    }
+
+    /// Progress of every stack's current operation together
+    fn progress(&self) -> Option<Progress> {
+        let (done, total) = self
+            .stacks
+            .iter()
+            .filter_map(|(stack_name, states)| {
+                let total = self
+                    .template_resources
+                    .as_ref()
+                    .and_then(|counts| counts.get(stack_name).cloned());
+                Progress::of(stack_name, states, total)
+            })
+            .fold((0, 0), |(done, total), progress| {
+                (done + progress.done, total + progress.total)
+            });
+        if total == 0 {
+            return None;
+        }
+        Some(Progress {
+            done,
+            total,
+            remaining: None,
+        })
+    }
+
+    pub fn update(
+        &mut self,
+        update: Update,
+    ) {
+        match update {
+            Update::States(stack_name, states) => {
+                // events arriving while scrolled back don't move what's displayed
+                if self.scroll > 0 {
+                    let before = self.stacks.get(&stack_name).map_or(0, Vec::len);
+                    self.scroll += states.len().saturating_sub(before);
+                }
+                self.stacks.insert(stack_name, states);
+            }
+            Update::Key(key) => self.key(key),
+            Update::Mouse(mouse) => self.mouse(mouse),
+            Update::Tick | Update::Resize | Update::Stop => (),
+        }
+    }
+
+    fn key(
+        &mut self,
+        key: Key,
+    ) {
+        if let Some(search) = &mut self.search {
+            match key {
+                Key::Char('\n') => {
+                    self.query = self.search.take().filter(|search| !search.is_empty())
+                }
+                Key::Esc => self.search = None,
+                Key::Backspace => {
+                    search.pop();
+                }
+                Key::Char(c) => {
+                    search.push(c);
+                    let search = search.clone();
+                    // searching incrementally keeps to the current match while it still matches
+                    let bottom = self.bottom();
+                    if let Some(index) = self.matching(&search, bottom + 1, Direction::Older) {
+                        self.reveal(index);
+                    }
+                }
+                _ => (),
+            }
+            return;
+        }
+        self.notice = None;
+        let (resources, events) = (self.resources().len(), self.event_count());
+        match (self.focus, key) {
+            (_, Key::Char('q')) | (_, Key::Ctrl('c')) => self.quit = true,
+            (_, Key::Char('o')) => self.open(),
+            (_, Key::Char('/')) => {
+                self.focus = Pane::Events;
+                self.search = Some(String::new());
+            }
+            (_, Key::Esc) => self.query = None,
+            (Pane::Resources, Key::Char('\n')) => self.expand(Pane::Resources, self.selected),
+            (Pane::Events, Key::Char('\n')) => self.expand(Pane::Events, self.bottom()),
+            (Pane::Events, Key::Char('n')) | (Pane::Events, Key::Char('N')) => {
+                let direction = if key == Key::Char('n') {
+                    Direction::Older
+                } else {
+                    Direction::Newer
+                };
+                if let Some(query) = self.query.clone() {
+                    let bottom = self.bottom();
+                    if let Some(index) = self.matching(&query, bottom, direction) {
+                        self.reveal(index);
+                    }
+                }
+            }
+            (_, Key::Char('\t')) => {
+                self.focus = match self.focus {
+                    Pane::Resources => Pane::Events,
+                    Pane::Events => Pane::Resources,
+                }
+            }
+            (Pane::Resources, Key::Up) | (Pane::Resources, Key::Char('k')) => {
+                self.selected = self.selected.saturating_sub(1)
+            }
+            (Pane::Resources, Key::Down) | (Pane::Resources, Key::Char('j')) => {
+                self.selected = (self.selected + 1).min(resources.saturating_sub(1))
+            }
+            (Pane::Resources, Key::Home) | (Pane::Resources, Key::Char('g')) => self.selected = 0,
+            (Pane::Resources, Key::End) | (Pane::Resources, Key::Char('G')) => {
+                self.selected = resources.saturating_sub(1)
+            }
+            (Pane::Events, Key::Up) | (Pane::Events, Key::Char('k')) => {
+                self.scroll = (self.scroll + 1).min(events.saturating_sub(1))
+            }
+            (Pane::Events, Key::Down) | (Pane::Events, Key::Char('j')) => {
+                self.scroll = self.scroll.saturating_sub(1)
+            }
+            (Pane::Events, Key::PageUp) => {
+                self.scroll = (self.scroll + PAGE).min(events.saturating_sub(1))
+            }
+            (Pane::Events, Key::PageDown) => self.scroll = self.scroll.saturating_sub(PAGE),
+            (Pane::Events, Key::Home) | (Pane::Events, Key::Char('g')) => {
+                self.scroll = events.saturating_sub(1)
+            }
+            (Pane::Events, Key::End) | (Pane::Events, Key::Char('G')) => self.scroll = 0,
+            _ => (),
+        }
+    }
+
+    fn mouse(
+        &mut self,
+        mouse: MouseEvent,
+    ) {
+        // terminals report positions from one
+        let (button, x, y) = match mouse {
+            MouseEvent::Press(button, x, y) => (button, x.saturating_sub(1), y.saturating_sub(1)),
+            _ => return,
+        };
+        let (pane, area) = if contains(self.areas.resources, x, y) {
+            (Pane::Resources, self.areas.resources)
+        } else if contains(self.areas.events, x, y) {
+            (Pane::Events, self.areas.events)
+        } else {
+            return;
+        };
+        let (resources, events) = (self.resources().len(), self.event_count());
+        match (pane, button) {
+            (Pane::Resources, MouseButton::WheelUp) => {
+                self.selected = self.selected.saturating_sub(WHEEL)
+            }
+            (Pane::Resources, MouseButton::WheelDown) => {
+                self.selected = (self.selected + WHEEL).min(resources.saturating_sub(1))
+            }
+            (Pane::Events, MouseButton::WheelUp) => {
+                self.scroll = (self.scroll + WHEEL).min(events.saturating_sub(1))
+            }
+            (Pane::Events, MouseButton::WheelDown) => {
+                self.scroll = self.scroll.saturating_sub(WHEEL)
+            }
+            (_, MouseButton::Left) => {
+                self.focus = pane;
+                let row = match y.checked_sub(area.y + 1 + HEADER_ROWS) {
+                    Some(row) if (row as usize) < visible(area) => row as usize,
+                    _ => return,
+                };
+                match pane {
+                    Pane::Resources => {
+                        let index = self.resource_offset(visible(area)) + row;
+                        if index < resources {
+                            self.selected = index;
+                            self.expand(pane, index);
+                        }
+                    }
+                    Pane::Events => {
+                        let (start, end) = self.event_window(visible(area));
+                        if start + row < end {
+                            self.expand(pane, start + row);
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Expands a resource's or event's details, or collapses them when they
+    /// already are
+    fn expand(
+        &mut self,
+        pane: Pane,
+        index: usize,
+    ) {
+        self.expanded = if self.expanded == Some((pane, index)) {
+            None
+        } else {
+            Some((pane, index))
+        };
+    }
+
+    /// The resource or event whose details are expanded
+    fn details(&self) -> Option<(String, ResourceState)> {
+        match self.expanded? {
+            (Pane::Resources, index) => self
+                .resources()
+                .into_iter()
+                .nth(index)
+                .map(|(stack_name, state)| (stack_name.to_string(), state)),
+            (Pane::Events, index) => self
+                .events()
+                .get(index)
+                .map(|(stack_name, state)| (stack_name.to_string(), (*state).clone())),
+        }
+    }
+
+    /// Index of the first resource displayed, keeping the selection in view
+    fn resource_offset(
+        &self,
+        height: usize,
+    ) -> usize {
+        (self.selected + 1).saturating_sub(height)
+    }
+
+    /// Range of the events displayed, ending with the scrolled to event
+    fn event_window(
+        &self,
+        height: usize,
+    ) -> (usize, usize) {
+        let end = self.event_count().saturating_sub(self.scroll);
+        (end.saturating_sub(height), end)
+    }
+
+    /// The selected resource, or the event at the bottom of the events pane
+    fn selection(&self) -> Option<(String, ResourceState)> {
+        match self.focus {
+            Pane::Resources => self
+                .resources()
+                .into_iter()
+                .nth(self.selected)
+                .map(|(stack_name, state)| (stack_name.to_string(), state)),
+            Pane::Events => self
+                .events()
+                .get(self.bottom())
+                .map(|(stack_name, state)| (stack_name.to_string(), (*state).clone())),
+        }
+    }
+
+    /// Opens the selection's console page in the default browser
+    fn open(&mut self) {
+        let (stack_name, state) = match self.selection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        let region = region_of(&stack_name).unwrap_or_default();
+        self.notice = match links::console_url(&region, &state) {
+            Some(url) => links::open(&url)
+                .err()
+                .map(|err| format!("unable to open {}: {}", url, err)),
+            None if state.physical_id.is_none() => {
+                Some(format!("{} has no physical id yet", state.resource_id))
+            }
+            None => Some(format!(
+                "no console page is known for {}",
+                state.resource_type
+            )),
+        };
+    }
+
+    fn event_count(&self) -> usize {
+        self.stacks.values().map(Vec::len).sum()
+    }
+
+    /// Index of the event at the bottom of the events pane
+    fn bottom(&self) -> usize {
+        self.event_count().saturating_sub(self.scroll + 1)
+    }
+
+    /// Scrolls the events pane so that an event is at its bottom
+    fn reveal(
+        &mut self,
+        index: usize,
+    ) {
+        self.scroll = self.event_count().saturating_sub(index + 1);
+    }
+
+    /// Index of the closest event matching a search, searching from but not
+    /// including an event in a direction and wrapping around at either end
+    fn matching(
+        &self,
+        search: &str,
+        from: usize,
+        direction: Direction,
+    ) -> Option<usize> {
+        let events = self.events();
+        let len = events.len();
+        (1..=len)
+            .map(|distance| match direction {
+                Direction::Older => (from + len * 2 - distance) % len,
+                Direction::Newer => (from + distance) % len,
+            })
+            .find(|index| matches(events[*index].1, search))
+    }
+
+    fn draw<B: Backend>(
+        &mut self,
+        f: &mut Frame<B>,
+    ) {
+        let details = self.details();
+        let chunks = Layout::default()
+            .direction(layout::Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Percentage(40),
+                    Constraint::Length(if details.is_some() { 7 } else { 0 }),
+                    Constraint::Min(3),
+                    Constraint::Length(1),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+        self.areas = Areas {
+            resources: chunks[0],
+            events: chunks[2],
+        };
+        self.draw_resources(f, chunks[0]);
+        if let Some((stack_name, state)) = details {
+            self.draw_details(f, chunks[1], &stack_name, &state);
+        }
+        self.draw_events(f, chunks[2]);
+        self.draw_progress(f, chunks[3]);
+        self.draw_status(f, chunks[4]);
+    }
+
+    /// A resource's full reason, physical id and timestamp, which the panes' columns cut short
+    fn draw_details<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+        stack_name: &str,
+        state: &ResourceState,
+    ) {
+        let muted = Style::default().fg(Color::DarkGray);
+        let text = [
+            Text::styled("resource  ", muted),
+            Text::raw(format!(
+                "{} {}\n",
+                resource_id(stack_name, state, self.stacks.len() > 1),
+                state.resource_type
+            )),
+            Text::styled("physical  ", muted),
+            Text::raw(format!("{}\n", state.physical_id.as_deref().unwrap_or("-"))),
+            Text::styled("status    ", muted),
+            Text::styled(
+                format!("{} ", state.status),
+                status_style(&state.status).modifier(Modifier::BOLD),
+            ),
+            Text::raw(format!("at {}\n", state.timestamp(&self.clock))),
+            Text::styled("reason    ", muted),
+            Text::raw(state.reason.clone()),
+        ];
+        Paragraph::new(text.iter())
+            .block(Block::default().borders(Borders::ALL).title("details"))
+            .wrap(true)
+            .render(f, area);
+    }
+
+    fn block(
+        &self,
+        title: &'static str,
+        pane: Pane,
+    ) -> Block<'static> {
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(if self.focus == pane {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default()
+            })
+    }
+
+    fn draw_resources<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+    ) {
+        let resources = self.resources();
+        let height = visible(area);
+        let offset = self.resource_offset(height);
+        let labeled = self.stacks.len() > 1;
+        let rows = resources.iter().enumerate().skip(offset).take(height).map(
+            |(index, (stack_name, state))| {
+                let style = if index == self.selected {
+                    status_style(&state.status).modifier(Modifier::REVERSED)
+                } else {
+                    status_style(&state.status)
+                };
+                Row::StyledData(
+                    vec![
+                        self.markers.status(&state.status).to_string(),
+                        resource_id(stack_name, state, labeled),
+                        state.resource_type.clone(),
+                        state.status.clone(),
+                        state.reason.clone(),
+                    ]
+                    .into_iter(),
+                    style,
+                )
+            },
+        );
+        Table::new(["", "resource", "type", "status", "reason"].iter(), rows)
+            .block(self.block("resources", Pane::Resources))
+            .header_style(Style::default().modifier(Modifier::BOLD))
+            .widths(&widths(area.width))
+            .render(f, area);
+    }
+
+    fn draw_events<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+    ) {
+        let events = self.events();
+        let (start, end) = self.event_window(visible(area));
+        let labeled = self.stacks.len() > 1;
+        let query = self.search.as_ref().or_else(|| self.query.as_ref());
+        let rows = events[start..end].iter().map(|(stack_name, state)| {
+            let style = match query {
+                Some(query) if !query.is_empty() && matches(state, query) => {
+                    Style::default().fg(Color::Yellow).modifier(Modifier::BOLD)
+                }
+                _ => status_style(&state.status),
+            };
+            Row::StyledData(
+                vec![
+                    state.timestamp(&self.clock),
+                    resource_id(stack_name, state, labeled),
+                    format!("{} {}", self.markers.status(&state.status), state.status),
+                    state.reason.clone(),
+                ]
+                .into_iter(),
+                style,
+            )
+        });
+        Table::new(["time", "resource", "status", "reason"].iter(), rows)
+            .block(self.block(
+                if self.scroll > 0 {
+                    "events (scrolled back, End to follow)"
+                } else {
+                    "events"
+                },
+                Pane::Events,
+            ))
+            .header_style(Style::default().modifier(Modifier::BOLD))
+            .widths(&[25, 30, 32, area.width.saturating_sub(95)])
+            .render(f, area);
+    }
+
+    fn draw_progress<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+    ) {
+        let (ratio, label) = match self.progress() {
+            Some(progress) => (
+                progress.done as f64 / progress.total as f64,
+                format!("{}/{} resources complete", progress.done, progress.total),
+            ),
+            None => (0.0, "no operation in progress".to_string()),
+        };
+        Gauge::default()
+            .style(Style::default().fg(Color::Green))
+            .ratio(ratio)
+            .label(&label)
+            .render(f, area);
+    }
+
+    fn draw_status<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        area: Rect,
+    ) {
+        let mut text = Vec::new();
+        for (stack_name, states) in &self.stacks {
+            let status = stack_status(stack_name, states).unwrap_or("status unknown");
+            text.push(Text::raw(format!("{} ", stack_logical_id(stack_name))));
+            text.push(Text::styled(
+                format!("{}  ", status),
+                status_style(status).modifier(Modifier::BOLD),
+            ));
+        }
+        if let Some(notice) = &self.notice {
+            text.push(Text::styled(
+                format!("{}  ", notice),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let help = match (&self.search, &self.query) {
+            (Some(search), _) => format!("/{}", search),
+            (None, Some(query)) => format!("/{}  n older  N newer  esc clear", query),
+            (None, None) => {
+                "tab switch pane  ↑↓ navigate  enter details  / search  o open in console  q quit"
+                    .into()
+            }
+        };
+        text.push(Text::styled(help, Style::default().fg(Color::DarkGray)));
+        Paragraph::new(text.iter()).render(f, area);
+    }
+}
+
+/// Whether an event's logical id, status or reason contains a search, ignoring case
+fn matches(
+    state: &ResourceState,
+    search: &str,
+) -> bool {
+    let search = search.to_lowercase();
+    [&state.resource_id, &state.status, &state.reason]
+        .iter()
+        .any(|field| field.to_lowercase().contains(&search))
+}
+
+/// A resource's logical id, prefixed with its stack's name when tailing several
+fn resource_id(
+    stack_name: &str,
+    state: &ResourceState,
+    labeled: bool,
+) -> String {
+    if labeled {
+        format!("{}/{}", stack_logical_id(stack_name), state.resource_id)
+    } else {
+        state.resource_id.clone()
+    }
+}
+
+/// Widths of the resources pane's columns, the reason taking what's left
+fn widths(width: u16) -> [u16; 5] {
+    [4, 30, 32, 32, width.saturating_sub(106)]
+}
+
+/// How a status is colored, matching the colors of the line by line view
+fn status_style(status: &str) -> Style {
+    match status {
+        failed if is_failure(failed) => Style::default().fg(Color::LightRed),
+        complete if complete.ends_with(COMPLETE) => Style::default().fg(Color::LightGreen),
+        _ => Style::default(),
+    }
+}
+
+/// Follows updates in the full-screen interface until they stop, returning
+/// each stack's last states once the terminal is restored
+pub fn follow(
+    runtime: &mut Runtime,
+    updates: impl Stream<Item = Update, Error = Error> + Send + 'static,
+    app: App,
+) -> Result<BTreeMap<String, Vec<ResourceState>>, Error> {
+    let stdout = AlternateScreen::from(MouseTerminal::from(io::stdout().into_raw_mode()?));
+    let mut terminal = Terminal::new(TermionBackend::new(stdout))?;
+    terminal.hide_cursor()?;
+    let app = runtime.block_on(future::loop_fn(
+        (updates, terminal, app),
+        |(updates, mut terminal, mut app)| {
+            updates
+                .into_future()
+                .map_err(|(err, _)| err)
+                .and_then(move |(update, updates)| {
+                    match update {
+                        Some(update) => app.update(update),
+                        None => return Ok(Loop::Break(app)),
+                    }
+                    if app.quit {
+                        return Ok(Loop::Break(app));
+                    }
+                    terminal.draw(|mut f| app.draw(&mut f))?;
+                    Ok(Loop::Continue((updates, terminal, app)))
+                })
+        },
+    ))?;
+    Ok(app.stacks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App::new(Clock::default(), Markers::Ascii, None);
+        app.update(Update::States(
+            "stack".into(),
+            vec![
+                state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+                state("AWS::SQS::Queue", "queue", "UPDATE_IN_PROGRESS"),
+                state("AWS::SNS::Topic", "topic", "UPDATE_IN_PROGRESS"),
+                state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE"),
+            ],
+        ));
+        app
+    }
+
+    #[test]
+    fn resources_are_in_their_latest_state() {
+        assert_eq!(
+            app()
+                .resources()
+                .iter()
+                .map(|(_, state)| (state.resource_id.as_str(), state.status.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("stack", "UPDATE_IN_PROGRESS"),
+                ("queue", "UPDATE_COMPLETE"),
+                ("topic", "UPDATE_IN_PROGRESS")
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_navigate_the_focused_pane() {
+        let mut app = app();
+        for key in &[Key::Down, Key::Down, Key::Down, Key::Down] {
+            app.update(Update::Key(*key));
+        }
+        assert_eq!(app.selected, 2);
+        app.update(Update::Key(Key::Char('\t')));
+        app.update(Update::Key(Key::PageUp));
+        assert_eq!((app.selected, app.scroll), (2, 3));
+        app.update(Update::Key(Key::End));
+        assert_eq!(app.scroll, 0);
+    }
+
+    #[test]
+    fn searches_reveal_matching_events() {
+        let mut app = app();
+        for key in &[Key::Char('/'), Key::Char('q'), Key::Char('u')] {
+            app.update(Update::Key(*key));
+        }
+        // the latest queue event is revealed as the search is typed
+        assert_eq!((app.scroll, app.quit), (0, false));
+        app.update(Update::Key(Key::Char('\n')));
+        assert_eq!(app.query.as_deref(), Some("qu"));
+        app.update(Update::Key(Key::Char('n')));
+        assert_eq!(app.scroll, 2);
+        app.update(Update::Key(Key::Char('n')));
+        assert_eq!(app.scroll, 0);
+        app.update(Update::Key(Key::Char('N')));
+        assert_eq!(app.scroll, 2);
+        app.update(Update::Key(Key::Char('q')));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn selection_follows_the_focused_pane() {
+        let mut app = app();
+        app.update(Update::Key(Key::Down));
+        assert_eq!(
+            app.selection()
+                .map(|(_, state)| (state.resource_id, state.status)),
+            Some(("queue".into(), "UPDATE_COMPLETE".into()))
+        );
+        app.update(Update::Key(Key::Char('\t')));
+        app.update(Update::Key(Key::Up));
+        assert_eq!(
+            app.selection()
+                .map(|(_, state)| (state.resource_id, state.status)),
+            Some(("topic".into(), "UPDATE_IN_PROGRESS".into()))
+        );
+        app.update(Update::Key(Key::Char('o')));
+        assert_eq!(app.notice.as_deref(), Some("topic has no physical id yet"));
+    }
+
+    #[test]
+    fn clicks_expand_rows_and_wheels_scroll() {
+        let mut app = app();
+        app.areas = Areas {
+            resources: Rect::new(0, 0, 80, 10),
+            events: Rect::new(0, 10, 80, 10),
+        };
+        // the third row of the resources pane, beneath its border and header
+        app.update(Update::Mouse(MouseEvent::Press(MouseButton::Left, 5, 6)));
+        assert_eq!(
+            app.details()
+                .map(|(_, state)| (state.resource_id, state.status)),
+            Some(("topic".into(), "UPDATE_IN_PROGRESS".into()))
+        );
+        assert_eq!(app.selected, 2);
+        app.update(Update::Mouse(MouseEvent::Press(MouseButton::Left, 5, 6)));
+        assert_eq!(app.details(), None);
+        // the first row of the events pane
+        app.update(Update::Mouse(MouseEvent::Press(MouseButton::Left, 5, 14)));
+        assert_eq!(app.focus, Pane::Events);
+        assert_eq!(
+            app.details()
+                .map(|(_, state)| (state.resource_id, state.status)),
+            Some(("stack".into(), "UPDATE_IN_PROGRESS".into()))
+        );
+        app.update(Update::Mouse(MouseEvent::Press(
+            MouseButton::WheelUp,
+            5,
+            14,
+        )));
+        assert_eq!(app.scroll, 3);
+    }
+
+    #[test]
+    fn scrolled_back_events_stay_put() {
+        let mut app = app();
+        app.update(Update::Key(Key::Char('\t')));
+        app.update(Update::Key(Key::Up));
+        let mut states = app.stacks["stack"].clone();
+        states.push(state("AWS::SNS::Topic", "topic", "UPDATE_COMPLETE"));
+        app.update(Update::States("stack".into(), states));
+        assert_eq!(app.scroll, 2);
+    }
+}
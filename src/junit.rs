@@ -0,0 +1,132 @@
+//! JUnit XML reports of resource states, for surfacing deployment failures in CI
+use crate::{latest, ResourceState, COMPLETE, FAILED};
+use std::{collections::BTreeMap, fmt};
+
+fn escaped(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Stacks rendered as JUnit test suites where the latest state
+/// of each resource is a test case
+pub struct Report<'a>(pub &'a BTreeMap<String, Vec<ResourceState>>);
+
+impl fmt::Display for Report<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Report(stacks) = self;
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(f, "<testsuites>")?;
+        for (stack_name, states) in stacks.iter() {
+            write!(f, "{}", Suite(stack_name, &latest(states.clone())))?;
+        }
+        writeln!(f, "</testsuites>")
+    }
+}
+
+/// A stack's resources rendered as a JUnit test suite
+struct Suite<'a>(&'a str, &'a [ResourceState]);
+
+impl fmt::Display for Suite<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Suite(stack_name, states) = self;
+        let failures = states
+            .iter()
+            .filter(|state| state.status.ends_with(FAILED))
+            .count();
+        let skipped = states
+            .iter()
+            .filter(|state| !state.complete_or_failed())
+            .count();
+        writeln!(
+            f,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" skipped="{}">"#,
+            escaped(stack_name),
+            states.len(),
+            failures,
+            skipped
+        )?;
+        for state in states.iter() {
+            write!(
+                f,
+                r#"    <testcase classname="{}" name="{}""#,
+                escaped(&state.resource_type),
+                escaped(&state.resource_id)
+            )?;
+            match &state.status[..] {
+                complete if complete.ends_with(COMPLETE) => writeln!(f, "/>")?,
+                failed if failed.ends_with(FAILED) => {
+                    writeln!(f, ">")?;
+                    writeln!(
+                        f,
+                        r#"      <failure message="{}" type="{}">{}</failure>"#,
+                        escaped(&state.reason),
+                        escaped(failed),
+                        escaped(&state.reason)
+                    )?;
+                    writeln!(f, "    </testcase>")?;
+                }
+                pending => {
+                    writeln!(f, ">")?;
+                    writeln!(f, r#"      <skipped message="{}"/>"#, escaped(pending))?;
+                    writeln!(f, "    </testcase>")?;
+                }
+            }
+        }
+        writeln!(f, "  </testsuite>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn report_marks_failed_resources_as_failures() {
+        let mut stacks = BTreeMap::new();
+        stacks.insert(
+            "stack".to_string(),
+            vec![
+                state("AWS::SQS::Queue", "Queue", "CREATE_IN_PROGRESS"),
+                state("AWS::SQS::Queue", "Queue", "CREATE_COMPLETE"),
+                ResourceState {
+                    reason: "Access <denied>".into(),
+                    ..state("AWS::SQS::Queue", "Topic", "CREATE_FAILED")
+                },
+            ],
+        );
+        let xml = Report(&stacks).to_string();
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains(r#"<testsuite name="stack" tests="2" failures="1" skipped="0">"#));
+        assert!(xml.contains(r#"<testcase classname="AWS::SQS::Queue" name="Queue"/>"#));
+        assert!(xml.contains(
+            r#"<failure message="Access &lt;denied&gt;" type="CREATE_FAILED">Access &lt;denied&gt;</failure>"#
+        ));
+    }
+}
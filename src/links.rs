@@ -0,0 +1,222 @@
+//! Links to the aws console pages of resources
+use crate::{Markers, ResourceState};
+use rusoto_core::Region;
+use std::{
+    fmt, io,
+    process::{Command, Stdio},
+};
+
+/// How console links are displayed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Links {
+    /// Terminal hyperlinks, for terminals supporting OSC 8
+    Hyperlinks,
+    /// Plain urls, for output which isn't a terminal
+    Urls,
+}
+
+/// Percent encodes a value for use in a url
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                char::from(byte).to_string()
+            }
+            other => format!("%{:02X}", other),
+        })
+        .collect()
+}
+
+/// The url of a resource's console page, for the resource types with one
+pub fn console_url(
+    region: &Region,
+    state: &ResourceState,
+) -> Option<String> {
+    let id = state.physical_id.as_deref()?;
+    let region = region.name();
+    let console = format!("https://{}.console.aws.amazon.com", region);
+    let url = match state.resource_type.as_str() {
+        "AWS::CloudFormation::Stack" => format!(
+            "{}/cloudformation/home?region={}#/stacks/stackinfo?stackId={}",
+            console,
+            region,
+            encode(id)
+        ),
+        "AWS::DynamoDB::Table" => format!(
+            "{}/dynamodbv2/home?region={}#table?name={}",
+            console, region, id
+        ),
+        "AWS::EC2::Instance" => format!(
+            "{}/ec2/home?region={}#InstanceDetails:instanceId={}",
+            console, region, id
+        ),
+        "AWS::EC2::SecurityGroup" => format!(
+            "{}/ec2/home?region={}#SecurityGroup:groupId={}",
+            console, region, id
+        ),
+        "AWS::ECS::Cluster" => format!("{}/ecs/v2/clusters/{}?region={}", console, id, region),
+        "AWS::ECS::Service" => {
+            // arn:aws:ecs:region:account:service/cluster/name
+            let mut path = id.rsplit(':').next()?.split('/').skip(1);
+            let (cluster, service) = (path.next()?, path.next()?);
+            format!(
+                "{}/ecs/v2/clusters/{}/services/{}?region={}",
+                console, cluster, service, region
+            )
+        }
+        "AWS::IAM::Role" => format!("https://console.aws.amazon.com/iam/home#/roles/{}", id),
+        "AWS::Lambda::Function" => format!(
+            "{}/lambda/home?region={}#/functions/{}",
+            console, region, id
+        ),
+        "AWS::RDS::DBInstance" => {
+            format!("{}/rds/home?region={}#database:id={}", console, region, id)
+        }
+        "AWS::S3::Bucket" => format!(
+            "https://s3.console.aws.amazon.com/s3/buckets/{}?region={}",
+            id, region
+        ),
+        "AWS::SNS::Topic" => format!("{}/sns/v3/home?region={}#/topic/{}", console, region, id),
+        "AWS::SQS::Queue" => format!(
+            "{}/sqs/v2/home?region={}#/queues/{}",
+            console,
+            region,
+            encode(id)
+        ),
+        "AWS::StepFunctions::StateMachine" => format!(
+            "{}/states/home?region={}#/statemachines/view/{}",
+            console,
+            region,
+            encode(id)
+        ),
+        _ => return None,
+    };
+    Some(url)
+}
+
+/// Opens a url in the default browser
+pub fn open(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        // start treats its first quoted argument as a window title
+        command.args(&["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+/// Provides a means of displaying a console link
+pub struct Link<'a>(pub Links, pub &'a str, pub Markers);
+
+impl fmt::Display for Link<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Link(links, url, markers) = self;
+        match links {
+            Links::Hyperlinks => write!(
+                f,
+                "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+                url,
+                match markers {
+                    Markers::Ascii => "console",
+                    _ => "🔗 console",
+                }
+            ),
+            Links::Urls => write!(f, "{}", url),
+        }
+    }
+}
+
+/// A line without its OSC 8 hyperlink sequences, leaving their text
+pub fn without_hyperlinks(line: &str) -> String {
+    let mut visible = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("\x1b]8;") {
+        visible.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match rest.find("\x1b\\") {
+            Some(end) => rest = &rest[end + 2..],
+            None => return visible,
+        }
+    }
+    visible.push_str(rest);
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn console_url_links_known_resource_types() {
+        let function = ResourceState {
+            physical_id: Some("my-stack-Handler-1A2B3C".into()),
+            ..state("AWS::Lambda::Function", "Handler", "CREATE_COMPLETE")
+        };
+        assert_eq!(
+            console_url(&Region::UsEast1, &function).as_deref(),
+            Some("https://us-east-1.console.aws.amazon.com/lambda/home?region=us-east-1#/functions/my-stack-Handler-1A2B3C")
+        );
+        let service = ResourceState {
+            physical_id: Some(
+                "arn:aws:ecs:us-east-1:111111111111:service/web/my-stack-Service-1A2B3C".into(),
+            ),
+            ..state("AWS::ECS::Service", "Service", "CREATE_COMPLETE")
+        };
+        assert_eq!(
+            console_url(&Region::UsEast1, &service).as_deref(),
+            Some("https://us-east-1.console.aws.amazon.com/ecs/v2/clusters/web/services/my-stack-Service-1A2B3C?region=us-east-1")
+        );
+        let unknown = ResourceState {
+            physical_id: Some("id".into()),
+            ..state("Custom::Thing", "Thing", "CREATE_COMPLETE")
+        };
+        assert_eq!(console_url(&Region::UsEast1, &unknown), None);
+        assert_eq!(
+            console_url(
+                &Region::UsEast1,
+                &state("AWS::Lambda::Function", "Handler", "CREATE_IN_PROGRESS")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn without_hyperlinks_keeps_their_text() {
+        let line = format!(
+            "Handler\t{}",
+            Link(Links::Hyperlinks, "https://example.com", Markers::Ascii)
+        );
+        assert_eq!(without_hyperlinks(&line), "Handler\tconsole");
+    }
+}
@@ -0,0 +1,156 @@
+//! Advisory per-stack lock files, preventing concurrent stack-tail processes
+//! from issuing the same mutating actions against a stack
+use crate::{accounts::account_of, cache, region_of, stack_logical_id};
+use rusoto_core::Region;
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+/// A held lock, released when dropped, or when its process exits
+#[derive(Debug)]
+pub struct Lock {
+    // the lock is held for as long as the file stays open
+    _file: File,
+}
+
+/// Directory lock files are kept in
+fn dir() -> PathBuf {
+    env::temp_dir().join("stack-tail")
+}
+
+/// Name of a stack's lock file, scoped to its account and region so stacks
+/// of the same name elsewhere lock separately
+fn file_name(
+    account: &str,
+    region: &Region,
+    stack_name: &str,
+) -> String {
+    format!(
+        "{}-{}-{}.lock",
+        account,
+        region.name(),
+        stack_logical_id(stack_name)
+    )
+}
+
+/// Takes an exclusive lock on an open file without blocking, returning
+/// whether it was free. Locks of exited processes are released by the
+/// operating system, so there are none left behind to take over
+#[cfg(unix)]
+fn try_lock(file: &File) -> io::Result<bool> {
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+/// Files are only locked on unix
+#[cfg(not(unix))]
+fn try_lock(_: &File) -> io::Result<bool> {
+    Ok(true)
+}
+
+impl Lock {
+    /// Acquires the lock for a stack, returning the path of the existing
+    /// lock file as an error when another running process already holds it.
+    /// Stacks named rather than given by id are locked in the account of the
+    /// default credentials and the default region
+    pub fn acquire(stack_name: &str) -> io::Result<Result<Lock, PathBuf>> {
+        let account = account_of(stack_name)
+            .map(String::from)
+            .or_else(cache::account_id)
+            .unwrap_or_else(|| "unknown".into());
+        let region = region_of(stack_name).unwrap_or_default();
+        Self::acquire_in(&dir(), &file_name(&account, &region, stack_name))
+    }
+
+    fn acquire_in(
+        dir: &Path,
+        file_name: &str,
+    ) -> io::Result<Result<Lock, PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(file_name);
+        // the file is left in place when released, as removing it could
+        // unlink one another process has just opened and locked
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&path)?;
+        if !try_lock(&file)? {
+            return Ok(Err(path));
+        }
+        // the holder's process id, for whoever finds the lock held
+        file.set_len(0)?;
+        writeln!(file, "{}", process::id())?;
+        Ok(Ok(Lock { _file: file }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("stack-tail-{}-{}", name, process::id()))
+    }
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() -> io::Result<()> {
+        let dir = test_dir("lock-test");
+        let lock = Lock::acquire_in(&dir, "stack.lock")?.expect("lock should be free");
+        assert_eq!(
+            Lock::acquire_in(&dir, "stack.lock")?.err(),
+            Some(dir.join("stack.lock"))
+        );
+        drop(lock);
+        assert!(Lock::acquire_in(&dir, "stack.lock")?.is_ok());
+        fs::remove_dir_all(&dir)
+    }
+
+    #[test]
+    fn file_names_are_scoped_by_account_and_region() {
+        assert_eq!(
+            file_name(
+                "123456789012",
+                &Region::UsEast1,
+                "arn:aws:cloudformation:us-east-1:123456789012:stack/stack/abc"
+            ),
+            file_name("123456789012", &Region::UsEast1, "stack")
+        );
+        assert_ne!(
+            file_name("123456789012", &Region::UsEast1, "stack"),
+            file_name("210987654321", &Region::UsEast1, "stack")
+        );
+        assert_ne!(
+            file_name("123456789012", &Region::UsEast1, "stack"),
+            file_name("123456789012", &Region::EuWest1, "stack")
+        );
+    }
+
+    #[test]
+    fn lock_files_of_exited_processes_are_reused() -> io::Result<()> {
+        let dir = test_dir("reused-lock-test");
+        fs::create_dir_all(&dir)?;
+        // left behind by a process which exited while holding the lock
+        fs::write(dir.join("stack.lock"), format!("{}\n", i32::MAX))?;
+        let lock = Lock::acquire_in(&dir, "stack.lock")?.expect("lock should be free");
+        assert_eq!(
+            fs::read_to_string(dir.join("stack.lock"))?,
+            format!("{}\n", process::id())
+        );
+        drop(lock);
+        fs::remove_dir_all(&dir)
+    }
+}
@@ -0,0 +1,299 @@
+//! Log tails of the Lambda functions behind failed custom and Lambda resources,
+//! whose own failure reasons rarely say what went wrong
+use crate::{accounts::Accounts, template, Error, ResourceState, FAILED};
+use colored::Colorize;
+use rusoto_logs::{
+    CloudWatchLogs, CloudWatchLogsClient, DescribeLogStreamsRequest, GetLogEventsRequest,
+};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+/// Number of log events fetched from the end of a function's latest log stream
+const EVENTS: i64 = 50;
+
+/// Whether a resource is provisioned by a Lambda function
+fn custom(state: &ResourceState) -> bool {
+    state.resource_type.starts_with("Custom::")
+        || state.resource_type == "AWS::CloudFormation::CustomResource"
+}
+
+/// The name of the function which failed a resource: the function itself or,
+/// for custom resources, the function its ServiceToken refers to
+fn function_name(
+    template: Option<&Value>,
+    states: &[ResourceState],
+    state: &ResourceState,
+) -> Option<String> {
+    if state.resource_type == "AWS::Lambda::Function" {
+        return state.physical_id.clone();
+    }
+    if !custom(state) {
+        return None;
+    }
+    let token = template?
+        .get("Resources")?
+        .get(&state.resource_id)?
+        .get("Properties")?
+        .get("ServiceToken")?;
+    let function = match token {
+        Value::String(arn) => arn.clone(),
+        // tokens of functions in the same stack are usually { "Fn::GetAtt": ["Function", "Arn"] }
+        other => {
+            let target = match other.get("Fn::GetAtt")? {
+                Value::Array(parts) => parts.first()?.as_str()?,
+                Value::String(attribute) => attribute.split('.').next()?,
+                _ => return None,
+            };
+            states
+                .iter()
+                .rev()
+                .find_map(|state| match &state.physical_id {
+                    Some(id) if state.resource_id == target => Some(id.clone()),
+                    _ => None,
+                })?
+        }
+    };
+    if !function.starts_with("arn:") {
+        return Some(function);
+    }
+    // arn:aws:lambda:{region}:{account}:function:{name}, optionally with a qualifier.
+    // custom resources backed by sns topics have no function to read logs of
+    let mut fields = function.split(':');
+    if fields.nth(2)? != "lambda" {
+        return None;
+    }
+    fields.nth(3).map(String::from)
+}
+
+/// Lines of a function's most recent invocation in its latest log stream
+fn recent(
+    logs: &CloudWatchLogsClient,
+    function: &str,
+) -> Result<Vec<String>, Error> {
+    let log_group_name = format!("/aws/lambda/{}", function);
+    let stream = logs
+        .describe_log_streams(DescribeLogStreamsRequest {
+            log_group_name: log_group_name.clone(),
+            order_by: Some("LastEventTime".into()),
+            descending: Some(true),
+            limit: Some(1),
+            ..DescribeLogStreamsRequest::default()
+        })
+        .sync()?
+        .log_streams
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|stream| stream.log_stream_name);
+    let log_stream_name = match stream {
+        Some(name) => name,
+        None => return Ok(Vec::new()),
+    };
+    let messages = logs
+        .get_log_events(GetLogEventsRequest {
+            log_group_name,
+            log_stream_name,
+            limit: Some(EVENTS),
+            start_from_head: Some(false),
+            ..GetLogEventsRequest::default()
+        })
+        .sync()?
+        .events
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|event| event.message)
+        .collect::<Vec<_>>();
+    Ok(invocation(&messages))
+}
+
+/// Lines of the last invocation among log messages, the messages from its
+/// START line on. Tabs are expanded so that they don't misalign columns
+fn invocation(messages: &[String]) -> Vec<String> {
+    let start = messages
+        .iter()
+        .rposition(|message| message.starts_with("START RequestId"))
+        .unwrap_or_default();
+    messages[start..]
+        .iter()
+        .flat_map(|message| message.lines())
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.replace('\t', "  "))
+        .collect()
+}
+
+/// Identifies a failure, so that its logs are fetched once
+fn key(state: &ResourceState) -> String {
+    match &state.event_id {
+        Some(event_id) => event_id.clone(),
+        None => format!("{}@{}", state.resource_id, state.timestamp),
+    }
+}
+
+/// Log tails fetched for failed resources
+#[derive(Default)]
+pub struct Logs {
+    tails: HashMap<String, Vec<String>>,
+}
+
+impl Logs {
+    /// Fetches log tails of failures among a stack's recent states which
+    /// haven't been fetched yet. Logs which can't be fetched are noted in
+    /// their place rather than interrupting tailing
+    pub fn fetch(
+        &mut self,
+        accounts: &Accounts,
+        stack_name: &str,
+        states: &[ResourceState],
+        recent_states: &[ResourceState],
+    ) {
+        let failures = recent_states
+            .iter()
+            .filter(|state| state.status.ends_with(FAILED))
+            .filter(|state| state.resource_type == "AWS::Lambda::Function" || custom(state))
+            .filter(|state| !self.tails.contains_key(&key(state)))
+            .collect::<Vec<_>>();
+        if failures.is_empty() {
+            return;
+        }
+        let template = if failures.iter().any(|state| custom(state)) {
+            template::fetch(&accounts.client_for(stack_name), stack_name)
+                .ok()
+                .map(|(template, _)| template)
+        } else {
+            None
+        };
+        let logs = accounts.logs_client_for(stack_name);
+        for state in failures {
+            let tail = match function_name(template.as_ref(), states, state) {
+                Some(function) => recent(&logs, &function).unwrap_or_else(|err| {
+                    vec![format!("logs of {} unavailable: {}", function, err)]
+                }),
+                None => Vec::new(),
+            };
+            self.tails.insert(key(state), tail);
+        }
+    }
+
+    /// The log tail fetched for a failure, if any
+    pub fn tail(
+        &self,
+        state: &ResourceState,
+    ) -> &[String] {
+        self.tails
+            .get(&key(state))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// A line of a failed resource's function logs, indented beneath it
+pub struct LogLine<'a>(pub &'a str);
+
+impl fmt::Display for LogLine<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(f, "  {} {}", "│".bright_black(), self.0.bright_black())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+    use serde_json::json;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn function_name_follows_service_tokens() {
+        let template = json!({
+            "Resources": {
+                "Seed": {
+                    "Type": "Custom::Seed",
+                    "Properties": { "ServiceToken": { "Fn::GetAtt": ["Seeder", "Arn"] } }
+                },
+                "Shared": {
+                    "Type": "Custom::Shared",
+                    "Properties": {
+                        "ServiceToken": "arn:aws:lambda:us-east-1:111111111111:function:shared:live"
+                    }
+                },
+                "Topic": {
+                    "Type": "Custom::Topic",
+                    "Properties": {
+                        "ServiceToken": "arn:aws:sns:us-east-1:111111111111:provisioner"
+                    }
+                }
+            }
+        });
+        let states = vec![ResourceState {
+            physical_id: Some("my-stack-Seeder-1A2B3C".into()),
+            ..state("AWS::Lambda::Function", "Seeder", "CREATE_COMPLETE")
+        }];
+        let failed =
+            |resource_type, resource_id| state(resource_type, resource_id, "CREATE_FAILED");
+        assert_eq!(
+            function_name(Some(&template), &states, &failed("Custom::Seed", "Seed")).as_deref(),
+            Some("my-stack-Seeder-1A2B3C")
+        );
+        assert_eq!(
+            function_name(
+                Some(&template),
+                &states,
+                &failed("Custom::Shared", "Shared")
+            )
+            .as_deref(),
+            Some("shared")
+        );
+        assert_eq!(
+            function_name(Some(&template), &states, &failed("Custom::Topic", "Topic")),
+            None
+        );
+        assert_eq!(
+            function_name(None, &states, &states[0]).as_deref(),
+            Some("my-stack-Seeder-1A2B3C")
+        );
+        assert_eq!(
+            function_name(
+                Some(&template),
+                &states,
+                &failed("AWS::S3::Bucket", "Bucket")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn invocation_starts_at_last_start() {
+        let messages = vec![
+            "START RequestId: 1 Version: $LATEST\n".to_string(),
+            "END RequestId: 1\n".into(),
+            "START RequestId: 2 Version: $LATEST\n".into(),
+            "2019-01-01T00:00:00.000Z\t2\tERROR\tTable not found\n".into(),
+            "\n".into(),
+        ];
+        assert_eq!(
+            invocation(&messages),
+            vec![
+                "START RequestId: 2 Version: $LATEST",
+                "2019-01-01T00:00:00.000Z  2  ERROR  Table not found"
+            ]
+        );
+    }
+}
@@ -1,27 +1,260 @@
 //! Stack-tail is a CLI for visualizing the state of AWS Cloudformation stacks
-use chrono::{DateTime, FixedOffset};
+use accounts::{account_of, AccountRole, Accounts};
+use archive::S3Location;
+use cfn_hooks::Invocation;
+use changes::{Changing, Planned};
+use checkpoint::{Checkpoint, StateFile};
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, FixedOffset, TimeZone, Utc,
+};
 use chrono_tz::Tz;
-use colored::Colorize;
+use colored::{Color, Colorize};
+use config::Config;
 use console::Term;
-use futures::{stream, Future, Stream};
+use eta::History;
+use filter::{Filter, Moment, Pattern};
+use format::{Custom, LineFormat};
+use futures::{
+    future::{self, Loop},
+    stream, Future, Stream,
+};
+use icons::{Icons, IconsConfig};
+use interactive::App;
+use links::{Link, Links};
+use logs::{LogLine, Logs};
+use metadata::Header;
+use nested::{Family, Scope};
+use notify::{
+    Bell, Desktop, Discord, EventBridge, HttpHeader, Notifier, PagerDuty, Ses, Slack, Sns, Teams,
+    Trigger, Webhook,
+};
+use pacing::Pacing;
+use policy::Denials;
+use progress::{Bar, Progress};
 use rusoto_cloudformation::{
-    CloudFormation, CloudFormationClient, DescribeStackEventsError, DescribeStackEventsInput,
-    DescribeStackResourcesError, DescribeStackResourcesInput, StackEvent, StackResource,
+    CloudFormation, CloudFormationClient, DescribeChangeSetError,
+    DescribeStackDriftDetectionStatusError, DescribeStackEventsError, DescribeStackEventsInput,
+    DescribeStackResourceDriftsError, DescribeStackResourcesError, DescribeStackResourcesInput,
+    DescribeStackSetOperationError, DescribeStacksError, DescribeStacksInput,
+    DetectStackDriftError, EstimateTemplateCostError, GetStackPolicyError, GetTemplateError,
+    ListImportsError, ListStackInstancesError, ListStackSetOperationResultsError,
+    ListStackSetOperationsError, ListStacksError, Stack, StackEvent, StackResource,
+};
+use rusoto_cloudtrail::LookupEventsError;
+use rusoto_core::{
+    credential::{ChainProvider, CredentialsError},
+    request::{HttpClient, HttpDispatchError},
+    Region, RusotoError,
+};
+use rusoto_events::PutEventsError;
+use rusoto_logs::{DescribeLogStreamsError, GetLogEventsError};
+use rusoto_s3::{GetObjectError, ListObjectsV2Error, PutObjectError};
+use rusoto_ses::SendEmailError;
+use rusoto_sns::PublishError;
+use selection::TagFilter;
+use serde::{Deserialize, Serialize};
+use snapshot::Change;
+use sort::SortBy;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env,
+    error::Error as StdError,
+    ffi::OsString,
+    fmt,
+    io::{self, Write},
+    iter, mem,
+    path::PathBuf,
+    process,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
-use rusoto_core::{credential::ChainProvider, request::HttpClient, Region, RusotoError};
-use std::{error::Error as StdError, fmt, io::Write, thread::sleep, time::Duration};
 use structopt::StructOpt;
 use tabwriter::TabWriter;
+use template::Policies;
+use termion::event::{Key, MouseEvent};
+use theme::Theme;
+use tokio::{
+    runtime::Runtime,
+    timer::{Delay, Interval},
+};
+#[cfg(unix)]
+use tokio_signal::unix::Signal;
+use trail::Initiators;
 
+mod accounts;
+mod appregistry;
+mod archive;
+mod cache;
+mod cfn_hooks;
+mod changes;
+mod checkpoint;
+mod config;
+mod cost;
+mod drift;
+mod eta;
+mod explain;
+mod filter;
+mod format;
+mod github;
+mod graph;
+mod health;
+mod hooks;
+mod icons;
+mod interactive;
+mod junit;
+mod links;
+mod lock;
+mod logs;
+mod metadata;
+mod nested;
+mod notify;
+mod pacing;
+mod policy;
+mod preflight;
+mod progress;
+mod replay;
+mod rollback;
+mod selection;
+mod snapshot;
+mod sort;
+mod stackset;
+mod stats;
+mod template;
+mod theme;
+mod title;
+mod trail;
+mod wait;
+
+/// Exit code after tailing is interrupted with Ctrl-C, per shell convention
+const INTERRUPTED: i32 = 130;
+/// Exit code after following outlasts --timeout, like timeout(1)
+const TIMED_OUT: i32 = 124;
+/// Exit code when a stack ends up in a failed status
+const FAILED_STATUS: i32 = 1;
+/// Exit code when a stack ends up rolling or rolled back
+const ROLLED_BACK: i32 = 2;
+/// Exit code when a stack ends up in a status other than those expected
+const UNEXPECTED: i32 = 3;
 const STACK_RESOURCE: &str = "AWS::CloudFormation::Stack";
 const COMPLETE: &str = "_COMPLETE";
 const FAILED: &str = "_FAILED";
+const IN_PROGRESS: &str = "_IN_PROGRESS";
+const ROLLBACK_IN_PROGRESS: &str = "ROLLBACK_IN_PROGRESS";
+/// The status of a stack whose resource import failed and was rolled back,
+/// leaving none of the resources imported
+const IMPORT_ROLLBACK_COMPLETE: &str = "IMPORT_ROLLBACK_COMPLETE";
+/// Prefix of the statuses of CloudFormation hooks invoked on a resource, like
+/// `HOOK_IN_PROGRESS`. These are reported alongside the resource's own
+/// statuses rather than replacing them
+const HOOK: &str = "HOOK_";
+const HOOK_COMPLETE_SUCCEEDED: &str = "HOOK_COMPLETE_SUCCEEDED";
+/// Stack statuses which mark the start of a new stack operation
+const OPERATION_STARTS: &[&str] = &[
+    "CREATE_IN_PROGRESS",
+    "UPDATE_IN_PROGRESS",
+    "DELETE_IN_PROGRESS",
+    "IMPORT_IN_PROGRESS",
+];
 
+#[derive(Debug)]
 enum Error {
     Events(RusotoError<DescribeStackEventsError>),
     Resources(RusotoError<DescribeStackResourcesError>),
+    Stacks(RusotoError<DescribeStacksError>),
+    ListStacks(RusotoError<ListStacksError>),
+    DetectDrift(RusotoError<DetectStackDriftError>),
+    DriftStatus(RusotoError<DescribeStackDriftDetectionStatusError>),
+    ResourceDrifts(RusotoError<DescribeStackResourceDriftsError>),
+    Template(RusotoError<GetTemplateError>),
+    ChangeSet(RusotoError<DescribeChangeSetError>),
+    StackPolicy(RusotoError<GetStackPolicyError>),
+    Imports(RusotoError<ListImportsError>),
+    Cost(RusotoError<EstimateTemplateCostError>),
+    StackSetOperation(RusotoError<DescribeStackSetOperationError>),
+    StackSetOperations(RusotoError<ListStackSetOperationsError>),
+    StackSetResults(RusotoError<ListStackSetOperationResultsError>),
+    StackInstances(RusotoError<ListStackInstancesError>),
+    LogStreams(RusotoError<DescribeLogStreamsError>),
+    LogEvents(RusotoError<GetLogEventsError>),
+    Trail(RusotoError<LookupEventsError>),
+    GetObject(RusotoError<GetObjectError>),
+    PutObject(RusotoError<PutObjectError>),
+    ListObjects(RusotoError<ListObjectsV2Error>),
+    Publish(RusotoError<PublishError>),
+    PutEvents(RusotoError<PutEventsError>),
+    SendEmail(RusotoError<SendEmailError>),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    Config(toml::de::Error),
+    Sqlite(rusqlite::Error),
+    Http(reqwest::Error),
+    Credentials(CredentialsError),
+    Dispatch(HttpDispatchError),
+    Timer(tokio::timer::Error),
+    Blocking(tokio_threadpool::BlockingError),
+    AppRegistry(String),
+    Health(String),
 }
 
+impl fmt::Display for Error {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match self {
+            Error::Events(e) => write!(f, "failed to describe stack events: {}", e),
+            Error::Resources(e) => write!(f, "failed to describe stack resources: {}", e),
+            Error::Stacks(e) => write!(f, "failed to describe stack: {}", e),
+            Error::ListStacks(e) => write!(f, "failed to list stacks: {}", e),
+            Error::DetectDrift(e) => write!(f, "failed to detect stack drift: {}", e),
+            Error::DriftStatus(e) => write!(f, "failed to describe drift detection: {}", e),
+            Error::ResourceDrifts(e) => write!(f, "failed to describe resource drifts: {}", e),
+            Error::Template(e) => write!(f, "failed to get stack template: {}", e),
+            Error::ChangeSet(e) => write!(f, "failed to describe change set: {}", e),
+            Error::StackPolicy(e) => write!(f, "failed to get stack policy: {}", e),
+            Error::Imports(e) => write!(f, "failed to list export imports: {}", e),
+            Error::Cost(e) => write!(f, "failed to estimate template cost: {}", e),
+            Error::StackSetOperation(e) => {
+                write!(f, "failed to describe stack set operation: {}", e)
+            }
+            Error::StackSetOperations(e) => write!(f, "failed to list stack set operations: {}", e),
+            Error::StackSetResults(e) => {
+                write!(f, "failed to list stack set operation results: {}", e)
+            }
+            Error::StackInstances(e) => write!(f, "failed to list stack instances: {}", e),
+            Error::LogStreams(e) => write!(f, "failed to describe log streams: {}", e),
+            Error::LogEvents(e) => write!(f, "failed to get log events: {}", e),
+            Error::Trail(e) => write!(f, "failed to look up cloudtrail events: {}", e),
+            Error::GetObject(e) => write!(f, "failed to read archive from s3: {}", e),
+            Error::PutObject(e) => write!(f, "failed to write archive to s3: {}", e),
+            Error::ListObjects(e) => write!(f, "failed to list archives in s3: {}", e),
+            Error::Publish(e) => write!(f, "failed to publish to sns: {}", e),
+            Error::PutEvents(e) => write!(f, "failed to put events onto eventbridge: {}", e),
+            Error::SendEmail(e) => write!(f, "failed to send email through ses: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Json(e) => write!(f, "{}", e),
+            Error::Yaml(e) => write!(f, "{}", e),
+            Error::Config(e) => write!(f, "invalid config: {}", e),
+            Error::Sqlite(e) => write!(f, "archive database error: {}", e),
+            Error::Http(e) => write!(f, "http request failed: {}", e),
+            Error::Credentials(e) => write!(f, "failed to resolve credentials: {}", e),
+            Error::Dispatch(e) => write!(f, "failed to dispatch request: {}", e),
+            Error::Timer(e) => write!(f, "poll timer failed: {}", e),
+            Error::Blocking(e) => write!(f, "failed to run blocking work: {}", e),
+            Error::AppRegistry(e) => write!(f, "failed to list application stacks: {}", e),
+            Error::Health(e) => write!(f, "failed to describe aws health events: {}", e),
+        }
+    }
+}
+
+impl StdError for Error {}
+
 impl From<RusotoError<DescribeStackEventsError>> for Error {
     fn from(e: RusotoError<DescribeStackEventsError>) -> Self {
         Error::Events(e)
@@ -34,8 +267,227 @@ impl From<RusotoError<DescribeStackResourcesError>> for Error {
     }
 }
 
+impl From<RusotoError<DescribeStacksError>> for Error {
+    fn from(e: RusotoError<DescribeStacksError>) -> Self {
+        Error::Stacks(e)
+    }
+}
+
+impl From<RusotoError<ListStacksError>> for Error {
+    fn from(e: RusotoError<ListStacksError>) -> Self {
+        Error::ListStacks(e)
+    }
+}
+
+impl From<RusotoError<DetectStackDriftError>> for Error {
+    fn from(e: RusotoError<DetectStackDriftError>) -> Self {
+        Error::DetectDrift(e)
+    }
+}
+
+impl From<RusotoError<DescribeStackDriftDetectionStatusError>> for Error {
+    fn from(e: RusotoError<DescribeStackDriftDetectionStatusError>) -> Self {
+        Error::DriftStatus(e)
+    }
+}
+
+impl From<RusotoError<DescribeStackResourceDriftsError>> for Error {
+    fn from(e: RusotoError<DescribeStackResourceDriftsError>) -> Self {
+        Error::ResourceDrifts(e)
+    }
+}
+
+impl From<RusotoError<GetTemplateError>> for Error {
+    fn from(e: RusotoError<GetTemplateError>) -> Self {
+        Error::Template(e)
+    }
+}
+
+impl From<RusotoError<DescribeChangeSetError>> for Error {
+    fn from(e: RusotoError<DescribeChangeSetError>) -> Self {
+        Error::ChangeSet(e)
+    }
+}
+
+impl From<RusotoError<GetStackPolicyError>> for Error {
+    fn from(e: RusotoError<GetStackPolicyError>) -> Self {
+        Error::StackPolicy(e)
+    }
+}
+
+impl From<RusotoError<ListImportsError>> for Error {
+    fn from(e: RusotoError<ListImportsError>) -> Self {
+        Error::Imports(e)
+    }
+}
+
+impl From<RusotoError<EstimateTemplateCostError>> for Error {
+    fn from(e: RusotoError<EstimateTemplateCostError>) -> Self {
+        Error::Cost(e)
+    }
+}
+
+impl From<RusotoError<DescribeStackSetOperationError>> for Error {
+    fn from(e: RusotoError<DescribeStackSetOperationError>) -> Self {
+        Error::StackSetOperation(e)
+    }
+}
+
+impl From<RusotoError<ListStackSetOperationsError>> for Error {
+    fn from(e: RusotoError<ListStackSetOperationsError>) -> Self {
+        Error::StackSetOperations(e)
+    }
+}
+
+impl From<RusotoError<ListStackSetOperationResultsError>> for Error {
+    fn from(e: RusotoError<ListStackSetOperationResultsError>) -> Self {
+        Error::StackSetResults(e)
+    }
+}
+
+impl From<RusotoError<ListStackInstancesError>> for Error {
+    fn from(e: RusotoError<ListStackInstancesError>) -> Self {
+        Error::StackInstances(e)
+    }
+}
+
+impl From<RusotoError<DescribeLogStreamsError>> for Error {
+    fn from(e: RusotoError<DescribeLogStreamsError>) -> Self {
+        Error::LogStreams(e)
+    }
+}
+
+impl From<RusotoError<GetLogEventsError>> for Error {
+    fn from(e: RusotoError<GetLogEventsError>) -> Self {
+        Error::LogEvents(e)
+    }
+}
+
+impl From<RusotoError<LookupEventsError>> for Error {
+    fn from(e: RusotoError<LookupEventsError>) -> Self {
+        Error::Trail(e)
+    }
+}
+
+impl From<RusotoError<GetObjectError>> for Error {
+    fn from(e: RusotoError<GetObjectError>) -> Self {
+        Error::GetObject(e)
+    }
+}
+
+impl From<RusotoError<PutObjectError>> for Error {
+    fn from(e: RusotoError<PutObjectError>) -> Self {
+        Error::PutObject(e)
+    }
+}
+
+impl From<RusotoError<ListObjectsV2Error>> for Error {
+    fn from(e: RusotoError<ListObjectsV2Error>) -> Self {
+        Error::ListObjects(e)
+    }
+}
+
+impl From<RusotoError<PublishError>> for Error {
+    fn from(e: RusotoError<PublishError>) -> Self {
+        Error::Publish(e)
+    }
+}
+
+impl From<RusotoError<PutEventsError>> for Error {
+    fn from(e: RusotoError<PutEventsError>) -> Self {
+        Error::PutEvents(e)
+    }
+}
+
+impl From<RusotoError<SendEmailError>> for Error {
+    fn from(e: RusotoError<SendEmailError>) -> Self {
+        Error::SendEmail(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
+impl From<toml::de::Error> for Error {
+    fn from(e: toml::de::Error) -> Self {
+        Error::Config(e)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sqlite(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<CredentialsError> for Error {
+    fn from(e: CredentialsError) -> Self {
+        Error::Credentials(e)
+    }
+}
+
+impl From<HttpDispatchError> for Error {
+    fn from(e: HttpDispatchError) -> Self {
+        Error::Dispatch(e)
+    }
+}
+
+impl From<tokio_threadpool::BlockingError> for Error {
+    fn from(e: tokio_threadpool::BlockingError) -> Self {
+        Error::Blocking(e)
+    }
+}
+
+impl From<tokio::timer::Error> for Error {
+    fn from(e: tokio::timer::Error) -> Self {
+        Error::Timer(e)
+    }
+}
+
+/// How tailed resource states are reported
+#[derive(PartialEq, Debug)]
+enum Output {
+    Table,
+    Junit,
+}
+
+impl FromStr for Output {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Output::Table),
+            "junit" => Ok(Output::Junit),
+            other => Err(format!("unsupported output {}", other)),
+        }
+    }
+}
+
 #[derive(StructOpt, PartialEq, Debug)]
-#[structopt(about = "Tails AWS CloudFormation events for a given stack")]
+#[structopt(
+    about = "Tails AWS CloudFormation events for a given stack",
+    raw(setting = "structopt::clap::AppSettings::SubcommandsNegateReqs")
+)]
 struct Options {
     #[structopt(
         short = "r",
@@ -49,96 +501,1114 @@ struct Options {
         help = "Display timestamps adjusted for the provided timezone.\nSee list of supported timezones here https://en.wikipedia.org/wiki/List_of_tz_database_time_zones#List"
     )]
     timezone: Option<Tz>,
+    #[structopt(
+        long = "time-format",
+        help = "Display timestamps in a strftime format, for example %H:%M:%S"
+    )]
+    time_format: Option<TimeFormat>,
+    #[structopt(
+        long = "relative",
+        help = "Display timestamps as time elapsed, for example 42s ago"
+    )]
+    relative: bool,
     #[structopt(
         short = "f",
         long = "follow",
         help = "Follow the state of progress in changes to a stack until stack completion or failure"
     )]
     follow: bool,
-    stack_name: String,
+    #[structopt(
+        long = "forever",
+        help = "Keep following once stacks finish, reporting each following operation as it begins"
+    )]
+    forever: bool,
+    #[structopt(
+        long = "wait-for-create",
+        help = "Wait for stacks which don't exist yet to be created, then tail them"
+    )]
+    wait_for_create: bool,
+    #[structopt(
+        long = "state-file",
+        parse(from_os_str),
+        raw(conflicts_with = r#""resources""#),
+        help = "File recording the last event seen of each stack, so that re-invoking stack-tail only displays newer events"
+    )]
+    state_file: Option<PathBuf>,
+    #[structopt(
+        long = "interval",
+        default_value = "1s",
+        parse(try_from_str = "humantime::parse_duration"),
+        help = "Time between polls while following, for example 5s. Polls back off while nothing changes"
+    )]
+    interval: Duration,
+    #[structopt(
+        long = "timeout",
+        parse(try_from_str = "humantime::parse_duration"),
+        help = "Stop following with a non-zero exit code when stacks haven't finished within this time, for example 30m"
+    )]
+    timeout: Option<Duration>,
+    #[structopt(
+        long = "expect",
+        raw(number_of_values = "1", conflicts_with = r#""resources""#),
+        help = "Exit with a non-zero code unless stacks end with this status, for example CREATE_COMPLETE. May be repeated"
+    )]
+    expect: Vec<String>,
+    #[structopt(
+        short = "o",
+        long = "output",
+        default_value = "table",
+        raw(possible_values = r#"&["table", "junit"]"#),
+        help = "Output format. junit reports each resource as a test case once tailing completes"
+    )]
+    output: Output,
+    #[structopt(
+        long = "format",
+        help = "Format lines with placeholders {time}, {id}, {type}, {status} and {reason} instead of the built-in table layout"
+    )]
+    format: Option<LineFormat>,
+    #[structopt(
+        long = "status-filter",
+        help = "Only display states whose status matches a glob like *_FAILED, or a regex between slashes like /^UPDATE_/"
+    )]
+    status_filter: Option<Pattern>,
+    #[structopt(
+        long = "type",
+        raw(number_of_values = "1"),
+        help = "Only display states of resource types matching this pattern, for example AWS::IAM::*. May be repeated"
+    )]
+    types: Vec<Pattern>,
+    #[structopt(
+        long = "resource",
+        raw(number_of_values = "1"),
+        help = "Only display states of logical resource ids matching this pattern, for example Api*. May be repeated"
+    )]
+    resource_ids: Vec<Pattern>,
+    #[structopt(
+        long = "exclude-type",
+        raw(number_of_values = "1"),
+        help = "Hide states of resource types matching this pattern. May be repeated"
+    )]
+    exclude_types: Vec<Pattern>,
+    #[structopt(
+        long = "exclude-resource",
+        raw(number_of_values = "1"),
+        help = "Hide states of logical resource ids matching this pattern. May be repeated"
+    )]
+    exclude_resource_ids: Vec<Pattern>,
+    #[structopt(
+        long = "max-depth",
+        help = "Levels of nested stacks to tail beneath each stack. 0 tails no nested stacks"
+    )]
+    max_depth: Option<usize>,
+    #[structopt(
+        long = "include-nested",
+        raw(number_of_values = "1"),
+        help = "Only tail nested stacks whose logical ids or names match this pattern. May be repeated"
+    )]
+    include_nested: Vec<Pattern>,
+    #[structopt(
+        long = "exclude-nested",
+        raw(number_of_values = "1"),
+        help = "Don't tail nested stacks whose logical ids or names match this pattern. May be repeated"
+    )]
+    exclude_nested: Vec<Pattern>,
+    #[structopt(
+        long = "failed-only",
+        help = "Only display failed states and the stack's final status, to see what broke"
+    )]
+    failed_only: bool,
+    #[structopt(
+        long = "since",
+        help = "Only display states since a time, like 30m or an RFC 3339 timestamp"
+    )]
+    since: Option<Moment>,
+    #[structopt(
+        long = "until",
+        help = "Only display states until a time, like 10m or an RFC 3339 timestamp"
+    )]
+    until: Option<Moment>,
+    #[structopt(
+        short = "n",
+        long = "lines",
+        help = "Only display the most recent number of states, like tail -n"
+    )]
+    lines: Option<usize>,
+    #[structopt(
+        long = "current-operation",
+        raw(conflicts_with = r#""resources""#),
+        help = "Only display events of the stack's most recent operation rather than its whole history"
+    )]
+    current_operation: bool,
+    #[structopt(
+        long = "transitions-only",
+        help = "Hide events repeating their resource's previous status, only displaying status transitions"
+    )]
+    transitions_only: bool,
+    #[structopt(
+        long = "physical-ids",
+        help = "Display each resource's physical id next to its logical id, for pasting into other aws commands"
+    )]
+    physical_ids: bool,
+    #[structopt(
+        long = "links",
+        help = "Link resources to their aws console pages, as terminal hyperlinks or urls when output isn't a terminal"
+    )]
+    links: bool,
+    #[structopt(
+        long = "logs",
+        help = "Print the latest logs of the Lambda function behind each failed custom or Lambda resource beneath it"
+    )]
+    logs: bool,
+    #[structopt(
+        long = "who",
+        raw(conflicts_with = r#""resources""#),
+        help = "Display who initiated each stack operation, according to CloudTrail"
+    )]
+    who: bool,
+    #[structopt(
+        long = "full-reasons",
+        help = "Print long reasons in full, wrapped on continuation lines beneath their event"
+    )]
+    full_reasons: bool,
+    #[structopt(
+        long = "metadata",
+        help = "Print a header of each stack's tags, service role, termination protection, rollback alarms, capabilities and creation time before its events"
+    )]
+    metadata: bool,
+    #[structopt(
+        long = "headers",
+        raw(conflicts_with_all = r#"&["format", "tui"]"#),
+        help = "Head the table with a row naming its columns"
+    )]
+    headers: bool,
+    #[structopt(
+        long = "legend",
+        raw(requires = r#""headers""#),
+        help = "Print a line explaining the status icons above the table's header row"
+    )]
+    legend: bool,
+    #[structopt(
+        long = "sort-by",
+        raw(possible_values = r#"&["status", "type", "name", "timestamp"]"#),
+        raw(requires = r#""resources""#),
+        help = "Order resources by status, type, name or timestamp. status orders failures first"
+    )]
+    sort_by: Option<SortBy>,
+    #[structopt(long = "reverse", help = "Reverse the order of --sort-by")]
+    reverse: bool,
+    #[structopt(
+        long = "diff-since",
+        raw(requires = r#""resources""#),
+        help = "Mark resources which changed status, appeared or disappeared since a time, like 2h or an RFC 3339 timestamp, according to archived events"
+    )]
+    diff_since: Option<Moment>,
+    #[structopt(
+        long = "notify-on",
+        raw(use_delimiter = "true"),
+        raw(possible_values = r#"&["first-failure", "failure", "rollback-start", "complete"]"#),
+        raw(conflicts_with = r#""resources""#),
+        help = "Ring the terminal bell as soon as the current operation hits any of these comma separated conditions"
+    )]
+    notify_on: Vec<Trigger>,
+    #[structopt(
+        long = "notify",
+        raw(conflicts_with = r#""resources""#),
+        help = "Ring the terminal bell and fire a desktop notification when the current operation completes"
+    )]
+    notify: bool,
+    #[structopt(
+        long = "notify-slack",
+        raw(conflicts_with = r#""resources""#),
+        help = "Slack incoming webhook url to post a message to when the current operation completes, with its status, duration and root cause"
+    )]
+    notify_slack: Option<String>,
+    #[structopt(
+        long = "notify-slack-failures",
+        raw(requires = r#""notify_slack""#),
+        help = "Also post a message to Slack for each failed resource"
+    )]
+    notify_slack_failures: bool,
+    #[structopt(
+        long = "notify-teams",
+        raw(conflicts_with = r#""resources""#),
+        help = "Microsoft Teams incoming webhook url to post a message card to when the current operation completes"
+    )]
+    notify_teams: Option<String>,
+    #[structopt(
+        long = "notify-discord",
+        raw(conflicts_with = r#""resources""#),
+        help = "Discord webhook url to post a message to when the current operation completes"
+    )]
+    notify_discord: Option<String>,
+    #[structopt(
+        long = "pagerduty-routing-key",
+        raw(conflicts_with = r#""resources""#),
+        help = "PagerDuty Events API v2 routing key to trigger an event with when the current operation fails or rolls back"
+    )]
+    pagerduty_routing_key: Option<String>,
+    #[structopt(
+        long = "email",
+        raw(requires = r#""ses""#),
+        raw(number_of_values = "1"),
+        help = "Address to email a summary of the current operation to when it completes, with its status, duration, failed resources and a console link. May be repeated"
+    )]
+    email: Vec<String>,
+    #[structopt(
+        long = "email-from",
+        raw(requires = r#""email""#),
+        help = "Verified SES address emails are sent from. Defaults to the first --email address"
+    )]
+    email_from: Option<String>,
+    #[structopt(
+        long = "ses",
+        raw(requires = r#""email""#),
+        help = "Send --email summaries through SES"
+    )]
+    ses: bool,
+    #[structopt(
+        long = "archive",
+        raw(conflicts_with = r#""resources""#),
+        help = "S3 url, like s3://bucket/prefix/, to upload the run's complete event log and a summary of it to as newline delimited JSON once tailing completes"
+    )]
+    archive_to: Option<S3Location>,
+    #[structopt(
+        long = "notify-sns",
+        raw(conflicts_with = r#""resources""#),
+        help = "SNS topic arn to publish a JSON document describing the stack's final state to when the current operation completes"
+    )]
+    notify_sns: Option<String>,
+    #[structopt(
+        long = "webhook",
+        raw(conflicts_with = r#""resources""#),
+        help = "URL to POST a JSON document of each new event, and of the current operation's completion, to"
+    )]
+    webhook: Option<String>,
+    #[structopt(
+        long = "webhook-header",
+        raw(requires = r#""webhook""#),
+        raw(number_of_values = "1"),
+        help = "Header to send with webhook requests, like 'Authorization: Bearer xxx'. May be repeated"
+    )]
+    webhook_headers: Vec<HttpHeader>,
+    #[structopt(
+        long = "emit-eventbridge",
+        raw(conflicts_with = r#""resources""#),
+        help = "EventBridge bus name or arn to put a stack-status-change event from source stack-tail onto when the current operation completes"
+    )]
+    emit_eventbridge: Option<String>,
+    #[structopt(
+        long = "no-color",
+        help = "Disable colored output. Also disabled when the NO_COLOR environment variable is set"
+    )]
+    no_color: bool,
+    #[structopt(
+        long = "ascii",
+        help = "Mark statuses with [OK], [FAIL], [DEL] and [..] instead of emoji"
+    )]
+    ascii: bool,
+    #[structopt(
+        long = "plain",
+        raw(conflicts_with_all = r#"&["split", "tui"]"#),
+        help = "Write strictly linear, unstyled text for screen readers and simple log processors, spelling out statuses as FAILED:, COMPLETE: and the like rather than marking them with emoji"
+    )]
+    plain: bool,
+    #[structopt(
+        long = "split",
+        raw(conflicts_with_all = r#"&["resources", "tui"]"#),
+        help = "Head the event log with the latest status of each resource, keeping the most recent events that fit beneath it"
+    )]
+    split: bool,
+    #[structopt(
+        long = "tui",
+        raw(conflicts_with_all = r#"&["resources", "output"]"#),
+        help = "Follow stacks in an interactive full-screen interface, with a pane of resources above a scrollable pane of events"
+    )]
+    tui: bool,
+    #[structopt(
+        long = "check-health",
+        help = "Once tailing completes, check AWS Health for open issues with the services of failed resources"
+    )]
+    check_health: bool,
+    #[structopt(
+        long = "application",
+        help = "Tail all stacks associated with a Service Catalog AppRegistry application"
+    )]
+    application: Option<String>,
+    #[structopt(
+        long = "tag",
+        raw(number_of_values = "1"),
+        help = "Tail all stacks tagged with this key=value pair, for example team=payments. May be repeated"
+    )]
+    tags: Vec<TagFilter>,
+    #[structopt(
+        long = "match",
+        raw(number_of_values = "1"),
+        help = "Tail all stacks whose names match this glob, or regex between slashes, for example prod-api-*. May be repeated"
+    )]
+    matches: Vec<Pattern>,
+    #[structopt(
+        long = "no-cache",
+        help = "Select stacks by --match and --tag afresh rather than reuse those listed by a recent run"
+    )]
+    no_cache: bool,
+    #[structopt(
+        long = "region",
+        raw(number_of_values = "1"),
+        help = "Tail stacks in this region rather than the default one. May be repeated to tail stacks deployed to several regions side by side"
+    )]
+    regions: Vec<Region>,
+    #[structopt(
+        long = "account",
+        raw(number_of_values = "1"),
+        help = "Assume this role, like 111111111111:role/Deployer, to tail stacks in its account. May be repeated to tail several accounts side by side"
+    )]
+    account_roles: Vec<AccountRole>,
+    #[structopt(
+        raw(required_unless_one = r#"&["application", "tags", "matches"]"#),
+        help = "Names of stacks to tail concurrently"
+    )]
+    stack_names: Vec<String>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(StructOpt, PartialEq, Debug)]
+enum Command {
+    #[structopt(
+        name = "cost",
+        about = "Estimates the monthly cost of a stack's template, or a template file, before deploying it"
+    )]
+    Cost(cost::Options),
+    #[structopt(
+        name = "drift",
+        about = "Detects drift across stacks and reports on the results"
+    )]
+    Drift(drift::Options),
+    #[structopt(
+        name = "explain",
+        about = "Explains what a stack status means, its typical causes and how to recover"
+    )]
+    Explain(explain::Options),
+    #[structopt(
+        name = "graph",
+        about = "Renders a stack's resource dependency graph colored by resource status"
+    )]
+    Graph(graph::Options),
+    #[structopt(
+        name = "preflight",
+        about = "Reports whether a change set is safe to execute, from its changes and the stack's drift, policy, protection and exports"
+    )]
+    Preflight(preflight::Options),
+    #[structopt(name = "policy", about = "Prints a stack's policy")]
+    Policy(policy::Options),
+    #[structopt(
+        name = "replay",
+        about = "Replays recorded stack events and their notifications, for incident response drills"
+    )]
+    Replay(replay::Options),
+    #[structopt(
+        name = "stackset",
+        about = "Follows a StackSet operation across the accounts and regions of its instances"
+    )]
+    StackSet(stackset::Options),
+    #[structopt(
+        name = "stats",
+        about = "Reports per-resource duration distributions across recent archived operations"
+    )]
+    Stats(stats::Options),
+    #[structopt(
+        name = "wait",
+        about = "Waits quietly for a stack's status, as a drop-in replacement for aws cloudformation wait"
+    )]
+    Wait(wait::Options),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct ResourceState {
+    /// Unique id of the event this state was derived from, if any
+    #[serde(default)]
+    event_id: Option<String>,
     resource_type: String,
     timestamp: DateTime<FixedOffset>,
     status: String,
     resource_id: String,
+    /// Physical id of the resource, once it's known
+    #[serde(default)]
+    physical_id: Option<String>,
     reason: String,
 }
 
+/// A strftime format for timestamps, for example `%H:%M:%S`
+#[derive(PartialEq, Debug, Clone)]
+struct TimeFormat(String);
+
+impl FromStr for TimeFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if StrftimeItems::new(s).any(|item| item == Item::Error) {
+            return Err(format!("invalid time format {}", s));
+        }
+        Ok(TimeFormat(s.into()))
+    }
+}
+
+/// How timestamps are displayed
+#[derive(PartialEq, Debug, Clone, Default)]
+struct Clock {
+    /// Timezone timestamps are adjusted for, when provided
+    timezone: Option<Tz>,
+    /// Display time elapsed since, for example 42s ago
+    relative: bool,
+    /// Format timestamps are displayed in, defaulting to RFC 3339
+    format: Option<TimeFormat>,
+}
+
+impl Clock {
+    fn display<Z: TimeZone>(
+        &self,
+        timestamp: DateTime<Z>,
+    ) -> String
+    where
+        Z::Offset: fmt::Display,
+    {
+        match &self.format {
+            Some(TimeFormat(format)) => timestamp.format(format).to_string(),
+            None => timestamp.to_string(),
+        }
+    }
+
+    /// A timestamp displayed in this clock's timezone, format or relative to now
+    fn timestamp(
+        &self,
+        timestamp: DateTime<FixedOffset>,
+    ) -> String {
+        if self.relative {
+            return ago(timestamp, Utc::now());
+        }
+        match self.timezone {
+            None => self.display(timestamp),
+            Some(tz) => self.display(timestamp.with_timezone(&tz)),
+        }
+    }
+}
+
+/// Time elapsed between a timestamp and now, for example 42s ago
+fn ago(
+    timestamp: DateTime<FixedOffset>,
+    now: DateTime<Utc>,
+) -> String {
+    format!("{} ago", humanize(now.signed_duration_since(timestamp)))
+}
+
+/// Returns true for statuses left by failures, including an import's rollback
+/// which, unlike an update's, leaves nothing changed, and failed hooks
+fn is_failure(status: &str) -> bool {
+    status.ends_with(FAILED) || status == IMPORT_ROLLBACK_COMPLETE
+}
+
+/// Returns true for the statuses of hooks rather than of resources themselves
+fn is_hook(status: &str) -> bool {
+    status.starts_with(HOOK)
+}
+
 impl ResourceState {
+    /// Timestamp displayed according to a given clock
+    fn timestamp(
+        &self,
+        clock: &Clock,
+    ) -> String {
+        clock.timestamp(self.timestamp)
+    }
+
     fn complete_or_failed(&self) -> bool {
-        self.status.ends_with(COMPLETE) || self.status.ends_with(FAILED)
+        // a hook finishing leaves the resource's own operation underway
+        !is_hook(&self.status) && (self.status.ends_with(COMPLETE) || self.status.ends_with(FAILED))
     }
 
     fn is_stack(&self) -> bool {
         self.resource_type == STACK_RESOURCE
     }
+
+    /// Returns true for the stack's own event marking where a failed
+    /// operation pivots into rolling back its changes
+    fn is_rollback_pivot(
+        &self,
+        stack_name: &str,
+    ) -> bool {
+        self.is_stack()
+            && self.resource_id == stack_logical_id(stack_name)
+            && self.status.ends_with(ROLLBACK_IN_PROGRESS)
+    }
+
+    /// Returns true for the stack's own event marking the start of a new operation
+    fn is_operation_start(
+        &self,
+        stack_name: &str,
+    ) -> bool {
+        self.is_stack()
+            && self.resource_id == stack_logical_id(stack_name)
+            && OPERATION_STARTS.contains(&self.status.as_str())
+    }
+}
+
+/// Glyphs marking resource statuses
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Markers {
+    Emoji,
+    /// Plain markers for consoles without emoji fonts
+    Ascii,
+    /// Configured icons in place of either of the built in sets
+    Custom(Icons),
+    /// Spelled out status families, like `FAILED:`, for screen readers
+    Plain,
+}
+
+impl Markers {
+    /// Markers with any icons configured in place of the built in ones
+    fn configured(
+        ascii: bool,
+        config: IconsConfig,
+    ) -> Markers {
+        let base = if ascii {
+            Markers::Ascii
+        } else {
+            Markers::Emoji
+        };
+        if config == IconsConfig::default() {
+            base
+        } else {
+            Markers::Custom(Icons::configured(base.icons(), config))
+        }
+    }
+
+    /// The built in set custom icons build on
+    fn base(self) -> Markers {
+        match self {
+            Markers::Custom(icons) if icons.ascii => Markers::Ascii,
+            Markers::Custom(_) => Markers::Emoji,
+            Markers::Plain => Markers::Ascii,
+            base => base,
+        }
+    }
+
+    fn icons(self) -> Icons {
+        match self {
+            Markers::Emoji => Icons {
+                ascii: false,
+                complete: "✅",
+                deleted: "⚰️ ",
+                imported: "📥",
+                failed: "❌",
+                in_progress: "🔄",
+                hook: "🪝",
+            },
+            Markers::Ascii => Icons {
+                ascii: true,
+                complete: "[OK]",
+                deleted: "[DEL]",
+                imported: "[IMP]",
+                failed: "[FAIL]",
+                in_progress: "[..]",
+                hook: "[HOOK]",
+            },
+            Markers::Custom(icons) => icons,
+            Markers::Plain => Icons {
+                ascii: true,
+                complete: "COMPLETE:",
+                deleted: "DELETED:",
+                imported: "IMPORTED:",
+                failed: "FAILED:",
+                in_progress: "IN PROGRESS:",
+                hook: "HOOK:",
+            },
+        }
+    }
+
+    fn status(
+        self,
+        status: &str,
+    ) -> &'static str {
+        let icons = self.icons();
+        match status {
+            failed_status if is_failure(failed_status) => icons.failed,
+            HOOK_COMPLETE_SUCCEEDED => icons.complete,
+            hook if is_hook(hook) => icons.hook,
+            "IMPORT_COMPLETE" => icons.imported,
+            complete_or_deleted if complete_or_deleted.ends_with(COMPLETE) => {
+                if complete_or_deleted.starts_with("DELETE") {
+                    icons.deleted
+                } else {
+                    icons.complete
+                }
+            }
+            _ => icons.in_progress,
+        }
+    }
+
+    fn pivot(self) -> &'static str {
+        match self.base() {
+            Markers::Ascii => "<< -------- rolling back --------",
+            _ => "⏪ ──────── rolling back ────────",
+        }
+    }
+
+    /// A frame of a spinner, advancing each second
+    fn spinner(
+        self,
+        now: DateTime<Utc>,
+    ) -> &'static str {
+        let frames: &[&'static str] = match self.base() {
+            Markers::Ascii => &["|", "/", "-", "\\"],
+            _ => &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+        };
+        frames[now.timestamp().rem_euclid(frames.len() as i64) as usize]
+    }
+
+    fn divider(self) -> &'static str {
+        match self.base() {
+            Markers::Ascii => "-------- events --------",
+            _ => "──────── events ────────",
+        }
+    }
+
+    /// A line explaining what each status icon marks
+    fn legend(self) -> String {
+        let icons = self.icons();
+        [
+            (icons.complete, "complete"),
+            (icons.deleted, "deleted"),
+            (icons.imported, "imported"),
+            (icons.failed, "failed"),
+            (icons.in_progress, "in progress"),
+            (icons.hook, "hook running"),
+        ]
+        .iter()
+        .map(|(icon, family)| format!("{} {}", icon.trim_end(), family))
+        .collect::<Vec<_>>()
+        .join("  ")
+    }
+
+    fn separator(self) -> &'static str {
+        match self.base() {
+            Markers::Ascii => ">> -------- next operation --------",
+            _ => "⏩ ──────── next operation ────────",
+        }
+    }
+}
+
+/// Divides the forward changes of a failed operation from its rollback
+struct Pivot<'a>(&'a ResourceState, Markers, Theme);
+
+impl fmt::Display for Pivot<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Pivot(state, markers, theme) = self;
+        write!(f, "{} ", markers.pivot().bold().color(theme.warning))?;
+        let alarms = rollback::alarms(&state.reason);
+        if !alarms.is_empty() {
+            write!(
+                f,
+                "{} {} ",
+                "triggered by alarm".bold().color(theme.failure),
+                alarms.join(", ").bold()
+            )?;
+        }
+        write!(f, "{}", theme.muted(&state.reason))
+    }
+}
+
+/// Divides one operation on a stack from the next
+struct Separator<'a>(&'a ResourceState, Markers, Theme);
+
+impl fmt::Display for Separator<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Separator(state, markers, theme) = self;
+        write!(
+            f,
+            "{} {}",
+            markers.separator().bold().color(theme.accent),
+            theme.muted(&state.reason)
+        )
+    }
+}
+
+/// A failure within a nested stack which caused its parent's failure, indented by
+/// how deep in the chain of causes it is
+struct Cause<'a>(&'a ResourceState, usize, Theme);
+
+impl fmt::Display for Cause<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Cause(state, depth, theme) = self;
+        write!(
+            f,
+            "{}{} {} {} {}",
+            "  ".repeat(*depth + 1),
+            "caused by:".bold().color(theme.failure),
+            state.resource_id.bold(),
+            state.status.color(theme.failure),
+            theme.muted(&state.reason)
+        )
+    }
+}
+
+/// Reduces a series of resource states to the latest state of each resource,
+/// in order of each resource's first appearance
+fn latest(states: Vec<ResourceState>) -> Vec<ResourceState> {
+    let mut positions = HashMap::new();
+    let mut latest: Vec<ResourceState> = Vec::new();
+    for state in states {
+        match positions.get(&state.resource_id) {
+            Some(&position) => latest[position] = state,
+            None => {
+                positions.insert(state.resource_id.clone(), latest.len());
+                latest.push(state);
+            }
+        }
+    }
+    latest
+}
+
+/// Accumulates a polled page of a stack's most recent events into those already
+/// known, keeping each event once. Pages without event ids, like those of the
+/// stack status fallback, replace what's known
+fn accumulate(
+    known: Vec<ResourceState>,
+    page: Vec<ResourceState>,
+) -> Vec<ResourceState> {
+    if page.iter().any(|state| state.event_id.is_none()) {
+        return page;
+    }
+    archive::merge(known, &page)
+}
+
+/// The logical id of a stack's own events, given a stack name or id
+fn stack_logical_id(stack_name: &str) -> &str {
+    // stack ids take the form arn:aws:cloudformation:{region}:{account}:stack/{name}/{uuid}
+    match stack_name.split('/').nth(1) {
+        Some(name) if stack_name.starts_with("arn:") => name,
+        _ => stack_name,
+    }
+}
+
+/// The failure which set off a stack's most recent operation failing: its
+/// first resource failure, skipping the cascade of cancellations it causes,
+/// followed down into nested stacks
+fn root_cause<'a>(
+    stack_name: &str,
+    states: &'a [ResourceState],
+) -> Option<&'a ResourceState> {
+    let first = current_operation(stack_name, states)
+        .iter()
+        .filter(|state| {
+            state.status.ends_with(FAILED)
+                && !(state.is_stack() && state.resource_id == stack_logical_id(stack_name))
+                && !state.reason.contains("cancelled")
+        })
+        .min_by_key(|state| state.timestamp)?;
+    nested::causes(states, first)
+        .last()
+        .cloned()
+        .or(Some(first))
+}
+
+/// A footer identifying a stack's root cause failure, with its full reason
+struct RootCause<'a>(Option<&'a str>, &'a ResourceState, Theme);
+
+impl fmt::Display for RootCause<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let RootCause(stack_name, state, theme) = self;
+        write!(f, "{} ", "ROOT CAUSE".bold().color(theme.failure))?;
+        if let Some(stack_name) = stack_name {
+            write!(f, "{} ", stack_logical_id(stack_name).bold())?;
+        }
+        writeln!(
+            f,
+            "{} {} {}",
+            state.resource_id.bold(),
+            theme.muted(&state.resource_type),
+            state.status.bold().color(theme.failure)
+        )?;
+        write!(f, "  {}", state.reason)
+    }
+}
+
+/// Narrows a chronological series of stack events to those of the stack's
+/// most recent operation
+fn current_operation<'a>(
+    stack_name: &str,
+    states: &'a [ResourceState],
+) -> &'a [ResourceState] {
+    let start = states
+        .iter()
+        .rposition(|state| state.is_operation_start(stack_name))
+        .unwrap_or_default();
+    &states[start..]
+}
+
+/// Splits a chronological series of stack events into the stack's operations.
+/// Events preceding the first recorded operation start are grouped together
+fn operations<'a>(
+    stack_name: &str,
+    states: &'a [ResourceState],
+) -> Vec<&'a [ResourceState]> {
+    let mut starts = states
+        .iter()
+        .enumerate()
+        .filter(|(_, state)| state.is_operation_start(stack_name))
+        .map(|(position, _)| position)
+        .collect::<Vec<_>>();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain(iter::once(&states.len())))
+        .map(|(&start, &end)| &states[start..end])
+        .filter(|operation| !operation.is_empty())
+        .collect()
+}
+
+/// Time taken by each resource within an operation, from its first in progress
+/// event to its final complete or failed event
+fn durations(operation: &[ResourceState]) -> HashMap<&str, chrono::Duration> {
+    let mut started = HashMap::new();
+    let mut durations = HashMap::new();
+    for state in operation {
+        let resource_id = state.resource_id.as_str();
+        if state.status.ends_with(IN_PROGRESS) {
+            started.entry(resource_id).or_insert(state.timestamp);
+        } else if state.complete_or_failed() {
+            if let Some(start) = started.get(resource_id) {
+                durations.insert(resource_id, state.timestamp - *start);
+            }
+        }
+    }
+    durations
+}
+
+/// Whether each state repeats the previous status of the same resource,
+/// like heartbeat in progress events
+fn repeats(states: &[ResourceState]) -> Vec<bool> {
+    let mut statuses = HashMap::new();
+    states
+        .iter()
+        .map(|state| {
+            statuses.insert(state.resource_id.as_str(), state.status.as_str())
+                == Some(state.status.as_str())
+        })
+        .collect()
+}
+
+/// Time each state's resource took to reach it, for complete or failed states
+/// following an in progress state of the same resource within an operation
+fn elapsed(
+    stack_name: &str,
+    states: &[ResourceState],
+) -> Vec<Option<chrono::Duration>> {
+    let mut started = HashMap::new();
+    states
+        .iter()
+        .map(|state| {
+            if state.is_operation_start(stack_name) {
+                started.clear();
+            }
+            let resource_id = state.resource_id.as_str();
+            if state.status.ends_with(IN_PROGRESS) {
+                started.entry(resource_id).or_insert(state.timestamp);
+                None
+            } else if state.complete_or_failed() {
+                // rollbacks of a resource are timed separately
+                started
+                    .remove(resource_id)
+                    .map(|start| state.timestamp - start)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Width reasons are wrapped to with --full-reasons, in characters
+const REASON_WIDTH: usize = 60;
+
+/// Breaks text into lines of at most a given width, between words where
+/// possible
+fn wrap(
+    text: &str,
+    width: usize,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+        // words longer than a line, like arns, are broken where they must be
+        while word.len() > width {
+            if !line.is_empty() {
+                lines.push(mem::take(&mut line));
+            }
+            let rest = word.split_off(width);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+        let word = word.into_iter().collect::<String>();
+        let length = line.chars().count();
+        if length > 0 && length + 1 + word.chars().count() > width {
+            lines.push(mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Renders a duration compactly, for example 3m12s
+fn humanize(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
 }
 
 /// Provides a means of displaying resource state
-/// with time formatted for a given timezone
-/// when provided
-struct Formatted(ResourceState, Option<Tz>);
+/// with time formatted according to a given clock
+/// and a column of additional detail, like the time
+/// taken to reach that state, along with optional columns
+struct Formatted<'a>(ResourceState, &'a Clock, Markers, String, Columns, Theme);
+
+/// Optional columns of resource state lines
+#[derive(Default)]
+struct Columns {
+    /// Whether a column of physical ids follows logical ids
+    physical_ids: bool,
+    /// A link to the resource's console page, ending the line
+    link: Option<(Links, String)>,
+}
 
-impl fmt::Display for Formatted {
+impl fmt::Display for Formatted<'_> {
     fn fmt(
         &self,
         f: &mut fmt::Formatter,
     ) -> fmt::Result {
-        let Formatted(state, timezone) = self;
-        let timestamp = match timezone {
-            None => state.timestamp.to_string(),
-            Some(tz) => state.timestamp.with_timezone(tz).to_string(),
-        };
+        let Formatted(state, clock, markers, detail, columns, theme) = self;
         write!(
             f,
-            "{}\t{}\t{}\t{}\t{}",
-            timestamp,
+            "{}\t{}\t{}{}\t{} {}\t{}\t{}",
+            state.timestamp(clock),
             state.resource_id.bold(),
-            state.resource_type.bright_black(),
-            match &state.status[..] {
-                complete if complete.ends_with(COMPLETE) => format!(
-                    "{} {}",
-                    if complete.starts_with("DELETE") {
-                        "⚰️ "
-                    } else {
-                        "✅"
-                    },
-                    state.status.bold().bright_green()
-                ),
-                failed if failed.ends_with(FAILED) => {
-                    format!("❌ {}", state.status.bold().bright_red())
-                }
-                _ => format!("🔄 {}", state.status),
+            if columns.physical_ids {
+                format!("{}\t", state.physical_id.as_deref().unwrap_or("-"))
+            } else {
+                String::new()
             },
-            state.reason.bright_black()
-        )
+            theme.muted(&state.resource_type),
+            markers.status(&state.status),
+            theme.status(&state.status),
+            detail,
+            theme.muted(&state.reason)
+        )?;
+        match &columns.link {
+            Some((links, url)) => write!(f, "\t{}", Link(*links, url, *markers)),
+            None => Ok(()),
+        }
     }
 }
 
 impl From<StackEvent> for ResourceState {
     fn from(e: StackEvent) -> Self {
         ResourceState {
+            event_id: Some(e.event_id),
             resource_type: e.resource_type.unwrap_or_default(),
             timestamp: DateTime::parse_from_rfc3339(&e.timestamp).expect("invalid timestamp"),
             status: e.resource_status.unwrap_or_default(),
             resource_id: e.logical_resource_id.unwrap_or_default(),
+            physical_id: e.physical_resource_id.filter(|id| !id.is_empty()),
+            reason: e.resource_status_reason.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<StackResource> for ResourceState {
+    fn from(e: StackResource) -> Self {
+        ResourceState {
+            event_id: None,
+            resource_type: e.resource_type,
+            timestamp: DateTime::parse_from_rfc3339(&e.timestamp).expect("invalid timestamp"),
+            status: e.resource_status,
+            resource_id: e.logical_resource_id,
+            physical_id: e.physical_resource_id.filter(|id| !id.is_empty()),
             reason: e.resource_status_reason.unwrap_or_default(),
         }
     }
 }
 
-impl From<StackResource> for ResourceState {
-    fn from(e: StackResource) -> Self {
-        ResourceState {
-            resource_type: e.resource_type,
-            timestamp: DateTime::parse_from_rfc3339(&e.timestamp).expect("invalid timestamp"),
-            status: e.resource_status,
-            resource_id: e.logical_resource_id,
-            reason: e.resource_status_reason.unwrap_or_default(),
+impl From<Stack> for ResourceState {
+    fn from(s: Stack) -> Self {
+        ResourceState {
+            event_id: None,
+            resource_type: STACK_RESOURCE.into(),
+            timestamp: DateTime::parse_from_rfc3339(
+                s.last_updated_time.as_ref().unwrap_or(&s.creation_time),
+            )
+            .expect("invalid timestamp"),
+            status: s.stack_status,
+            resource_id: s.stack_name,
+            physical_id: s.stack_id,
+            reason: s.stack_status_reason.unwrap_or_default(),
+        }
+    }
+}
+
+/// Returns true when an api call was rejected for lack of permission
+fn access_denied<E>(err: &RusotoError<E>) -> bool {
+    match err {
+        RusotoError::Unknown(response) => {
+            response.status.as_u16() == 403
+                || String::from_utf8_lossy(&response.body).contains("AccessDenied")
+        }
+        _ => false,
+    }
+}
+
+/// Returns true when an api call failed because the stack doesn't exist
+fn missing_stack<E>(err: &RusotoError<E>) -> bool {
+    match err {
+        RusotoError::Unknown(response) => {
+            String::from_utf8_lossy(&response.body).contains("does not exist")
         }
+        _ => false,
+    }
+}
+
+/// The final event of a stack deleted while following it, which CloudFormation
+/// stops reporting events for once it no longer exists
+fn deleted(stack_name: &str) -> ResourceState {
+    let id = stack_logical_id(stack_name);
+    ResourceState {
+        event_id: Some(format!("{}-no-longer-exists", id)),
+        resource_type: STACK_RESOURCE.into(),
+        timestamp: Utc::now().with_timezone(&FixedOffset::east(0)),
+        status: "DELETE_COMPLETE".into(),
+        resource_id: id.into(),
+        physical_id: None,
+        reason: "stack no longer exists".into(),
     }
 }
 
@@ -171,23 +1641,56 @@ impl State {
     }
 }
 
+/// Runs blocking work, like synchronous api calls, where it won't stall the
+/// runtime's threads polling other stacks
+fn blocking<T>(work: impl FnOnce() -> T) -> impl Future<Item = T, Error = Error> {
+    let mut work = Some(work);
+    future::poll_fn(move || {
+        tokio_threadpool::blocking(|| match work.take() {
+            Some(work) => work(),
+            None => panic!("blocking work polled after completion"),
+        })
+        .map_err(Error::from)
+    })
+}
+
+/// Timer for the next poll of a stack, immediate for the first
+fn pause(
+    state: &State,
+    pacing: &Mutex<Pacing>,
+) -> Delay {
+    let delay = match (state, pacing.lock()) {
+        (State::Next(_, _), Ok(pacing)) => pacing.delay(),
+        _ => Duration::from_secs(0),
+    };
+    Delay::new(Instant::now() + delay)
+}
+
 fn fetch_resources(
     cf: CloudFormationClient,
     stack_name: String,
     follow: bool,
+    forever: bool,
+    interval: Duration,
 ) -> impl Stream<Item = (usize, Vec<ResourceState>), Error = Error> {
+    let pacing = Arc::new(Mutex::new(Pacing::new(interval)));
     stream::unfold(State::Init(follow), move |state| {
         if state.complete() {
             return None;
         }
-        if let State::Next(_, _) = state {
-            sleep(Duration::from_secs(1));
-        }
+        let delay = pause(&state, &pacing);
+        let pacing = pacing.clone();
+        let cf = cf.clone();
+        let stack_name = stack_name.clone();
         Some(
-            cf.clone()
-                .describe_stack_resources(DescribeStackResourcesInput {
-                    stack_name: Some(stack_name.clone()),
-                    ..DescribeStackResourcesInput::default()
+            delay
+                .map_err(Error::from)
+                .and_then(move |_| {
+                    cf.describe_stack_resources(DescribeStackResourcesInput {
+                        stack_name: Some(stack_name),
+                        ..DescribeStackResourcesInput::default()
+                    })
+                    .map_err(Error::from)
                 })
                 .map(move |result| {
                     let states = result
@@ -196,15 +1699,19 @@ fn fetch_resources(
                         .into_iter()
                         .map(ResourceState::from)
                         .collect::<Vec<_>>();
+                    if let Ok(mut pacing) = pacing.lock() {
+                        pacing.observe(&states);
+                    }
                     (
                         (state.prev_len(), states.clone()),
                         State::Next(
-                            state.follow() && !states.iter().all(ResourceState::complete_or_failed),
+                            state.follow()
+                                && (forever
+                                    || !states.iter().all(ResourceState::complete_or_failed)),
                             states.len(),
                         ),
                     )
-                })
-                .map_err(Error::from),
+                }),
         )
     })
 }
@@ -213,45 +1720,149 @@ fn fetch_events(
     cf: CloudFormationClient,
     stack_name: String,
     follow: bool,
+    forever: bool,
+    interval: Duration,
 ) -> impl Stream<Item = (usize, Vec<ResourceState>), Error = Error> {
+    let pacing = Arc::new(Mutex::new(Pacing::new(interval)));
     stream::unfold(State::Init(follow), move |state| {
         if state.complete() {
             return None;
         }
-        if let State::Next(_, _) = state {
-            sleep(Duration::from_secs(1));
+        let delay = pause(&state, &pacing);
+        let pacing = pacing.clone();
+        let cf = cf.clone();
+        let stack_name = stack_name.clone();
+        let existed = matches!(state, State::Next(_, _));
+        Some(
+            delay
+                .map_err(Error::from)
+                .and_then(move |_| {
+                    cf.describe_stack_events(DescribeStackEventsInput {
+                        stack_name: Some(stack_name.clone()),
+                        ..DescribeStackEventsInput::default()
+                    })
+                    .then(move |result| match result {
+                        // following a deletion ends with the stack no longer existing
+                        Err(ref err) if existed && missing_stack(err) => {
+                            Ok((Vec::new(), Some(deleted(&stack_name))))
+                        }
+                        result => result
+                            .map(|output| (output.stack_events.unwrap_or_default(), None))
+                            .map_err(Error::from),
+                    })
+                })
+                .map(move |(events, deleted)| {
+                    let mut states = events
+                        .into_iter()
+                        .map(ResourceState::from)
+                        .collect::<Vec<_>>();
+                    states.reverse();
+                    if let Ok(mut pacing) = pacing.lock() {
+                        pacing.observe(&states);
+                    }
+                    let gone = deleted.is_some();
+                    states.extend(deleted);
+                    (
+                        (state.prev_len(), states.clone()),
+                        State::Next(
+                            !gone
+                                && state.follow()
+                                && (forever
+                                    || !states.last().iter().any(|state| {
+                                        state.is_stack() && state.complete_or_failed()
+                                    })),
+                            states.len(),
+                        ),
+                    )
+                }),
+        )
+    })
+}
+
+/// Polls the stack's own status, for roles permitted to describe stacks
+/// but not their events
+fn fetch_stack_status(
+    cf: CloudFormationClient,
+    stack_name: String,
+    follow: bool,
+    forever: bool,
+    interval: Duration,
+) -> impl Stream<Item = (usize, Vec<ResourceState>), Error = Error> {
+    let pacing = Arc::new(Mutex::new(Pacing::new(interval)));
+    stream::unfold(State::Init(follow), move |state| {
+        if state.complete() {
+            return None;
         }
+        let delay = pause(&state, &pacing);
+        let pacing = pacing.clone();
+        let cf = cf.clone();
+        let stack_name = stack_name.clone();
         Some(
-            cf.clone()
-                .describe_stack_events(DescribeStackEventsInput {
-                    stack_name: Some(stack_name.clone()),
-                    ..DescribeStackEventsInput::default()
+            delay
+                .map_err(Error::from)
+                .and_then(move |_| {
+                    cf.describe_stacks(DescribeStacksInput {
+                        stack_name: Some(stack_name),
+                        ..DescribeStacksInput::default()
+                    })
+                    .map_err(Error::from)
                 })
                 .map(move |result| {
-                    let mut states = result
-                        .stack_events
+                    let states = result
+                        .stacks
                         .unwrap_or_default()
                         .into_iter()
+                        .take(1)
                         .map(ResourceState::from)
                         .collect::<Vec<_>>();
-                    states.reverse();
+                    if let Ok(mut pacing) = pacing.lock() {
+                        pacing.observe(&states);
+                    }
                     (
                         (state.prev_len(), states.clone()),
                         State::Next(
                             state.follow()
-                                && !states
-                                    .last()
-                                    .iter()
-                                    .any(|state| state.is_stack() && state.complete_or_failed()),
+                                && (forever
+                                    || !states.iter().all(ResourceState::complete_or_failed)),
                             states.len(),
                         ),
                     )
-                })
-                .map_err(Error::from),
+                }),
         )
     })
 }
 
+type States = Box<dyn Stream<Item = (usize, Vec<ResourceState>), Error = Error> + Send + 'static>;
+
+/// Streams stack events, falling back on following the stack's status alone
+/// when describing events isn't permitted
+fn fetch_events_or_status(
+    cf: CloudFormationClient,
+    stack_name: String,
+    follow: bool,
+    forever: bool,
+    interval: Duration,
+    theme: Theme,
+) -> impl Stream<Item = (usize, Vec<ResourceState>), Error = Error> {
+    fetch_events(cf.clone(), stack_name.clone(), follow, forever, interval)
+        .into_future()
+        .then(move |result| -> Result<States, Error> {
+            match result {
+                Ok((first, rest)) => Ok(Box::new(stream::iter_ok(first).chain(rest))),
+                Err((Error::Events(ref err), _)) if access_denied(err) => {
+                    eprintln!(
+                        "{} not permitted to describe events for {}, following its stack status instead",
+                        theme.notice("notice:"),
+                        stack_name
+                    );
+                    Ok(Box::new(fetch_stack_status(cf, stack_name, follow, forever, interval)))
+                }
+                Err((err, _)) => Err(err),
+            }
+        })
+        .flatten_stream()
+}
+
 /// Return a stream of cloud formation resoure states,
 /// either for a aggregate list of resources for the resource
 /// states over time
@@ -260,54 +1871,1595 @@ fn states(
     stack_name: String,
     resources: bool,
     follow: bool,
-) -> Box<dyn Stream<Item = (usize, Vec<ResourceState>), Error = Error> + Send + 'static> {
-    if resources {
-        Box::new(fetch_resources(cf, stack_name, follow))
+    forever: bool,
+    interval: Duration,
+    wait_for_create: bool,
+    theme: Theme,
+) -> States {
+    let tail: States = if resources {
+        Box::new(fetch_resources(
+            cf.clone(),
+            stack_name.clone(),
+            follow,
+            forever,
+            interval,
+        ))
+    } else {
+        Box::new(fetch_events_or_status(
+            cf.clone(),
+            stack_name.clone(),
+            follow,
+            forever,
+            interval,
+            theme,
+        ))
+    };
+    if wait_for_create {
+        Box::new(
+            existence(cf, stack_name, interval)
+                .map(move |_| tail)
+                .flatten_stream(),
+        )
+    } else {
+        tail
+    }
+}
+
+/// Resolves once a stack exists, polling for it at an interval until it's created
+fn existence(
+    cf: CloudFormationClient,
+    stack_name: String,
+    interval: Duration,
+) -> impl Future<Item = (), Error = Error> {
+    future::loop_fn((), move |_| {
+        let delay = Delay::new(Instant::now() + interval);
+        cf.describe_stacks(DescribeStacksInput {
+            stack_name: Some(stack_name.clone()),
+            ..DescribeStacksInput::default()
+        })
+        .then(
+            move |result| -> Box<dyn Future<Item = Loop<(), ()>, Error = Error> + Send> {
+                match result {
+                    Ok(_) => Box::new(future::ok(Loop::Break(()))),
+                    Err(ref err) if missing_stack(err) => {
+                        Box::new(delay.from_err().map(|_| Loop::Continue(())))
+                    }
+                    Err(err) => Box::new(future::err(err.into())),
+                }
+            },
+        )
+    })
+}
+
+/// How tailed resource states are rendered
+struct View {
+    /// Whether states are of the stack's resources rather than its events
+    resources: bool,
+    /// Whether a row naming columns heads the table
+    headers: bool,
+    /// Whether lines are labeled with their stack's name
+    labeled: bool,
+    /// Whether labels include a column of each stack's region
+    regional: bool,
+    /// Aliases of accounts whose stacks are tailed, labeling lines by account
+    aliases: HashMap<String, String>,
+    format: Option<LineFormat>,
+    clock: Clock,
+    markers: Markers,
+    /// Which states are displayed
+    filter: Filter,
+    /// Number of most recent states displayed per stack, when limited
+    lines: Option<usize>,
+    /// Whether only events of each stack's most recent operation are displayed
+    current_operation: bool,
+    /// Whether events repeating their resource's previous status are hidden
+    transitions_only: bool,
+    /// Whether physical ids are displayed next to logical ids
+    physical_ids: bool,
+    /// Whether long reasons are wrapped onto continuation lines rather than clipped
+    full_reasons: bool,
+    /// How links to resources' console pages are displayed, when they are
+    links: Option<Links>,
+    /// Logs of functions behind failed resources, when they're displayed
+    logs: Option<Logs>,
+    /// Who initiated stack operations, when they're displayed
+    initiators: Option<Initiators>,
+    /// Policies of stacks whose updates they denied, warning of the denials
+    denials: Denials,
+    /// Changes planned by the change sets behind stacks' updates
+    planned: Planned,
+    /// Whether lines divide each operation from the next
+    separated: bool,
+    /// Whether a header with each stack's status and a live elapsed time heads its events
+    live: bool,
+    /// Whether the latest status of each resource heads a rolling log of events
+    split: bool,
+    theme: Theme,
+    /// Template resource counts of each stack when a progress bar heads each
+    /// stack's events
+    progress: Option<HashMap<String, usize>>,
+    /// Timings of previous runs, estimating the time remaining in progress bars
+    history: Option<History>,
+    /// Last events seen of each stack by a previous invocation, which only
+    /// later events are displayed after
+    checkpoints: BTreeMap<String, Checkpoint>,
+    /// Protective template policies of each stack's resources, in the resources view
+    policies: HashMap<String, HashMap<String, Policies>>,
+    /// Snapshots of each stack's resources to mark changes since, in the resources view
+    baselines: Option<HashMap<String, Vec<ResourceState>>>,
+}
+
+impl View {
+    /// The detail column of each state, how long resources took to reach
+    /// their final state or the hook invoked on them for events, and
+    /// protective policies for resources
+    fn details(
+        &self,
+        stack_name: &str,
+        states: &[ResourceState],
+    ) -> Vec<String> {
+        if self.resources {
+            let policies = self.policies.get(stack_name);
+            let baseline = self.baseline(stack_name);
+            states
+                .iter()
+                .map(|state| {
+                    let policies = policies
+                        .and_then(|policies| policies.get(&state.resource_id))
+                        .map(|policies| policies.to_string().color(self.theme.warning).to_string());
+                    let change = baseline
+                        .map(|baseline| Change::of(baseline, state))
+                        .filter(|change| *change != Change::Unchanged)
+                        .map(|change| change.to_string());
+                    change
+                        .into_iter()
+                        .chain(policies)
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                })
+                .collect()
+        } else {
+            elapsed(stack_name, states)
+                .into_iter()
+                .zip(states)
+                .map(|(elapsed, state)| match Invocation::of(state) {
+                    Some(invocation) => invocation.to_string(),
+                    None => elapsed.map(humanize).unwrap_or_default(),
+                })
+                .collect()
+        }
+    }
+
+    fn baseline(
+        &self,
+        stack_name: &str,
+    ) -> Option<&[ResourceState]> {
+        self.baselines.as_ref().map(|baselines| {
+            baselines
+                .get(stack_name)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+        })
+    }
+
+    /// Resources of the stack's baseline which no longer exist, and their detail column
+    fn disappeared<'a>(
+        &'a self,
+        stack_name: &str,
+        states: &[ResourceState],
+    ) -> Vec<(&'a ResourceState, String)> {
+        self.baseline(stack_name)
+            .map(|baseline| snapshot::disappeared(baseline, states))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|state| (state, Change::Disappeared.to_string()))
+            .collect()
+    }
+}
+
+/// A color telling a stack's lines apart from other stacks', the same for a
+/// given stack name from one run to the next
+fn label_color(stack_name: &str) -> Color {
+    const PALETTE: &[Color] = &[
+        Color::Cyan,
+        Color::Magenta,
+        Color::Yellow,
+        Color::Blue,
+        Color::Green,
+        Color::BrightCyan,
+        Color::BrightMagenta,
+        Color::BrightBlue,
+    ];
+    let sum = stack_name.bytes().map(usize::from).sum::<usize>();
+    PALETTE[sum % PALETTE.len()]
+}
+
+/// Columns prefixing a stack's lines when there is more than one stack, naming
+/// its account and region too when stacks are tailed across several
+fn label(
+    stack_name: &str,
+    view: &View,
+) -> String {
+    if !view.labeled {
+        return String::new();
+    }
+    let name = stack_logical_id(stack_name);
+    let region = match region_of(stack_name) {
+        Some(region) if view.regional => format!("{}\t", region.name()),
+        _ => String::new(),
+    };
+    let account = match account_of(stack_name).and_then(|id| view.aliases.get(id)) {
+        Some(alias) => format!("{}\t", alias),
+        None => String::new(),
+    };
+    format!(
+        "{}{}{}\t",
+        account,
+        region,
+        name.bold().color(label_color(name))
+    )
+}
+
+/// Writes the current states of each stack, labeling lines with their stack's
+/// name when there is more than one, and returns the number of lines written
+fn render(
+    writer: &mut impl Write,
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    view: &View,
+) -> usize {
+    let mut lines = 0;
+    if view.headers {
+        drop(writeln!(writer, "{}", header(view)));
+        lines += 1;
+    }
+    for (stack_name, states) in stacks {
+        let region = region_of(stack_name).unwrap_or_default();
+        let label = label(stack_name, view);
+        if view.live {
+            drop(writeln!(
+                writer,
+                "{}{}",
+                label,
+                heading(stack_name, states, view.markers, view.theme, Utc::now())
+            ));
+            lines += 1;
+        }
+        if let Some(mut progress) = view
+            .progress
+            .as_ref()
+            .and_then(|counts| Progress::of(stack_name, states, counts.get(stack_name).cloned()))
+        {
+            progress.remaining = view
+                .history
+                .as_ref()
+                .and_then(|history| history.remaining(stack_name, states, Utc::now()));
+            drop(writeln!(
+                writer,
+                "{}{}",
+                label,
+                Bar(&progress, view.markers, view.theme)
+            ));
+            lines += 1;
+        }
+        let states = if view.current_operation && !view.resources {
+            current_operation(stack_name, states)
+        } else {
+            states
+        };
+        let states = match view.checkpoints.get(stack_name) {
+            Some(checkpoint) => checkpoint.unseen(states),
+            None => states,
+        };
+        let rows = states
+            .iter()
+            .zip(view.details(stack_name, states))
+            .zip(repeats(states))
+            .filter(|(_, repeat)| !(view.transitions_only && *repeat))
+            .map(|(row, _)| row)
+            .chain(view.disappeared(stack_name, states))
+            .collect::<Vec<_>>();
+        // like tail -n, only the most recent displayed states are written
+        let skip = view
+            .lines
+            .map(|limit| {
+                rows.iter()
+                    .filter(|(state, _)| view.filter.matches(state))
+                    .count()
+                    .saturating_sub(limit)
+            })
+            .unwrap_or_default();
+        let mut position = 0;
+        for (index, (state, detail)) in rows.into_iter().enumerate() {
+            if view.separated
+                && index > 0
+                && state.is_operation_start(stack_name)
+                && position >= skip
+            {
+                drop(writeln!(
+                    writer,
+                    "{}{}",
+                    label,
+                    Separator(state, view.markers, view.theme)
+                ));
+                lines += 1;
+            }
+            if state.is_rollback_pivot(stack_name) && position >= skip {
+                drop(writeln!(
+                    writer,
+                    "{}{}",
+                    label,
+                    Pivot(state, view.markers, view.theme)
+                ));
+                lines += 1;
+            }
+            if !view.filter.matches(state) {
+                continue;
+            }
+            position += 1;
+            if position <= skip {
+                continue;
+            }
+            let mut reasons = if view.full_reasons && view.format.is_none() {
+                wrap(&state.reason, REASON_WIDTH)
+            } else {
+                Vec::new()
+            }
+            .into_iter();
+            drop(match &view.format {
+                Some(format) => writeln!(writer, "{}{}", label, Custom(format, state, &view.clock)),
+                None => writeln!(
+                    writer,
+                    "{}{}",
+                    label,
+                    Formatted(
+                        ResourceState {
+                            reason: reasons.next().unwrap_or_else(|| state.reason.clone()),
+                            ..state.clone()
+                        },
+                        &view.clock,
+                        view.markers,
+                        detail,
+                        Columns {
+                            physical_ids: view.physical_ids,
+                            link: view.links.and_then(|links| {
+                                links::console_url(&region, state).map(|url| (links, url))
+                            }),
+                        },
+                        view.theme
+                    )
+                ),
+            });
+            lines += 1;
+            // continuation lines keep to the reason column, beneath the reason they continue
+            let columns = if view.physical_ids { 6 } else { 5 };
+            for reason in reasons {
+                drop(writeln!(
+                    writer,
+                    "{}{}{}",
+                    label,
+                    "\t".repeat(columns),
+                    view.theme.muted(&reason)
+                ));
+                lines += 1;
+            }
+            if !view.resources {
+                for (depth, cause) in nested::causes(states, state).into_iter().enumerate() {
+                    drop(writeln!(
+                        writer,
+                        "{}{}",
+                        label,
+                        Cause(cause, depth, view.theme)
+                    ));
+                    lines += 1;
+                }
+            }
+            if let Some(targets) = view.planned.of(stack_name, state) {
+                drop(writeln!(writer, "{}{}", label, Changing(targets)));
+                lines += 1;
+            }
+            if let Some(denial) = view.denials.of(stack_name, state) {
+                drop(writeln!(writer, "{}{}", label, denial));
+                lines += 1;
+            }
+            if let Some(initiator) = view
+                .initiators
+                .as_ref()
+                .and_then(|initiators| initiators.of(state))
+            {
+                drop(writeln!(writer, "{}{}", label, initiator));
+                lines += 1;
+            }
+            if let Some(logs) = &view.logs {
+                for line in logs.tail(state) {
+                    drop(writeln!(writer, "{}{}", label, LogLine(line)));
+                    lines += 1;
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// A row naming the columns of the table's lines
+fn header(view: &View) -> String {
+    let mut columns = Vec::new();
+    if view.labeled {
+        if !view.aliases.is_empty() {
+            columns.push("ACCOUNT");
+        }
+        if view.regional {
+            columns.push("REGION");
+        }
+        columns.push("STACK");
+    }
+    columns.extend(&["TIME", "RESOURCE"]);
+    if view.physical_ids {
+        columns.push("PHYSICAL ID");
+    }
+    columns.extend(&["TYPE", "STATUS", "DETAIL", "REASON"]);
+    if view.links.is_some() {
+        columns.push("LINK");
+    }
+    columns.join("\t").bold().to_string()
+}
+
+/// The latest status of each stack's resources, heading the split view
+fn render_latest(
+    writer: &mut impl Write,
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    view: &View,
+) {
+    for (stack_name, states) in stacks {
+        let label = label(stack_name, view);
+        for state in latest(states.clone())
+            .into_iter()
+            .filter(|state| view.filter.matches(state))
+        {
+            drop(writeln!(
+                writer,
+                "{}{}",
+                label,
+                Formatted(
+                    state,
+                    &view.clock,
+                    view.markers,
+                    String::new(),
+                    Columns {
+                        physical_ids: view.physical_ids,
+                        link: None,
+                    },
+                    view.theme
+                )
+            ));
+        }
+    }
+}
+
+/// Lines heading a log of events, keeping only as many of the most recent
+/// events as fit beneath them in a terminal of a given height
+fn rolling(
+    mut head: Vec<String>,
+    events: Vec<String>,
+    height: usize,
+) -> Vec<String> {
+    // leaving a row for the cursor
+    let room = height.saturating_sub(head.len() + 1);
+    let skip = events.len().saturating_sub(room);
+    head.extend(events.into_iter().skip(skip));
+    head
+}
+
+/// Lines written to the terminal, so that updates only rewrite
+/// lines from the first which changed rather than redrawing everything
+struct Screen {
+    term: Term,
+    lines: Vec<String>,
+    /// Width of the terminal when lines were written, to tell when it's resized
+    width: u16,
+    /// How many times each line of the latest rendering has been written when
+    /// output isn't a terminal, like CI logs, which can only be appended to.
+    /// Keyed by their words so that changes in column alignment don't repeat them
+    written: Option<HashMap<String, usize>>,
+}
+
+impl Screen {
+    /// A screen which only appends lines when plain, or when output isn't a terminal
+    fn new(
+        term: Term,
+        plain: bool,
+    ) -> Self {
+        Screen {
+            written: if term.is_term() && !plain {
+                None
+            } else {
+                Some(HashMap::new())
+            },
+            width: term.size().1,
+            term,
+            lines: Vec::new(),
+        }
+    }
+
+    fn update(
+        &mut self,
+        lines: Vec<String>,
+    ) -> io::Result<()> {
+        if let Some(written) = &mut self.written {
+            for line in unwritten(written, &lines) {
+                self.term.write_line(line)?;
+            }
+            return Ok(());
+        }
+        let (_, width) = self.term.size();
+        // lines wider than the terminal wrap onto rows clearing lines can't
+        // reliably count, so they're cut short instead
+        let lines = lines
+            .iter()
+            .map(|line| truncate(line, width as usize))
+            .collect::<Vec<_>>();
+        if width != self.width {
+            // a resized terminal reflows what was written, so start afresh
+            self.term.clear_screen()?;
+            self.lines.clear();
+            self.width = width;
+        }
+        let unchanged = unchanged(&self.lines, &lines);
+        self.term.clear_last_lines(
+            self.lines[unchanged..]
+                .iter()
+                .map(|line| rows(line, width as usize))
+                .sum(),
+        )?;
+        for line in &lines[unchanged..] {
+            self.term.write_line(line)?;
+        }
+        self.lines = lines;
+        Ok(())
+    }
+}
+
+/// A line's words, ignoring how they're spaced
+fn words(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lines of a rendering beyond those already written, counting repeats of a
+/// line so that identical rows are each written once. Only the rendering's
+/// own lines are remembered, so what's tracked never outgrows what's rendered
+fn unwritten<'a>(
+    written: &mut HashMap<String, usize>,
+    lines: &'a [String],
+) -> Vec<&'a String> {
+    let mut rendered = HashMap::new();
+    let unwritten = lines
+        .iter()
+        .filter(|line| {
+            let words = words(line);
+            let previously = written.get(&words).cloned().unwrap_or_default();
+            let count = rendered.entry(words).or_insert(0);
+            *count += 1;
+            *count > previously
+        })
+        .collect();
+    *written = rendered;
+    unwritten
+}
+
+/// Number of leading lines two renderings share
+fn unchanged(
+    previous: &[String],
+    lines: &[String],
+) -> usize {
+    previous
+        .iter()
+        .zip(lines)
+        .take_while(|(previous, line)| previous == line)
+        .count()
+}
+
+/// Number of terminal rows a line occupies once wrapped
+fn rows(
+    line: &str,
+    width: usize,
+) -> usize {
+    let chars = console::measure_text_width(&links::without_hyperlinks(line));
+    if width == 0 || chars == 0 {
+        return 1;
+    }
+    (chars + width - 1) / width
+}
+
+/// A line cut short to fit a width, ending in an ellipsis. Escape sequences
+/// are kept, so that colors and hyperlinks still end where they should
+fn truncate(
+    line: &str,
+    width: usize,
+) -> String {
+    if width == 0 || console::measure_text_width(&links::without_hyperlinks(line)) <= width {
+        return line.to_string();
+    }
+    let mut truncated = String::with_capacity(line.len());
+    let mut used = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            truncated.push(c);
+            match chars.next() {
+                // control sequences, like colors, end with a letter
+                Some('[') => {
+                    truncated.push('[');
+                    for c in &mut chars {
+                        truncated.push(c);
+                        if c.is_ascii_alphabetic() {
+                            break;
+                        }
+                    }
+                }
+                // operating system commands, like hyperlinks, end with ESC \
+                Some(']') => {
+                    truncated.push(']');
+                    while let Some(c) = chars.next() {
+                        truncated.push(c);
+                        if c == '\x1b' {
+                            truncated.extend(chars.next());
+                            break;
+                        }
+                    }
+                }
+                other => truncated.extend(other),
+            }
+            continue;
+        }
+        let chars_width = console::measure_text_width(c.encode_utf8(&mut [0; 4]));
+        // leaving room for the ellipsis
+        if used + chars_width < width {
+            truncated.push(c);
+            used += chars_width;
+        } else if used < width {
+            truncated.push('…');
+            used = width;
+        }
+    }
+    truncated
+}
+
+/// Terminal resizes, which redraw lines to fit the terminal's new width
+#[cfg(unix)]
+fn resizes() -> Box<dyn Stream<Item = Update, Error = Error> + Send> {
+    Box::new(
+        Signal::new(libc::SIGWINCH)
+            .flatten_stream()
+            .map(|_| Update::Resize)
+            .from_err(),
+    )
+}
+
+/// Terminal resizes, which are only signaled on unix
+#[cfg(not(unix))]
+fn resizes() -> Box<dyn Stream<Item = Update, Error = Error> + Send> {
+    Box::new(stream::empty())
+}
+
+/// What the display of tailed stacks reacts to
+#[derive(Debug, PartialEq)]
+enum Update {
+    /// A stack's latest states
+    States(String, Vec<ResourceState>),
+    /// Time passing, refreshing live elapsed times
+    Tick,
+    /// The terminal being resized, redrawing lines to fit
+    Resize,
+    /// A key pressed in the full-screen interface
+    Key(Key),
+    /// A mouse button clicked or wheel turned in the full-screen interface
+    Mouse(MouseEvent),
+    /// Tailing ends
+    Stop,
+}
+
+/// The latest status of a stack itself among its events
+fn stack_status<'a>(
+    stack_name: &str,
+    states: &'a [ResourceState],
+) -> Option<&'a str> {
+    states
+        .iter()
+        .rev()
+        .find(|state| {
+            state.is_stack()
+                && state.resource_id == stack_logical_id(stack_name)
+                && !is_hook(&state.status)
+        })
+        .map(|state| state.status.as_str())
+}
+
+/// A stack's current status and how long its current operation has taken so far,
+/// with a spinner showing polling continues through quiet periods
+fn heading(
+    stack_name: &str,
+    states: &[ResourceState],
+    markers: Markers,
+    theme: Theme,
+    now: DateTime<Utc>,
+) -> String {
+    let operation = current_operation(stack_name, states);
+    let status = stack_status(stack_name, operation).unwrap_or("status unknown");
+    let mut heading = format!(
+        "{} {} {}",
+        markers.spinner(now),
+        stack_logical_id(stack_name).bold(),
+        status.bold()
+    );
+    if let Some(start) = operation.first() {
+        heading.push_str(&format!(
+            " {}",
+            theme.muted(&humanize(now.signed_duration_since(start.timestamp)))
+        ));
+    }
+    heading
+}
+
+/// One line summary of a stack's current operation: the stack's status, how many
+/// resources completed or failed and how long the operation has taken so far
+fn summary(
+    stack_name: &str,
+    states: &[ResourceState],
+    resources: bool,
+    now: DateTime<Utc>,
+) -> String {
+    let operation = if resources {
+        states
+    } else {
+        current_operation(stack_name, states)
+    };
+    let latest = latest(operation.to_vec());
+    let (stack, others): (Vec<_>, Vec<_>) = latest
+        .iter()
+        .partition(|state| state.is_stack() && state.resource_id == stack_logical_id(stack_name));
+    let count = |suffix: &str| {
+        others
+            .iter()
+            .filter(|state| state.status.ends_with(suffix))
+            .count()
+    };
+    let mut summary = format!(
+        "{} {}: {} complete, {} failed",
+        stack_name,
+        stack
+            .first()
+            .map(|stack| stack.status.as_str())
+            .unwrap_or("status unknown"),
+        count(COMPLETE),
+        count(FAILED)
+    );
+    // resources carry when they last changed rather than when the operation began
+    if let (false, Some(start)) = (resources, operation.first()) {
+        summary.push_str(&format!(
+            ", {} elapsed",
+            humanize(now.signed_duration_since(start.timestamp))
+        ));
+    }
+    summary
+}
+
+/// Summarizes each stack after tailing stopped short and exits with a given code
+fn stop(
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    resources: bool,
+    reason: &str,
+    code: i32,
+    theme: Theme,
+) -> ! {
+    for (stack_name, states) in stacks {
+        eprintln!(
+            "{} {}",
+            theme.notice(reason),
+            summary(stack_name, states, resources, Utc::now())
+        );
+    }
+    process::exit(code)
+}
+
+/// The status each stack was left in, from its own latest event, or when
+/// tailing resources, which leaves out the stack's own events, described afresh
+fn final_statuses(
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    resources: bool,
+    accounts: &Accounts,
+    theme: Theme,
+) -> BTreeMap<String, Option<String>> {
+    stacks
+        .iter()
+        .map(|(stack_name, states)| {
+            let status = if resources {
+                match metadata::describe(&accounts.client_for(stack_name), stack_name) {
+                    Ok(stack) => stack.map(|stack| stack.stack_status),
+                    Err(err) => {
+                        eprintln!("{} {}", theme.notice("notice:"), err);
+                        None
+                    }
+                }
+            } else {
+                stack_status(stack_name, states).map(String::from)
+            };
+            (stack_name.clone(), status)
+        })
+        .collect()
+}
+
+/// Exit code reflecting the status each stack was left in: failed if any failed,
+/// otherwise rolled back if any rolled back, otherwise success only when every
+/// stack completed. Statuses still in progress or unknown are unexpected
+fn exit_code(
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    statuses: &BTreeMap<String, Option<String>>,
+) -> i32 {
+    let known = statuses.values().flatten().collect::<Vec<_>>();
+    // a hook failing in warn mode lets the operation carry on, but still failed
+    let hook_failed = stacks.iter().any(|(stack_name, states)| {
+        current_operation(stack_name, states)
+            .iter()
+            .any(|state| is_hook(&state.status) && is_failure(&state.status))
+    });
+    if hook_failed || known.iter().any(|status| is_failure(status)) {
+        FAILED_STATUS
+    } else if known.iter().any(|status| status.contains("ROLLBACK")) {
+        ROLLED_BACK
+    } else if known.len() == statuses.len() && known.iter().all(|status| status.ends_with(COMPLETE))
+    {
+        0
     } else {
-        Box::new(fetch_events(cf, stack_name, follow))
+        UNEXPECTED
     }
 }
 
+/// Stacks which ended in none of the expected statuses, with the status they ended in
+fn unexpected<'a>(
+    statuses: &'a BTreeMap<String, Option<String>>,
+    expect: &[String],
+) -> Vec<(&'a str, Option<&'a str>)> {
+    statuses
+        .iter()
+        .map(|(stack_name, status)| (stack_name.as_str(), status.as_deref()))
+        .filter(|(_, status)| {
+            !status
+                .map(|status| expect.iter().any(|expected| expected == status))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Per https://no-color.org, any non-empty NO_COLOR value disables color
+fn color_disabled_by_env(no_color: Option<OsString>) -> bool {
+    no_color.map(|value| !value.is_empty()).unwrap_or_default()
+}
+
 fn credentials() -> ChainProvider {
     let mut chain = ChainProvider::new();
     chain.set_timeout(Duration::from_millis(200));
     chain
 }
 
-fn client() -> CloudFormationClient {
+fn client_in(region: Region) -> CloudFormationClient {
     CloudFormationClient::new_with(
         HttpClient::new().expect("failed to create request dispatcher"),
         credentials(),
-        Region::default(),
+        region,
     )
 }
 
+fn client() -> CloudFormationClient {
+    client_in(Region::default())
+}
+
+/// The region of a stack given its id, which is where it's tailed from
+fn region_of(stack_name: &str) -> Option<Region> {
+    if !stack_name.starts_with("arn:") {
+        return None;
+    }
+    stack_name.split(':').nth(3)?.parse().ok()
+}
+
 fn main() -> Result<(), Box<dyn StdError>> {
     let Options {
-        stack_name,
+        stack_names,
         timezone,
+        time_format,
+        relative,
         follow,
+        forever,
+        wait_for_create,
+        state_file,
+        interval,
+        timeout,
+        expect,
         resources,
+        output,
+        format,
+        status_filter,
+        types,
+        resource_ids,
+        exclude_types,
+        exclude_resource_ids,
+        max_depth,
+        include_nested,
+        exclude_nested,
+        failed_only,
+        since,
+        until,
+        lines,
+        current_operation: latest_operation,
+        transitions_only,
+        physical_ids,
+        links,
+        logs,
+        who,
+        full_reasons,
+        metadata,
+        headers,
+        legend,
+        sort_by,
+        reverse,
+        diff_since,
+        mut notify_on,
+        notify,
+        notify_slack,
+        notify_slack_failures,
+        notify_teams,
+        notify_discord,
+        pagerduty_routing_key,
+        email,
+        email_from,
+        ses,
+        archive_to,
+        notify_sns,
+        webhook,
+        webhook_headers,
+        emit_eventbridge,
+        no_color,
+        ascii,
+        plain,
+        split,
+        tui,
+        check_health,
+        application,
+        tags,
+        matches,
+        no_cache,
+        regions,
+        account_roles,
+        command,
     } = Options::from_args();
 
-    let term = Term::stdout();
-    let mut writer = TabWriter::new(term.clone());
-    tokio::run(
-        states(client(), stack_name, resources, follow)
-            .for_each(move |result| {
-                let (prev_len, states) = result;
-                drop(term.clear_last_lines(prev_len));
-                drop(writer.flush());
-                for state in states {
-                    drop(writeln!(&mut writer, "{}", Formatted(state, timezone)));
-                }
-                drop(writer.flush());
-                Ok(())
+    if no_color || plain || color_disabled_by_env(env::var_os("NO_COLOR")) {
+        colored::control::set_override(false);
+    }
+
+    if let Some(command) = command {
+        match command {
+            Command::Cost(options) => cost::run(client(), options)?,
+            Command::Drift(options) => drift::run(client(), options)?,
+            Command::Explain(options) => explain::run(options)?,
+            Command::Graph(options) => graph::run(client(), options)?,
+            Command::Preflight(options) => preflight::run(client(), options)?,
+            Command::Policy(options) => policy::run(client(), options)?,
+            Command::Replay(options) => {
+                let config = Config::load()?;
+                replay::run(
+                    options,
+                    if plain {
+                        Markers::Plain
+                    } else {
+                        Markers::configured(ascii, config.icons)
+                    },
+                    Theme::from(&config.theme),
+                )?
+            }
+            Command::StackSet(options) => stackset::run(
+                client(),
+                options,
+                if plain {
+                    Markers::Plain
+                } else {
+                    Markers::configured(ascii, Config::load()?.icons)
+                },
+            )?,
+            Command::Stats(options) => stats::run(options)?,
+            Command::Wait(options) => wait::run(client(), options)?,
+        }
+        return Ok(());
+    }
+
+    // the full-screen interface is only of use while following
+    let follow = follow || tui;
+    let mut named = stack_names;
+    if let Some(application) = application {
+        named.extend(appregistry::stacks(&application)?);
+    }
+    let select = |cf: &CloudFormationClient,
+                  account: Option<&str>,
+                  region: &Region|
+     -> Result<Vec<String>, Error> {
+        let mut stack_names = named.clone();
+        if !tags.is_empty() {
+            stack_names.extend(selection::tagged(cf, account, region, &tags, no_cache)?);
+        }
+        if !matches.is_empty() {
+            stack_names.extend(selection::matching(
+                cf, account, region, &matches, no_cache,
+            )?);
+        }
+        Ok(stack_names)
+    };
+    let accounts = Accounts::new(&account_roles);
+    // stacks of other regions and accounts are tailed by id, which carries both
+    let mut stack_names = if regions.is_empty() && account_roles.is_empty() {
+        select(&client(), None, &Region::default())?
+    } else {
+        let regions = if regions.is_empty() {
+            vec![Region::default()]
+        } else {
+            regions.clone()
+        };
+        let account_ids = if account_roles.is_empty() {
+            vec![None]
+        } else {
+            account_roles
+                .iter()
+                .map(|role| Some(role.account()))
+                .collect()
+        };
+        let mut ids = Vec::new();
+        for account in &account_ids {
+            for region in &regions {
+                let cf = accounts.client(*account, region.clone());
+                ids.extend(selection::ids(
+                    &cf,
+                    region,
+                    &select(&cf, *account, region)?,
+                )?);
+            }
+        }
+        ids
+    };
+    let mut selected = HashSet::new();
+    stack_names.retain(|stack_name| selected.insert(stack_name.clone()));
+    if stack_names.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no stacks matched").into());
+    }
+    let config = Config::load()?;
+    let theme = Theme::from(&config.theme);
+    let policies = if resources {
+        stack_names
+            .iter()
+            .filter_map(|stack_name| {
+                match template::fetch(&accounts.client_for(stack_name), stack_name) {
+                    Ok((template, _)) => Some((stack_name.clone(), template::policies(&template))),
+                    Err(err) => {
+                        eprintln!(
+                            "{} unable to read deletion policies for {}: {}",
+                            theme.notice("notice:"),
+                            stack_name,
+                            err
+                        );
+                        None
+                    }
+                }
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    // progress is only of interest while following events, and best effort
+    let progress = if follow && !resources && !plain {
+        Some(
+            stack_names
+                .iter()
+                .filter_map(|stack_name| {
+                    template::fetch(&accounts.client_for(stack_name), stack_name)
+                        .ok()
+                        .map(|(template, _)| {
+                            (stack_name.clone(), template::resource_count(&template))
+                        })
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let store = match config.archive {
+        Some(backend) => Some(archive::open(backend)?),
+        None => None,
+    };
+    let baselines = match diff_since {
+        Some(Moment(moment)) => {
+            let store = store.as_ref().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "no archive is configured, --diff-since compares with archived events",
+                )
+            })?;
+            let mut baselines = HashMap::new();
+            for stack_name in &stack_names {
+                baselines.insert(
+                    stack_name.clone(),
+                    snapshot::at(stack_name, store.load(stack_name)?, moment),
+                );
+            }
+            Some(baselines)
+        }
+        None => None,
+    };
+    // a live header only makes sense on a terminal, where it's redrawn in place
+    let live = (follow || forever) && !resources && !tui && !plain && Term::stdout().is_term();
+    let mut state_file = match state_file {
+        Some(path) => Some(StateFile::load(&path)?),
+        None => None,
+    };
+    let mut view = View {
+        resources,
+        headers,
+        labeled: stack_names.len() > 1,
+        regional: regions.len() > 1,
+        aliases: if account_roles.is_empty() {
+            HashMap::new()
+        } else {
+            accounts.aliases()
+        },
+        format,
+        clock: Clock {
+            timezone,
+            relative,
+            format: time_format,
+        },
+        markers: if plain {
+            Markers::Plain
+        } else {
+            Markers::configured(ascii, config.icons)
+        },
+        filter: Filter {
+            status: status_filter,
+            types,
+            resources: resource_ids,
+            exclude_types,
+            exclude_resources: exclude_resource_ids,
+            failed_only,
+            since,
+            until,
+        },
+        lines,
+        current_operation: latest_operation,
+        transitions_only,
+        physical_ids,
+        full_reasons,
+        links: if links {
+            Some(if Term::stdout().is_term() && !plain {
+                Links::Hyperlinks
+            } else {
+                Links::Urls
+            })
+        } else {
+            None
+        },
+        logs: if logs { Some(Logs::default()) } else { None },
+        initiators: if who {
+            Some(Initiators::default())
+        } else {
+            None
+        },
+        denials: Denials::default(),
+        planned: Planned::default(),
+        separated: forever,
+        live,
+        // output which isn't a terminal, like CI logs, can only be appended to
+        split: split && Term::stdout().is_term(),
+        theme,
+        history: progress.as_ref().map(|_| History::load()),
+        progress,
+        checkpoints: state_file
+            .as_ref()
+            .map(|state_file| state_file.checkpoints().clone())
+            .unwrap_or_default(),
+        policies,
+        baselines,
+    };
+    let archive = if resources { None } else { store };
+    let hooks = if resources { Vec::new() } else { config.hooks };
+    if notify && !notify_on.contains(&Trigger::Complete) {
+        notify_on.push(Trigger::Complete);
+    }
+    // each notifier is only told of the conditions it was asked to watch for
+    let mut notifiers: Vec<(Vec<Trigger>, Box<dyn Notifier + Send>)> = Vec::new();
+    if !notify_on.is_empty() {
+        notifiers.push((notify_on.clone(), Box::new(Bell)));
+    }
+    if notify {
+        notifiers.push((notify_on.clone(), Box::new(Desktop)));
+    }
+    if let Some(url) = notify_slack {
+        let mut triggers = vec![Trigger::Complete];
+        if notify_slack_failures {
+            triggers.push(Trigger::Failure);
+        }
+        notifiers.push((triggers, Box::new(Slack::new(url))));
+    }
+    if let Some(url) = notify_teams {
+        notifiers.push((vec![Trigger::Complete], Box::new(Teams::new(url))));
+    }
+    if let Some(url) = notify_discord {
+        notifiers.push((vec![Trigger::Complete], Box::new(Discord::new(url))));
+    }
+    if let Some(routing_key) = pagerduty_routing_key {
+        notifiers.push((
+            vec![Trigger::Complete],
+            Box::new(PagerDuty::new(routing_key)),
+        ));
+    }
+    if ses {
+        let from = email_from.unwrap_or_else(|| email[0].clone());
+        notifiers.push((vec![Trigger::Complete], Box::new(Ses::new(from, email))));
+    }
+    if let Some(topic_arn) = notify_sns {
+        notifiers.push((vec![Trigger::Complete], Box::new(Sns::new(topic_arn))));
+    }
+    if let Some(url) = webhook {
+        notifiers.push((
+            vec![Trigger::Event, Trigger::Complete],
+            Box::new(Webhook::new(url, webhook_headers)),
+        ));
+    }
+    if let Some(bus) = emit_eventbridge {
+        notifiers.push((vec![Trigger::Complete], Box::new(EventBridge::new(bus))));
+    }
+    let mut history = if follow && !resources {
+        Some(History::load())
+    } else {
+        None
+    };
+    let mut previous = HashMap::new();
+    let scope = Scope {
+        max_depth,
+        include: include_nested,
+        exclude: exclude_nested,
+    };
+    if metadata && output == Output::Table {
+        for stack_name in &stack_names {
+            match metadata::describe(&accounts.client_for(stack_name), stack_name) {
+                Ok(Some(stack)) => println!("{}", Header(&stack, &view.clock)),
+                Ok(None) => (),
+                Err(err) => eprintln!("{} {}", theme.notice("notice:"), err),
+            }
+        }
+    }
+    if legend && output == Output::Table {
+        println!("{}", view.markers.legend());
+    }
+    // the view moves into the update fold, but its theme also colors what follows
+    // archiving, recording, notifying and hooks block on their io, so they run
+    // off the runtime's threads, one update at a time
+    let record = Arc::new(Mutex::new(
+        move |stack_name: String,
+              states: Vec<ResourceState>|
+              -> Result<(String, Vec<ResourceState>), Error> {
+            if let Some(store) = &archive {
+                // archiving is a record of the deployment, not a reason to stop following it
+                if let Err(err) = store.save(&stack_name, &states) {
+                    eprintln!(
+                        "{} failed to archive events: {}",
+                        theme.notice("notice:"),
+                        err
+                    );
+                }
+            }
+            if let Some(state_file) = &mut state_file {
+                state_file.record(&stack_name, &states)?;
+            }
+            if let Some(history) = &mut history {
+                history.record(&stack_name, &states);
+            }
+            if !notifiers.is_empty() || !hooks.is_empty() {
+                // events and conditions already present when tailing begins aren't news
+                if let Some(previous) = previous.get(&stack_name) {
+                    for (triggers, notifier) in &notifiers {
+                        for notification in
+                            notify::triggered(triggers, &stack_name, previous, &states)
+                        {
+                            // an unreachable notification channel is no reason to stop following
+                            if let Err(err) = notifier.notify(&stack_name, &notification) {
+                                eprintln!(
+                                    "{} failed to deliver notification: {}",
+                                    theme.notice("notice:"),
+                                    err
+                                );
+                            }
+                        }
+                    }
+                    hooks::run(&hooks, &stack_name, previous, &states);
+                }
+                previous.insert(stack_name.clone(), states.clone());
+            }
+            Ok((stack_name, states))
+        },
+    ));
+    let updates = stream::select_all(stack_names.into_iter().map(|stack_name| {
+        let mut known = Vec::new();
+        let tail = states(
+            accounts.client_for(&stack_name),
+            stack_name.clone(),
+            resources,
+            follow || forever,
+            forever,
+            interval,
+            wait_for_create,
+            theme,
+        );
+        // nested stacks are tailed along with their parent
+        let accounts = accounts.clone();
+        Family::new(
+            stack_name.clone(),
+            tail,
+            resources,
+            scope.clone(),
+            move |nested| {
+                states(
+                    accounts.client_for(&nested),
+                    nested,
+                    resources,
+                    follow || forever,
+                    forever,
+                    interval,
+                    false,
+                    theme,
+                )
+            },
+        )
+        .map(move |(_, mut states)| {
+            if !resources {
+                known = accumulate(mem::take(&mut known), states);
+                states = known.clone();
+            }
+            if let Some(sort_by) = sort_by {
+                sort_by.sort(&mut states, reverse);
+            }
+            (stack_name.clone(), states)
+        })
+    }))
+    .and_then(move |(stack_name, states)| {
+        let record = record.clone();
+        blocking(move || (*record.lock().expect("recording panicked"))(stack_name, states))
+            .flatten()
+    });
+
+    // Ctrl-C stops tailing cleanly, ending the stream of updates rather than the process
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let interrupts = {
+        let interrupted = interrupted.clone();
+        tokio_signal::ctrl_c()
+            .flatten_stream()
+            .map(move |_| {
+                interrupted.store(true, Ordering::SeqCst);
+                Update::Stop
             })
-            .map_err(|_| ()),
-    );
+            .from_err::<Error>()
+    };
+    // so does following for longer than the timeout, if any
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let deadline = {
+        let timed_out = timed_out.clone();
+        stream::iter_ok(timeout)
+            .and_then(|timeout| Delay::new(Instant::now() + timeout).from_err::<Error>())
+            .map(move |_| {
+                timed_out.store(true, Ordering::SeqCst);
+                Update::Stop
+            })
+    };
+    // runs end here, including interrupted and timed out runs, which exit early
+    let stopped = move |last: &BTreeMap<String, Vec<ResourceState>>| {
+        if let Some(location) = &archive_to {
+            // exit codes reflect deployments, so a failed upload is only reported
+            if let Err(err) = archive::upload(location, last, Utc::now()) {
+                eprintln!(
+                    "{} failed to upload archive: {}",
+                    theme.notice("notice:"),
+                    err
+                );
+            }
+        }
+        if interrupted.load(Ordering::SeqCst) {
+            stop(last, resources, "interrupted:", INTERRUPTED, theme);
+        }
+        if let (true, Some(timeout)) = (timed_out.load(Ordering::SeqCst), timeout) {
+            stop(
+                last,
+                resources,
+                &format!("timed out after {}:", humantime::format_duration(timeout)),
+                TIMED_OUT,
+                theme,
+            );
+        }
+    };
+    // only the full-screen interface reads input, leaving stdin alone otherwise
+    let input = stream::iter_ok::<_, Error>(if tui { Some(()) } else { None })
+        .map(|_| interactive::input())
+        .flatten();
+    // while following, time passing refreshes the header's elapsed time
+    let ticks = Interval::new_interval(Duration::from_secs(1))
+        .from_err::<Error>()
+        .map(|_| Update::Tick)
+        .take_while(move |_| Ok(live));
+    let updates = updates
+        .map(|(stack_name, states)| Update::States(stack_name, states))
+        .chain(stream::once(Ok(Update::Stop)))
+        .select(interrupts)
+        .select(deadline)
+        .select(ticks)
+        .select(input)
+        .select(resizes())
+        .take_while(|update| Ok(*update != Update::Stop));
 
-    Ok(())
+    // the tail's own clients are moved into it
+    let described = accounts.clone();
+    let mut runtime = Runtime::new()?;
+    let last = match output {
+        Output::Table if tui => {
+            let app = App::new(view.clock.clone(), view.markers, view.progress.clone());
+            let last = interactive::follow(&mut runtime, updates, app)?;
+            stopped(&last);
+            last
+        }
+        Output::Table => {
+            let (_, last, _) = runtime.block_on(updates.fold(
+                (Screen::new(Term::stdout(), plain), BTreeMap::new(), view),
+                move |(mut screen, mut stacks, mut view), update| {
+                    // fetching logs, denials, planned changes and initiators blocks on api
+                    // calls, so the view is updated off the runtime's threads
+                    let accounts = accounts.clone();
+                    blocking(move || {
+                        if let Update::States(stack_name, states) = update {
+                            if let Some(logs) = &mut view.logs {
+                                // only failures of the current operation are worth fetching logs of
+                                let recent_states = if view.resources {
+                                    &states[..]
+                                } else {
+                                    current_operation(&stack_name, &states)
+                                };
+                                logs.fetch(&accounts, &stack_name, &states, recent_states);
+                            }
+                            view.denials.fetch(&accounts, &stack_name, &states);
+                            if !view.resources {
+                                if let Some(start) = current_operation(&stack_name, &states)
+                                    .first()
+                                    .filter(|state| state.is_operation_start(&stack_name))
+                                {
+                                    view.planned.fetch(&accounts, &stack_name, start);
+                                }
+                            }
+                            if let Some(initiators) = &mut view.initiators {
+                                if let Some(start) = current_operation(&stack_name, &states)
+                                    .first()
+                                    .filter(|state| state.is_operation_start(&stack_name))
+                                {
+                                    initiators.fetch(&accounts, &stack_name, start);
+                                }
+                            }
+                            stacks.insert(stack_name, states);
+                        }
+                        if view.live {
+                            drop(title::set(
+                                &mut io::stdout(),
+                                &title::title(&stacks, view.progress.as_ref()),
+                            ));
+                        }
+                        let mut writer = TabWriter::new(Vec::new());
+                        render(&mut writer, &stacks, &view);
+                        let output = writer.into_inner().unwrap_or_default();
+                        let mut lines = String::from_utf8_lossy(&output)
+                            .lines()
+                            .map(String::from)
+                            .collect::<Vec<_>>();
+                        if view.split {
+                            let mut writer = TabWriter::new(Vec::new());
+                            render_latest(&mut writer, &stacks, &view);
+                            let output = writer.into_inner().unwrap_or_default();
+                            let mut head = String::from_utf8_lossy(&output)
+                                .lines()
+                                .map(String::from)
+                                .collect::<Vec<_>>();
+                            head.push(view.theme.muted(view.markers.divider()).to_string());
+                            // the header row stays above the rolling events it names
+                            if view.headers && !lines.is_empty() {
+                                head.push(lines.remove(0));
+                            }
+                            let (height, _) = Term::stdout().size();
+                            lines = rolling(head, lines, height as usize);
+                        }
+                        drop(screen.update(lines));
+                        Ok::<_, Error>((screen, stacks, view))
+                    })
+                    .flatten()
+                },
+            ))?;
+            for (stack_name, states) in last.iter().filter(|_| !resources) {
+                if let Some(cause) = root_cause(stack_name, states) {
+                    let label = if last.len() > 1 {
+                        Some(stack_name.as_str())
+                    } else {
+                        None
+                    };
+                    println!("\n{}", RootCause(label, cause, theme));
+                }
+            }
+            stopped(&last);
+            let mut failing = BTreeSet::new();
+            for (stack_name, states) in &last {
+                let states = if resources {
+                    states
+                } else {
+                    current_operation(stack_name, states)
+                };
+                // annotations are a courtesy to readers of the run, not part of its outcome
+                if let Err(err) = github::report(stack_name, states) {
+                    eprintln!(
+                        "{} failed to annotate the GitHub Actions run: {}",
+                        theme.notice("notice:"),
+                        err
+                    );
+                }
+                failing.extend(health::failing_services(states));
+            }
+            if check_health && !failing.is_empty() {
+                match health::issues(&failing) {
+                    Ok(issues) if issues.is_empty() => eprintln!(
+                        "{} no open issues with {}",
+                        "AWS Health:".bold().color(theme.warning),
+                        failing.into_iter().collect::<Vec<_>>().join(", ")
+                    ),
+                    Ok(issues) => {
+                        for issue in issues {
+                            eprintln!("{}", health::Notice(&issue, theme));
+                        }
+                    }
+                    Err(err) => eprintln!("{} {}", theme.notice("notice:"), err),
+                }
+            }
+            last
+        }
+        Output::Junit => {
+            let last = runtime.block_on(updates.fold(BTreeMap::new(), |mut stacks, update| {
+                if let Update::States(stack_name, states) = update {
+                    stacks.insert(stack_name, states);
+                }
+                Ok::<_, Error>(stacks)
+            }))?;
+            print!("{}", junit::Report(&last));
+            stopped(&last);
+            last
+        }
+    };
+    let statuses = final_statuses(&last, resources, &described, theme);
+    let code = if expect.is_empty() {
+        exit_code(&last, &statuses)
+    } else {
+        let unexpected = unexpected(&statuses, &expect);
+        for (stack_name, status) in &unexpected {
+            eprintln!(
+                "{} {} ended with {}, expected {}",
+                theme.notice("unexpected:"),
+                stack_name,
+                status.unwrap_or("status unknown"),
+                expect.join(" or ")
+            );
+        }
+        match (unexpected.is_empty(), exit_code(&last, &statuses)) {
+            (true, _) => 0,
+            (false, 0) => UNEXPECTED,
+            (false, code) => code,
+        }
+    };
+    match code {
+        0 => Ok(()),
+        code => process::exit(code),
+    }
 }
 
 #[cfg(test)]
@@ -316,6 +3468,24 @@ mod tests {
 
     use chrono_tz::America::New_York;
 
+    /// A resource state fixture shared by tests across modules
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
     #[test]
     fn state_communicates_followability() {
         for (state, expectation) in &[
@@ -337,10 +3507,12 @@ mod tests {
         ] {
             assert_eq!(
                 ResourceState {
+                    event_id: None,
                     resource_type: "foobar".into(),
                     timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")?,
                     status: status.to_string(),
                     resource_id: "foobar".into(),
+                    physical_id: None,
                     reason: "...".into(),
                 }
                 .complete_or_failed(),
@@ -355,10 +3527,12 @@ mod tests {
         for (resource_type, expectation) in &[(STACK_RESOURCE, true), ("not::a::stack", false)] {
             assert_eq!(
                 ResourceState {
+                    event_id: None,
                     resource_type: resource_type.to_string(),
                     timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")?,
                     status: "UPDATE_COMPLETE".into(),
                     resource_id: "foobar".into(),
+                    physical_id: None,
                     reason: "...".into()
                 }
                 .is_stack(),
@@ -383,6 +3557,678 @@ mod tests {
         assert!(State::Next(false, 0).complete())
     }
 
+    #[test]
+    fn stack_converts_to_resource_state() {
+        let state = ResourceState::from(Stack {
+            stack_name: "stack".into(),
+            stack_status: "UPDATE_IN_PROGRESS".into(),
+            creation_time: "2019-01-01T00:00:00Z".into(),
+            last_updated_time: Some("2019-01-02T00:00:00Z".into()),
+            ..Stack::default()
+        });
+        assert!(state.is_stack());
+        assert_eq!(state.resource_id, "stack");
+        assert_eq!(state.timestamp.to_rfc3339(), "2019-01-02T00:00:00+00:00");
+    }
+
+    #[test]
+    fn latest_keeps_most_recent_state_per_resource() {
+        let latest = latest(vec![
+            state("AWS::SQS::Queue", "a", "CREATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "b", "CREATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "a", "CREATE_COMPLETE"),
+        ]);
+        assert_eq!(
+            latest
+                .iter()
+                .map(|s| (s.resource_id.as_str(), s.status.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("a", "CREATE_COMPLETE"), ("b", "CREATE_IN_PROGRESS")]
+        );
+    }
+
+    #[test]
+    fn unchanged_counts_shared_leading_lines() {
+        let previous = vec!["a".to_string(), "b".into(), "c".into()];
+        assert_eq!(
+            unchanged(&previous, &["a".into(), "b".into(), "c".into(), "d".into()]),
+            3
+        );
+        assert_eq!(
+            unchanged(&previous, &["a".into(), "x".into(), "c".into()]),
+            1
+        );
+        assert_eq!(unchanged(&[], &previous), 0);
+    }
+
+    #[test]
+    fn unwritten_lines_keep_repeats_and_forget_what_is_no_longer_rendered() {
+        let lines = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut written = HashMap::new();
+        let first = lines(&["queue CREATE_IN_PROGRESS", "queue CREATE_IN_PROGRESS"]);
+        assert_eq!(unwritten(&mut written, &first).len(), 2);
+        let second = lines(&[
+            "queue  CREATE_IN_PROGRESS",
+            "queue  CREATE_IN_PROGRESS",
+            "queue  CREATE_COMPLETE",
+        ]);
+        assert_eq!(unwritten(&mut written, &second), vec![&second[2]]);
+        let third = lines(&["topic CREATE_IN_PROGRESS"]);
+        assert_eq!(unwritten(&mut written, &third), vec![&third[0]]);
+        assert_eq!(written.len(), 1);
+    }
+
+    #[test]
+    fn words_ignore_alignment() {
+        assert_eq!(
+            words("queue  \tAWS::SQS::Queue   CREATE_COMPLETE"),
+            words("queue AWS::SQS::Queue CREATE_COMPLETE")
+        );
+    }
+
+    #[test]
+    fn rows_account_for_wrapping() {
+        assert_eq!(rows("", 80), 1);
+        assert_eq!(rows(&"x".repeat(80), 80), 1);
+        assert_eq!(rows(&"x".repeat(81), 80), 2);
+        assert_eq!(rows(&"x".repeat(10).bold().to_string(), 5), 2);
+    }
+
+    #[test]
+    fn repeats_detect_unchanged_statuses() {
+        let states = vec![
+            state("AWS::ACM::Certificate", "cert", "CREATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "CREATE_IN_PROGRESS"),
+            state("AWS::ACM::Certificate", "cert", "CREATE_IN_PROGRESS"),
+            state("AWS::ACM::Certificate", "cert", "CREATE_COMPLETE"),
+        ];
+        assert_eq!(repeats(&states), vec![false, false, true, false]);
+    }
+
+    #[test]
+    fn summary_counts_current_operation() -> Result<(), chrono::format::ParseError> {
+        let states = vec![
+            state(STACK_RESOURCE, "stack", "CREATE_COMPLETE"),
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE"),
+            state("AWS::RDS::DBInstance", "db", "UPDATE_FAILED"),
+            state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_IN_PROGRESS"),
+        ];
+        let now = DateTime::parse_from_rfc3339("1996-12-19T16:41:57-08:00")?.with_timezone(&Utc);
+        assert_eq!(
+            summary("stack", &states, false, now),
+            "stack UPDATE_ROLLBACK_IN_PROGRESS: 1 complete, 1 failed, 2m00s elapsed"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn heading_shows_status_and_elapsed_time() -> Result<(), chrono::format::ParseError> {
+        let states = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE"),
+        ];
+        let now = DateTime::parse_from_rfc3339("1996-12-19T16:40:39-08:00")?.with_timezone(&Utc);
+        assert_eq!(
+            console::strip_ansi_codes(&heading(
+                "stack",
+                &states,
+                Markers::Ascii,
+                Theme::default(),
+                now
+            )),
+            "\\ stack UPDATE_IN_PROGRESS 42s"
+        );
+        Ok(())
+    }
+
+    fn statuses_of(
+        stacks: &BTreeMap<String, Vec<ResourceState>>
+    ) -> BTreeMap<String, Option<String>> {
+        stacks
+            .iter()
+            .map(|(stack_name, states)| {
+                let status = stack_status(stack_name, states).map(String::from);
+                (stack_name.clone(), status)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn exit_code_reflects_final_status() {
+        let exit_code = |statuses: &[&str]| {
+            let stacks = statuses
+                .iter()
+                .enumerate()
+                .map(|(i, status)| {
+                    let stack_name = format!("stack-{}", i);
+                    let states = vec![state(STACK_RESOURCE, &stack_name, status)];
+                    (stack_name, states)
+                })
+                .collect::<BTreeMap<_, _>>();
+            exit_code(&stacks, &statuses_of(&stacks))
+        };
+        assert_eq!(exit_code(&["UPDATE_COMPLETE", "DELETE_COMPLETE"]), 0);
+        assert_eq!(exit_code(&["CREATE_FAILED"]), FAILED_STATUS);
+        assert_eq!(exit_code(&["UPDATE_ROLLBACK_COMPLETE"]), ROLLED_BACK);
+        assert_eq!(
+            exit_code(&["ROLLBACK_COMPLETE", "UPDATE_ROLLBACK_FAILED"]),
+            FAILED_STATUS
+        );
+        assert_eq!(
+            exit_code(&["IMPORT_COMPLETE", "IMPORT_ROLLBACK_COMPLETE"]),
+            FAILED_STATUS
+        );
+        assert_eq!(
+            exit_code(&["UPDATE_COMPLETE", "UPDATE_IN_PROGRESS"]),
+            UNEXPECTED
+        );
+        assert_eq!(exit_code(&["REVIEW_IN_PROGRESS"]), UNEXPECTED);
+        assert_eq!(exit_code(&[]), 0);
+    }
+
+    #[test]
+    fn exit_code_is_unexpected_for_unknown_statuses() {
+        let mut stacks = BTreeMap::new();
+        stacks.insert("unknown".to_string(), vec![]);
+        assert_eq!(exit_code(&stacks, &statuses_of(&stacks)), UNEXPECTED);
+    }
+
+    #[test]
+    fn exit_code_uses_described_status_when_tailing_resources() {
+        // tailing resources leaves out the stack's own row
+        let mut stacks = BTreeMap::new();
+        stacks.insert(
+            "stack".to_string(),
+            vec![state("AWS::S3::Bucket", "bucket", "UPDATE_COMPLETE")],
+        );
+        let mut statuses = BTreeMap::new();
+        statuses.insert(
+            "stack".to_string(),
+            Some("UPDATE_ROLLBACK_FAILED".to_string()),
+        );
+        assert_eq!(exit_code(&stacks, &statuses), FAILED_STATUS);
+    }
+
+    #[test]
+    fn hook_failures_fail_without_ending_operations() {
+        assert!(is_failure("HOOK_COMPLETE_FAILED"));
+        assert!(!state(STACK_RESOURCE, "stack", "HOOK_COMPLETE_FAILED").complete_or_failed());
+        let mut stacks = BTreeMap::new();
+        stacks.insert(
+            "stack".to_string(),
+            vec![
+                state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+                state("AWS::S3::Bucket", "bucket", "HOOK_IN_PROGRESS"),
+                state("AWS::S3::Bucket", "bucket", "HOOK_COMPLETE_FAILED"),
+                state("AWS::S3::Bucket", "bucket", "UPDATE_COMPLETE"),
+                state(STACK_RESOURCE, "stack", "UPDATE_COMPLETE"),
+            ],
+        );
+        assert_eq!(
+            stack_status("stack", &stacks["stack"]),
+            Some("UPDATE_COMPLETE")
+        );
+        assert_eq!(exit_code(&stacks, &statuses_of(&stacks)), FAILED_STATUS);
+    }
+
+    #[test]
+    fn unexpected_lists_stacks_outside_expectations() {
+        let mut stacks = BTreeMap::new();
+        stacks.insert(
+            "created".to_string(),
+            vec![state(STACK_RESOURCE, "created", "CREATE_COMPLETE")],
+        );
+        stacks.insert(
+            "rolled-back".to_string(),
+            vec![state(STACK_RESOURCE, "rolled-back", "ROLLBACK_COMPLETE")],
+        );
+        stacks.insert("unknown".to_string(), vec![]);
+        assert_eq!(
+            unexpected(
+                &statuses_of(&stacks),
+                &["CREATE_COMPLETE".to_string(), "UPDATE_COMPLETE".to_string()]
+            ),
+            vec![
+                ("rolled-back", Some("ROLLBACK_COMPLETE")),
+                ("unknown", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn options_parse_multiple_stack_names() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "-f", "front-end", "back-end", "data"]).stack_names,
+            vec!["front-end", "back-end", "data"]
+        );
+    }
+
+    #[test]
+    fn options_parse_tags_without_stack_name() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "--tag", "team=payments", "--tag", "env=prod"]).tags,
+            vec![
+                "team=payments".parse().expect("invalid tag"),
+                "env=prod".parse().expect("invalid tag")
+            ]
+        );
+    }
+
+    #[test]
+    fn options_parse_matches_without_stack_name() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "--match", "prod-api-*"]).matches,
+            vec![Pattern::Glob("prod-api-*".into())]
+        );
+    }
+
+    #[test]
+    fn options_parse_regions() {
+        assert_eq!(
+            Options::from_iter(&[
+                "stack-tail",
+                "--region",
+                "us-east-1",
+                "--region",
+                "eu-west-1",
+                "foo"
+            ])
+            .regions,
+            vec![Region::UsEast1, Region::EuWest1]
+        );
+    }
+
+    #[test]
+    fn options_parse_account_roles() {
+        assert_eq!(
+            Options::from_iter(&[
+                "stack-tail",
+                "--account",
+                "111111111111:role/Deployer",
+                "foo"
+            ])
+            .account_roles,
+            vec!["111111111111:role/Deployer"
+                .parse()
+                .expect("invalid account role")]
+        );
+    }
+
+    #[test]
+    fn region_of_reads_stack_ids() {
+        assert_eq!(
+            region_of("arn:aws:cloudformation:eu-west-1:123456789012:stack/foo/uuid"),
+            Some(Region::EuWest1)
+        );
+        assert_eq!(region_of("foo"), None);
+    }
+
+    #[test]
+    fn label_color_is_stable_per_stack() {
+        assert_eq!(label_color("front-end"), label_color("front-end"));
+    }
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        assert_eq!(
+            wrap(
+                "Resource handler returned message: bucket already exists",
+                20
+            ),
+            vec![
+                "Resource handler",
+                "returned message:",
+                "bucket already",
+                "exists"
+            ]
+        );
+        assert_eq!(
+            wrap("arn:aws:iam::111111111111:role/x", 12),
+            vec!["arn:aws:iam:", ":11111111111", "1:role/x"]
+        );
+        assert!(wrap("", 20).is_empty());
+    }
+
+    #[test]
+    fn root_cause_skips_cancellations() {
+        let states = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            ResourceState {
+                reason: "Resource update cancelled".into(),
+                timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:58-08:00")
+                    .expect("invalid timestamp"),
+                ..state("AWS::Lambda::Function", "Handler", "UPDATE_FAILED")
+            },
+            ResourceState {
+                reason: "Bucket already exists".into(),
+                timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:59-08:00")
+                    .expect("invalid timestamp"),
+                ..state("AWS::S3::Bucket", "Bucket", "UPDATE_FAILED")
+            },
+            ResourceState {
+                reason: "Table limit exceeded".into(),
+                timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:40:00-08:00")
+                    .expect("invalid timestamp"),
+                ..state("AWS::DynamoDB::Table", "Table", "UPDATE_FAILED")
+            },
+            state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_IN_PROGRESS"),
+        ];
+        assert_eq!(
+            root_cause("stack", &states).map(|state| state.resource_id.as_str()),
+            Some("Bucket")
+        );
+        assert_eq!(root_cause("stack", &states[..1]), None);
+    }
+
+    #[test]
+    fn accumulate_keeps_each_event_once() {
+        let event = |id: &str, status: &str| ResourceState {
+            event_id: Some(id.into()),
+            ..state("AWS::SQS::Queue", "queue", status)
+        };
+        let known = accumulate(
+            Vec::new(),
+            vec![
+                event("1", "CREATE_IN_PROGRESS"),
+                event("2", "CREATE_COMPLETE"),
+            ],
+        );
+        let known = accumulate(
+            known,
+            vec![
+                event("2", "CREATE_COMPLETE"),
+                event("3", "UPDATE_IN_PROGRESS"),
+            ],
+        );
+        assert_eq!(
+            known
+                .iter()
+                .map(|state| state.event_id.as_deref().unwrap_or_default())
+                .collect::<Vec<_>>(),
+            vec!["1", "2", "3"]
+        );
+        let page = vec![state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS")];
+        assert_eq!(accumulate(known, page.clone()), page);
+    }
+
+    #[test]
+    fn current_operation_starts_at_latest_stack_operation() {
+        let states = vec![
+            state(STACK_RESOURCE, "stack", "CREATE_IN_PROGRESS"),
+            state(STACK_RESOURCE, "stack", "CREATE_COMPLETE"),
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state(STACK_RESOURCE, "nested", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_FAILED"),
+        ];
+        assert_eq!(current_operation("stack", &states).len(), 3);
+        assert_eq!(
+            current_operation(
+                "arn:aws:cloudformation:us-east-1:123456789012:stack/stack/abc",
+                &states
+            )
+            .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn state_is_rollback_pivot_aware() {
+        for (resource_type, resource_id, status, expectation) in &[
+            (STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_IN_PROGRESS", true),
+            (STACK_RESOURCE, "stack", "ROLLBACK_IN_PROGRESS", true),
+            (STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS", false),
+            (
+                STACK_RESOURCE,
+                "nested",
+                "UPDATE_ROLLBACK_IN_PROGRESS",
+                false,
+            ),
+        ] {
+            assert_eq!(
+                state(resource_type, resource_id, status).is_rollback_pivot("stack"),
+                *expectation
+            )
+        }
+    }
+
+    #[test]
+    fn operations_split_at_operation_starts() {
+        let states = vec![
+            state("AWS::SQS::Queue", "queue", "CREATE_COMPLETE"),
+            state(STACK_RESOURCE, "stack", "CREATE_COMPLETE"),
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state(STACK_RESOURCE, "stack", "UPDATE_COMPLETE"),
+            state(STACK_RESOURCE, "stack", "DELETE_IN_PROGRESS"),
+        ];
+        assert_eq!(
+            operations("stack", &states)
+                .iter()
+                .map(|operation| operation.len())
+                .collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+        assert!(operations("stack", &[]).is_empty());
+    }
+
+    #[test]
+    fn durations_span_first_progress_to_final_state() -> Result<(), chrono::format::ParseError> {
+        let at = |status: &str, timestamp: &str| -> Result<ResourceState, _> {
+            Ok(ResourceState {
+                timestamp: DateTime::parse_from_rfc3339(timestamp)?,
+                ..state("AWS::SQS::Queue", "queue", status)
+            })
+        };
+        let operation = vec![
+            at("CREATE_IN_PROGRESS", "2019-01-01T00:00:00Z")?,
+            at("CREATE_IN_PROGRESS", "2019-01-01T00:00:05Z")?,
+            at("CREATE_COMPLETE", "2019-01-01T00:03:12Z")?,
+        ];
+        assert_eq!(
+            durations(&operation).get("queue"),
+            Some(&chrono::Duration::seconds(192))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn elapsed_times_final_states_within_operations() -> Result<(), chrono::format::ParseError> {
+        let at = |resource_type: &str,
+                  resource_id: &str,
+                  status: &str,
+                  timestamp: &str|
+         -> Result<ResourceState, _> {
+            Ok(ResourceState {
+                timestamp: DateTime::parse_from_rfc3339(timestamp)?,
+                ..state(resource_type, resource_id, status)
+            })
+        };
+        let states = vec![
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "CREATE_IN_PROGRESS",
+                "2019-01-01T00:00:00Z",
+            )?,
+            at(
+                STACK_RESOURCE,
+                "stack",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-01T00:01:00Z",
+            )?,
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-01T00:01:10Z",
+            )?,
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_COMPLETE",
+                "2019-01-01T00:01:52Z",
+            )?,
+            at(
+                STACK_RESOURCE,
+                "stack",
+                "UPDATE_COMPLETE",
+                "2019-01-01T00:04:13Z",
+            )?,
+        ];
+        assert_eq!(
+            elapsed("stack", &states),
+            vec![
+                None,
+                None,
+                None,
+                Some(chrono::Duration::seconds(42)),
+                Some(chrono::Duration::seconds(193)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn humanize_renders_compact_durations() {
+        assert_eq!(humanize(chrono::Duration::seconds(42)), "42s");
+        assert_eq!(humanize(chrono::Duration::seconds(192)), "3m12s");
+        assert_eq!(humanize(chrono::Duration::seconds(3723)), "1h02m03s");
+    }
+
+    #[test]
+    fn color_is_disabled_by_non_empty_no_color() {
+        assert!(color_disabled_by_env(Some("1".into())));
+        assert!(!color_disabled_by_env(Some("".into())));
+        assert!(!color_disabled_by_env(None));
+    }
+
+    #[test]
+    fn rolling_keeps_the_most_recent_events_that_fit() {
+        let lines = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rolling(
+                lines(&["queue", "topic", "events"]),
+                lines(&["one", "two", "three"]),
+                6
+            ),
+            lines(&["queue", "topic", "events", "two", "three"])
+        );
+        assert_eq!(
+            rolling(lines(&["queue", "events"]), lines(&["one"]), 2),
+            lines(&["queue", "events"])
+        );
+    }
+
+    #[test]
+    fn truncate_cuts_lines_to_width() {
+        assert_eq!(truncate("CREATE_COMPLETE", 20), "CREATE_COMPLETE");
+        assert_eq!(truncate("CREATE_COMPLETE", 7), "CREATE…");
+        assert_eq!(
+            truncate("\x1b[1mCREATE_COMPLETE\x1b[0m", 7),
+            "\x1b[1mCREATE…\x1b[0m"
+        );
+        let link = format!(
+            "Handler {}",
+            Link(Links::Hyperlinks, "https://example.com", Markers::Ascii)
+        );
+        assert_eq!(
+            truncate(&link, 10),
+            "Handler \x1b]8;;https://example.com\x1b\\c…\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn markers_substitute_ascii_for_emoji() {
+        for (status, emoji, ascii) in &[
+            ("CREATE_COMPLETE", "✅", "[OK]"),
+            ("DELETE_COMPLETE", "⚰️ ", "[DEL]"),
+            ("UPDATE_FAILED", "❌", "[FAIL]"),
+            ("UPDATE_IN_PROGRESS", "🔄", "[..]"),
+            ("IMPORT_IN_PROGRESS", "🔄", "[..]"),
+            ("IMPORT_COMPLETE", "📥", "[IMP]"),
+            ("IMPORT_ROLLBACK_COMPLETE", "❌", "[FAIL]"),
+            ("HOOK_IN_PROGRESS", "🪝", "[HOOK]"),
+            ("HOOK_COMPLETE_SUCCEEDED", "✅", "[OK]"),
+            ("HOOK_COMPLETE_FAILED", "❌", "[FAIL]"),
+            ("HOOK_FAILED", "❌", "[FAIL]"),
+        ] {
+            assert_eq!(Markers::Emoji.status(status), *emoji);
+            assert_eq!(Markers::Ascii.status(status), *ascii);
+        }
+    }
+
+    #[test]
+    fn legend_explains_status_icons() {
+        assert_eq!(
+            Markers::Ascii.legend(),
+            "[OK] complete  [DEL] deleted  [IMP] imported  [FAIL] failed  [..] in progress  \
+             [HOOK] hook running"
+        );
+    }
+
+    #[test]
+    fn plain_markers_spell_out_statuses() {
+        assert_eq!(Markers::Plain.status("UPDATE_FAILED"), "FAILED:");
+        assert_eq!(Markers::Plain.status("CREATE_COMPLETE"), "COMPLETE:");
+        assert_eq!(Markers::Plain.status("UPDATE_IN_PROGRESS"), "IN PROGRESS:");
+        assert_eq!(Markers::Plain.pivot(), Markers::Ascii.pivot());
+    }
+
+    #[test]
+    fn configured_markers_fall_back_on_base() {
+        let markers = Markers::configured(
+            true,
+            IconsConfig {
+                failed: Some("✗".into()),
+                ..IconsConfig::default()
+            },
+        );
+        assert_eq!(markers.status("UPDATE_FAILED"), "✗");
+        assert_eq!(markers.status("UPDATE_COMPLETE"), "[OK]");
+        assert_eq!(markers.pivot(), Markers::Ascii.pivot());
+        assert_eq!(
+            Markers::configured(false, IconsConfig::default()),
+            Markers::Emoji
+        );
+    }
+
+    #[test]
+    fn ago_renders_elapsed_time() -> Result<(), chrono::format::ParseError> {
+        assert_eq!(
+            ago(
+                DateTime::parse_from_rfc3339("2019-01-01T00:00:00-05:00")?,
+                DateTime::parse_from_rfc3339("2019-01-01T05:00:42Z")?.with_timezone(&Utc)
+            ),
+            "42s ago"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clock_formats_timestamps_in_timezone() -> Result<(), String> {
+        let clock = Clock {
+            timezone: Some(New_York),
+            format: Some("%H:%M:%S".parse()?),
+            ..Clock::default()
+        };
+        assert_eq!(
+            state("AWS::SQS::Queue", "queue", "CREATE_COMPLETE").timestamp(&clock),
+            "19:39:57"
+        );
+        assert!("%H:%".parse::<TimeFormat>().is_err());
+        Ok(())
+    }
+
     #[test]
     fn options_require_stack_name() {
         assert!(Options::from_iter_safe(&["stack-tail"]).is_err())
@@ -395,9 +4241,244 @@ mod tests {
             Options {
                 resources: false,
                 timezone: Some(New_York),
+                time_format: None,
+                relative: false,
+                follow: false,
+                forever: false,
+                wait_for_create: false,
+                state_file: None,
+                interval: Duration::from_secs(1),
+                timeout: None,
+                expect: vec![],
+                output: Output::Table,
+                format: None,
+                status_filter: None,
+                types: vec![],
+                resource_ids: vec![],
+                exclude_types: vec![],
+                exclude_resource_ids: vec![],
+                max_depth: None,
+                include_nested: vec![],
+                exclude_nested: vec![],
+                failed_only: false,
+                since: None,
+                until: None,
+                lines: None,
+                current_operation: false,
+                transitions_only: false,
+                physical_ids: false,
+                links: false,
+                logs: false,
+                who: false,
+                full_reasons: false,
+                metadata: false,
+                headers: false,
+                legend: false,
+                sort_by: None,
+                reverse: false,
+                diff_since: None,
+                notify_on: vec![],
+                notify: false,
+                notify_slack: None,
+                notify_slack_failures: false,
+                notify_teams: None,
+                notify_discord: None,
+                pagerduty_routing_key: None,
+                email: vec![],
+                email_from: None,
+                ses: false,
+                archive_to: None,
+                notify_sns: None,
+                webhook: None,
+                webhook_headers: vec![],
+                emit_eventbridge: None,
+                no_color: false,
+                ascii: false,
+                plain: false,
+                split: false,
+                tui: false,
+                check_health: false,
+                application: None,
+                tags: vec![],
+                matches: vec![],
+                no_cache: false,
+                regions: vec![],
+                account_roles: vec![],
+                stack_names: vec!["foo".into()],
+                command: None,
+            }
+        )
+    }
+
+    #[test]
+    fn options_require_drift_stack_names_or_all() {
+        assert!(Options::from_iter_safe(&["stack-tail", "drift"]).is_err())
+    }
+
+    #[test]
+    fn options_parse_drift_without_stack_name() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "drift", "--all", "--out", "report.json"]),
+            Options {
+                resources: false,
+                timezone: None,
+                time_format: None,
+                relative: false,
                 follow: false,
-                stack_name: "foo".into(),
+                forever: false,
+                wait_for_create: false,
+                state_file: None,
+                interval: Duration::from_secs(1),
+                timeout: None,
+                expect: vec![],
+                output: Output::Table,
+                format: None,
+                status_filter: None,
+                types: vec![],
+                resource_ids: vec![],
+                exclude_types: vec![],
+                exclude_resource_ids: vec![],
+                max_depth: None,
+                include_nested: vec![],
+                exclude_nested: vec![],
+                failed_only: false,
+                since: None,
+                until: None,
+                lines: None,
+                current_operation: false,
+                transitions_only: false,
+                physical_ids: false,
+                links: false,
+                logs: false,
+                who: false,
+                full_reasons: false,
+                metadata: false,
+                headers: false,
+                legend: false,
+                sort_by: None,
+                reverse: false,
+                diff_since: None,
+                notify_on: vec![],
+                notify: false,
+                notify_slack: None,
+                notify_slack_failures: false,
+                notify_teams: None,
+                notify_discord: None,
+                pagerduty_routing_key: None,
+                email: vec![],
+                email_from: None,
+                ses: false,
+                archive_to: None,
+                notify_sns: None,
+                webhook: None,
+                webhook_headers: vec![],
+                emit_eventbridge: None,
+                no_color: false,
+                ascii: false,
+                plain: false,
+                split: false,
+                tui: false,
+                check_health: false,
+                application: None,
+                tags: vec![],
+                matches: vec![],
+                no_cache: false,
+                regions: vec![],
+                account_roles: vec![],
+                stack_names: vec![],
+                command: Some(Command::Drift(drift::Options {
+                    all: true,
+                    out: Some("report.json".into()),
+                    no_cache: false,
+                    stack_names: vec![],
+                })),
             }
         )
     }
+
+    #[test]
+    fn options_parse_junit_output() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "--output", "junit", "foo"]).output,
+            Output::Junit
+        )
+    }
+
+    #[test]
+    fn options_parse_line_format() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "--format", "{id} {status}", "foo"]).format,
+            Some("{id} {status}".parse().expect("invalid format"))
+        )
+    }
+
+    #[test]
+    fn options_parse_status_filter() {
+        assert_eq!(
+            Options::from_iter(&["stack-tail", "--status-filter", "*_FAILED", "foo"]).status_filter,
+            Some(Pattern::Glob("*_FAILED".into()))
+        )
+    }
+
+    #[test]
+    fn options_parse_repeated_types() {
+        assert_eq!(
+            Options::from_iter(&[
+                "stack-tail",
+                "--type",
+                "AWS::Lambda::Function",
+                "--type",
+                "AWS::IAM::*",
+                "foo"
+            ])
+            .types,
+            vec![
+                Pattern::Glob("AWS::Lambda::Function".into()),
+                Pattern::Glob("AWS::IAM::*".into())
+            ]
+        )
+    }
+
+    #[test]
+    fn options_parse_resource_ids_apart_from_resources_flag() {
+        let options = Options::from_iter(&["stack-tail", "--resource", "Api*", "foo"]);
+        assert_eq!(options.resource_ids, vec![Pattern::Glob("Api*".into())]);
+        assert!(!options.resources);
+    }
+
+    #[test]
+    fn options_parse_sort_by_for_resources() {
+        let options = Options::from_iter(&[
+            "stack-tail",
+            "--resources",
+            "--sort-by",
+            "status",
+            "--reverse",
+            "foo",
+        ]);
+        assert_eq!(options.sort_by, Some(SortBy::Status));
+        assert!(options.reverse);
+        assert!(Options::from_iter_safe(&["stack-tail", "--sort-by", "status", "foo"]).is_err());
+    }
+
+    #[test]
+    fn options_parse_notify_on_triggers() {
+        assert_eq!(
+            Options::from_iter(&[
+                "stack-tail",
+                "--notify-on",
+                "first-failure,rollback-start",
+                "foo"
+            ])
+            .notify_on,
+            vec![Trigger::FirstFailure, Trigger::RollbackStart]
+        )
+    }
+
+    #[test]
+    fn options_parse_application_without_stack_name() {
+        let options = Options::from_iter(&["stack-tail", "--application", "storefront"]);
+        assert_eq!(options.application, Some("storefront".into()));
+        assert!(options.stack_names.is_empty());
+    }
 }
@@ -0,0 +1,131 @@
+//! A header of a stack's metadata, giving context to its events without a trip to the console
+use crate::{rollback::Triggers, Clock, Error};
+use chrono::DateTime;
+use colored::Colorize;
+use rusoto_cloudformation::{CloudFormation, CloudFormationClient, DescribeStacksInput, Stack};
+use std::fmt;
+
+/// A stack's description, if it exists
+pub fn describe(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<Option<Stack>, Error> {
+    Ok(cf
+        .describe_stacks(DescribeStacksInput {
+            stack_name: Some(stack_name.into()),
+            ..DescribeStacksInput::default()
+        })
+        .sync()?
+        .stacks
+        .unwrap_or_default()
+        .into_iter()
+        .next())
+}
+
+/// Provides a means of displaying a stack's tags, service role, termination
+/// protection, rollback alarms, capabilities and creation time
+pub struct Header<'a>(pub &'a Stack, pub &'a Clock);
+
+impl fmt::Display for Header<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Header(stack, clock) = self;
+        let none = || "none".bright_black().to_string();
+        let list = |items: Vec<String>| {
+            if items.is_empty() {
+                none()
+            } else {
+                items.join(", ")
+            }
+        };
+        writeln!(f, "{}", stack.stack_name.bold())?;
+        writeln!(
+            f,
+            "  {:<13}{}",
+            "created",
+            DateTime::parse_from_rfc3339(&stack.creation_time)
+                .map(|created| clock.timestamp(created))
+                .unwrap_or_else(|_| stack.creation_time.clone())
+        )?;
+        writeln!(
+            f,
+            "  {:<13}{}",
+            "role",
+            stack.role_arn.clone().unwrap_or_else(none)
+        )?;
+        writeln!(
+            f,
+            "  {:<13}{}",
+            "protection",
+            if stack.enable_termination_protection.unwrap_or_default() {
+                "enabled".bold().bright_green()
+            } else {
+                "disabled".normal()
+            }
+        )?;
+        writeln!(
+            f,
+            "  {:<13}{}",
+            "rollback",
+            Triggers(stack.rollback_configuration.as_ref())
+        )?;
+        writeln!(
+            f,
+            "  {:<13}{}",
+            "capabilities",
+            list(stack.capabilities.clone().unwrap_or_default())
+        )?;
+        writeln!(
+            f,
+            "  {:<13}{}",
+            "tags",
+            list(
+                stack
+                    .tags
+                    .iter()
+                    .flatten()
+                    .map(|tag| format!("{}={}", tag.key, tag.value))
+                    .collect()
+            )
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudformation::Tag;
+
+    #[test]
+    fn header_lists_stack_metadata() {
+        let stack = Stack {
+            stack_name: "my-stack".into(),
+            creation_time: "2019-06-01T12:00:00Z".into(),
+            enable_termination_protection: Some(true),
+            capabilities: Some(vec!["CAPABILITY_IAM".into()]),
+            tags: Some(vec![
+                Tag {
+                    key: "team".into(),
+                    value: "payments".into(),
+                },
+                Tag {
+                    key: "env".into(),
+                    value: "prod".into(),
+                },
+            ]),
+            ..Stack::default()
+        };
+        assert_eq!(
+            console::strip_ansi_codes(&Header(&stack, &Clock::default()).to_string()),
+            "my-stack\n  \
+             created      2019-06-01 12:00:00 +00:00\n  \
+             role         none\n  \
+             protection   enabled\n  \
+             rollback     none\n  \
+             capabilities CAPABILITY_IAM\n  \
+             tags         team=payments, env=prod\n"
+        );
+    }
+}
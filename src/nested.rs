@@ -0,0 +1,538 @@
+//! Nested stacks tailed along with the stacks they belong to
+use crate::{
+    accumulate, filter::Pattern, stack_logical_id, Error, ResourceState, States, FAILED,
+    IN_PROGRESS,
+};
+use futures::{Async, Poll, Stream};
+use std::{collections::HashSet, mem};
+
+/// Which nested stacks are tailed
+#[derive(Debug, Default, Clone)]
+pub struct Scope {
+    /// How many levels of nested stacks deep to tail, when limited
+    pub max_depth: Option<usize>,
+    /// Patterns one of which nested stacks' logical ids or names must match, if any
+    pub include: Vec<Pattern>,
+    /// Patterns which exclude nested stacks whose logical ids or names they match
+    pub exclude: Vec<Pattern>,
+}
+
+impl Scope {
+    fn admits(
+        &self,
+        depth: usize,
+        logical_id: &str,
+        stack_name: &str,
+    ) -> bool {
+        let matches =
+            |pattern: &Pattern| pattern.matches(logical_id) || pattern.matches(stack_name);
+        self.max_depth.iter().all(|max_depth| depth <= *max_depth)
+            && (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+/// A stack tailed as part of a family
+struct Member {
+    /// Name or id the stack is tailed by
+    stack_name: String,
+    /// Whether this is the stack tailing began with rather than a nested stack
+    root: bool,
+    /// Levels of nesting beneath the stack tailing began with
+    depth: usize,
+    states: States,
+    /// Events received so far
+    known: Vec<ResourceState>,
+    done: bool,
+}
+
+impl Member {
+    fn new(
+        stack_name: String,
+        depth: usize,
+        states: States,
+    ) -> Self {
+        Member {
+            stack_name,
+            root: depth == 0,
+            depth,
+            states,
+            known: Vec::new(),
+            done: false,
+        }
+    }
+}
+
+/// Tails a stack along with its nested stacks, interleaving their events with
+/// the stack's own with logical ids prefixed by their stack name, or arranging
+/// their resources beneath them
+pub struct Family<F> {
+    /// Tails a nested stack given its id
+    tail: F,
+    /// Whether states are arranged as a tree, with each nested stack's
+    /// resources indented beneath it, rather than in chronological order
+    tree: bool,
+    scope: Scope,
+    members: Vec<Member>,
+    /// Ids of nested stacks tailed so far
+    tailed: HashSet<String>,
+    len: usize,
+}
+
+impl<F> Family<F>
+where
+    F: Fn(String) -> States,
+{
+    pub fn new(
+        stack_name: String,
+        states: States,
+        tree: bool,
+        scope: Scope,
+        tail: F,
+    ) -> Self {
+        Family {
+            tail,
+            tree,
+            scope,
+            members: vec![Member::new(stack_name, 0, states)],
+            tailed: HashSet::new(),
+            len: 0,
+        }
+    }
+
+    /// States of a member followed by those of its nested stacks, indented by depth
+    fn branch(
+        &self,
+        member: &Member,
+        depth: usize,
+        states: &mut Vec<ResourceState>,
+    ) {
+        for state in &member.known {
+            states.push(ResourceState {
+                resource_id: format!("{}{}", "  ".repeat(depth), state.resource_id),
+                ..state.clone()
+            });
+            let child = self.members.iter().find(|child| {
+                !child.root
+                    && state.is_stack()
+                    && state.physical_id.as_ref() == Some(&child.stack_name)
+            });
+            if let Some(child) = child {
+                self.branch(child, depth + 1, states);
+            }
+        }
+    }
+
+    /// States of the stack and its nested stacks, as a tree or in chronological order
+    fn states(&self) -> Vec<ResourceState> {
+        let mut states = Vec::new();
+        if self.tree {
+            self.branch(&self.members[0], 0, &mut states);
+            return states;
+        }
+        for member in &self.members {
+            if member.root {
+                states.extend(member.known.iter().cloned());
+                continue;
+            }
+            // the parent reports the nested stack's own status as one of its resources
+            let name = stack_logical_id(&member.stack_name);
+            states.extend(
+                member
+                    .known
+                    .iter()
+                    .filter(|state| !(state.is_stack() && state.resource_id == name))
+                    .map(|state| ResourceState {
+                        resource_id: format!("{}/{}", name, state.resource_id),
+                        ..state.clone()
+                    }),
+            );
+        }
+        states.sort_by_key(|state| state.timestamp);
+        states
+    }
+}
+
+/// Logical and physical ids of the nested stacks among a stack's events whose
+/// physical ids are known
+fn nested<'a>(
+    stack_name: &str,
+    states: &'a [ResourceState],
+) -> Vec<(&'a str, &'a str)> {
+    let mut ids = Vec::new();
+    for state in states {
+        if !state.is_stack() || state.resource_id == stack_logical_id(stack_name) {
+            continue;
+        }
+        if let Some(id) = state.physical_id.as_deref() {
+            if !ids.iter().any(|(_, known)| *known == id) {
+                ids.push((state.resource_id.as_str(), id));
+            }
+        }
+    }
+    ids
+}
+
+/// The failures within nested stacks which caused a nested stack to fail, from
+/// the first failure of its own resources down to the leaf failure
+pub fn causes<'a>(
+    states: &'a [ResourceState],
+    failure: &'a ResourceState,
+) -> Vec<&'a ResourceState> {
+    let mut chain = Vec::new();
+    let mut failure = failure;
+    while failure.is_stack()
+        && failure.status.ends_with(FAILED)
+        && failure.reason.starts_with("Embedded stack")
+    {
+        let name = match failure.physical_id.as_deref() {
+            Some(id) => stack_logical_id(id),
+            None => break,
+        };
+        // the nested stack's failures since its parent began changing it
+        let start = states
+            .iter()
+            .filter(|state| {
+                state.resource_id == failure.resource_id
+                    && state.status.ends_with(IN_PROGRESS)
+                    && state.timestamp <= failure.timestamp
+            })
+            .map(|state| state.timestamp)
+            .last();
+        let prefix = format!("{}/", name);
+        let cause = states.iter().find(|state| {
+            state.resource_id.starts_with(&prefix)
+                && state.status.ends_with(FAILED)
+                && start.iter().all(|start| state.timestamp >= *start)
+                && state.timestamp <= failure.timestamp
+        });
+        match cause {
+            Some(cause) => {
+                chain.push(cause);
+                failure = cause;
+            }
+            None => break,
+        }
+    }
+    chain
+}
+
+impl<F> Stream for Family<F>
+where
+    F: Fn(String) -> States,
+{
+    type Item = (usize, Vec<ResourceState>);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Error> {
+        let mut changed = false;
+        // members discovered along the way are polled in turn
+        let mut index = 0;
+        while index < self.members.len() {
+            let mut discovered = Vec::new();
+            let scope = &self.scope;
+            let member = &mut self.members[index];
+            let depth = member.depth + 1;
+            while !member.done {
+                match member.states.poll()? {
+                    Async::Ready(Some((_, page))) => {
+                        member.known = accumulate(mem::take(&mut member.known), page);
+                        changed = true;
+                        // nested stacks created mid-deploy are tailed as soon
+                        // as their physical ids are reported
+                        discovered.extend(
+                            nested(&member.stack_name, &member.known)
+                                .into_iter()
+                                .filter(|(logical_id, id)| {
+                                    scope.admits(depth, logical_id, stack_logical_id(id))
+                                })
+                                .map(|(_, id)| id.to_string()),
+                        );
+                    }
+                    Async::Ready(None) => member.done = true,
+                    Async::NotReady => break,
+                }
+            }
+            for id in discovered {
+                if self.tailed.insert(id.clone()) {
+                    let states = (self.tail)(id.clone());
+                    self.members.push(Member::new(id, depth, states));
+                }
+            }
+            index += 1;
+        }
+        if changed {
+            let states = self.states();
+            let len = mem::replace(&mut self.len, states.len());
+            return Ok(Async::Ready(Some((len, states))));
+        }
+        if self.members.iter().all(|member| member.done) {
+            return Ok(Async::Ready(None));
+        }
+        Ok(Async::NotReady)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+    use chrono::DateTime;
+    use futures::stream;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    const CHILD: &str = "arn:aws:cloudformation:us-east-1:123456789012:stack/stack-Child-1AB/uuid";
+
+    fn at(
+        state: ResourceState,
+        timestamp: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: Some(format!("{}-{}", state.resource_id, state.status)),
+            timestamp: DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp"),
+            ..state
+        }
+    }
+
+    #[test]
+    fn nested_lists_known_nested_stacks() {
+        let states = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state(STACK_RESOURCE, "Child", "UPDATE_IN_PROGRESS"),
+            ResourceState {
+                physical_id: Some(CHILD.into()),
+                ..state(STACK_RESOURCE, "Child", "UPDATE_IN_PROGRESS")
+            },
+            ResourceState {
+                physical_id: Some("queue-url".into()),
+                ..state("AWS::SQS::Queue", "Queue", "UPDATE_IN_PROGRESS")
+            },
+        ];
+        assert_eq!(nested("stack", &states), vec![("Child", CHILD)]);
+    }
+
+    #[test]
+    fn scope_limits_depth_and_names() -> Result<(), String> {
+        let scope = Scope {
+            max_depth: Some(1),
+            include: vec![],
+            exclude: vec!["*Pipeline*".parse()?],
+        };
+        assert!(scope.admits(1, "Database", "stack-Database-1AB"));
+        assert!(!scope.admits(2, "Database", "stack-Database-1AB"));
+        assert!(!scope.admits(1, "Pipeline", "stack-Pipeline-1AB"));
+        let scope = Scope {
+            include: vec!["Data*".parse()?],
+            ..Scope::default()
+        };
+        assert!(scope.admits(5, "Database", "stack-Database-1AB"));
+        assert!(!scope.admits(1, "Queue", "stack-Queue-1AB"));
+        Ok(())
+    }
+
+    #[test]
+    fn causes_chain_down_to_leaf_failure() {
+        let grandchild =
+            "arn:aws:cloudformation:us-east-1:123456789012:stack/stack-Child-1AB-Db-2CD/uuid";
+        let embedded = |resource_id: &str, physical_id: &str, timestamp: &str| {
+            at(
+                ResourceState {
+                    physical_id: Some(physical_id.into()),
+                    reason: "Embedded stack was not successfully created".into(),
+                    ..state(STACK_RESOURCE, resource_id, "CREATE_FAILED")
+                },
+                timestamp,
+            )
+        };
+        let states = vec![
+            at(
+                state(STACK_RESOURCE, "Child", "CREATE_IN_PROGRESS"),
+                "2019-01-01T00:00:00Z",
+            ),
+            at(
+                state(STACK_RESOURCE, "stack-Child-1AB/Db", "CREATE_IN_PROGRESS"),
+                "2019-01-01T00:00:01Z",
+            ),
+            at(
+                state(
+                    "AWS::RDS::DBInstance",
+                    "stack-Child-1AB-Db-2CD/Instance",
+                    "CREATE_FAILED",
+                ),
+                "2019-01-01T00:00:02Z",
+            ),
+            embedded("stack-Child-1AB/Db", grandchild, "2019-01-01T00:00:03Z"),
+            at(
+                state("AWS::SQS::Queue", "stack-Child-1AB/Queue", "CREATE_FAILED"),
+                "2019-01-01T00:00:04Z",
+            ),
+            embedded("Child", CHILD, "2019-01-01T00:00:05Z"),
+        ];
+        assert_eq!(
+            causes(&states, &states[5])
+                .iter()
+                .map(|state| state.resource_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["stack-Child-1AB/Db", "stack-Child-1AB-Db-2CD/Instance"]
+        );
+    }
+
+    #[test]
+    fn family_interleaves_nested_stack_events() {
+        let root: States = Box::new(stream::iter_ok(vec![(
+            0,
+            vec![
+                at(
+                    state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+                    "2019-01-01T00:00:00Z",
+                ),
+                at(
+                    ResourceState {
+                        physical_id: Some(CHILD.into()),
+                        ..state(STACK_RESOURCE, "Child", "UPDATE_IN_PROGRESS")
+                    },
+                    "2019-01-01T00:00:01Z",
+                ),
+                at(
+                    state(STACK_RESOURCE, "Child", "UPDATE_COMPLETE"),
+                    "2019-01-01T00:00:04Z",
+                ),
+            ],
+        )]));
+        let family = Family::new(
+            "stack".into(),
+            root,
+            false,
+            Scope::default(),
+            |_| -> States {
+                Box::new(stream::iter_ok(vec![(
+                    0,
+                    vec![
+                        at(
+                            state(STACK_RESOURCE, "stack-Child-1AB", "UPDATE_IN_PROGRESS"),
+                            "2019-01-01T00:00:02Z",
+                        ),
+                        at(
+                            state("AWS::SQS::Queue", "Queue", "UPDATE_COMPLETE"),
+                            "2019-01-01T00:00:03Z",
+                        ),
+                    ],
+                )]))
+            },
+        );
+        let (_, states) = family
+            .wait()
+            .last()
+            .expect("no states")
+            .expect("failed to tail");
+        assert_eq!(
+            states
+                .iter()
+                .map(|state| state.resource_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["stack", "Child", "stack-Child-1AB/Queue", "Child"]
+        );
+    }
+
+    #[test]
+    fn family_discovers_nested_stacks_mid_deploy() {
+        let started = at(
+            state(STACK_RESOURCE, "stack", "CREATE_IN_PROGRESS"),
+            "2019-01-01T00:00:00Z",
+        );
+        let root: States = Box::new(stream::iter_ok(vec![
+            (0, vec![started.clone()]),
+            (
+                1,
+                vec![
+                    started,
+                    at(
+                        ResourceState {
+                            physical_id: Some(CHILD.into()),
+                            ..state(STACK_RESOURCE, "Child", "CREATE_IN_PROGRESS")
+                        },
+                        "2019-01-01T00:00:01Z",
+                    ),
+                ],
+            ),
+        ]));
+        let family = Family::new(
+            "stack".into(),
+            root,
+            false,
+            Scope::default(),
+            |_| -> States {
+                Box::new(stream::iter_ok(vec![(
+                    0,
+                    vec![at(
+                        state("AWS::SQS::Queue", "Queue", "CREATE_IN_PROGRESS"),
+                        "2019-01-01T00:00:02Z",
+                    )],
+                )]))
+            },
+        );
+        let (_, states) = family
+            .wait()
+            .last()
+            .expect("no states")
+            .expect("failed to tail");
+        assert_eq!(
+            states.last().map(|state| state.resource_id.as_str()),
+            Some("stack-Child-1AB/Queue")
+        );
+    }
+
+    #[test]
+    fn family_tree_indents_nested_stack_resources() {
+        let root: States = Box::new(stream::iter_ok(vec![(
+            0,
+            vec![
+                ResourceState {
+                    physical_id: Some(CHILD.into()),
+                    ..state(STACK_RESOURCE, "Child", "CREATE_COMPLETE")
+                },
+                state("AWS::SNS::Topic", "Topic", "CREATE_COMPLETE"),
+            ],
+        )]));
+        let family = Family::new(
+            "stack".into(),
+            root,
+            true,
+            Scope::default(),
+            |_| -> States {
+                Box::new(stream::iter_ok(vec![(
+                    0,
+                    vec![state("AWS::SQS::Queue", "Queue", "CREATE_COMPLETE")],
+                )]))
+            },
+        );
+        let (_, states) = family
+            .wait()
+            .last()
+            .expect("no states")
+            .expect("failed to tail");
+        assert_eq!(
+            states
+                .iter()
+                .map(|state| state.resource_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Child", "  Queue", "Topic"]
+        );
+    }
+}
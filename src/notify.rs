@@ -0,0 +1,1227 @@
+//! Notifications of notable stack events delivered to external channels
+use crate::{
+    credentials, current_operation, humanize, is_failure, is_hook, links, region_of, root_cause,
+    stack_logical_id, Error, ResourceState, FAILED,
+};
+use rusoto_core::{request::HttpClient, Region};
+use rusoto_events::{
+    CloudWatchEvents, CloudWatchEventsClient, PutEventsRequest, PutEventsRequestEntry,
+};
+use rusoto_ses::{Body, Content, Destination, Message, SendEmailRequest, Ses as _, SesClient};
+use rusoto_sns::{PublishInput, Sns as _, SnsClient};
+use serde_json::{json, Value};
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+/// Something worth telling people about
+#[derive(Debug, PartialEq)]
+pub enum Notification<'a> {
+    /// A new event of any kind
+    Event(&'a ResourceState),
+    /// A resource failed
+    Failure(&'a ResourceState),
+    /// The stack began rolling back its changes
+    RollbackStart(&'a ResourceState),
+    /// The stack reached a terminal state
+    Complete(&'a ResourceState, Summary<'a>),
+}
+
+/// How a completed operation went
+#[derive(Debug, PartialEq, Default)]
+pub struct Summary<'a> {
+    /// Time from the operation's first event to its last
+    pub duration: Option<chrono::Duration>,
+    /// The failure which set off the operation failing, if it did
+    pub root_cause: Option<&'a ResourceState>,
+    /// Resources which failed during the operation
+    pub failures: Vec<&'a ResourceState>,
+}
+
+impl<'a> Summary<'a> {
+    /// A summary of a stack's most recent operation
+    pub fn of(
+        stack_name: &str,
+        states: &'a [ResourceState],
+    ) -> Self {
+        let operation = current_operation(stack_name, states);
+        Summary {
+            duration: match (operation.first(), operation.last()) {
+                (Some(first), Some(last)) => Some(last.timestamp - first.timestamp),
+                _ => None,
+            },
+            root_cause: root_cause(stack_name, states),
+            failures: operation
+                .iter()
+                .filter(|state| {
+                    state.status.ends_with(FAILED)
+                        && !(state.is_stack() && state.resource_id == stack_logical_id(stack_name))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Conditions within a stack operation which trigger notifications
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Trigger {
+    /// Each of the operation's events
+    Event,
+    /// The operation's first failed resource
+    FirstFailure,
+    /// Each of the operation's failed resources
+    Failure,
+    /// The stack pivoting into rolling back the operation
+    RollbackStart,
+    /// The operation reaching a terminal state
+    Complete,
+}
+
+impl FromStr for Trigger {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-failure" => Ok(Trigger::FirstFailure),
+            "failure" => Ok(Trigger::Failure),
+            "rollback-start" => Ok(Trigger::RollbackStart),
+            "complete" => Ok(Trigger::Complete),
+            other => Err(format!("unsupported trigger {}", other)),
+        }
+    }
+}
+
+impl Trigger {
+    /// The events satisfying this trigger within a stack's most recent operation
+    fn conditions<'a>(
+        self,
+        stack_name: &str,
+        states: &'a [ResourceState],
+    ) -> Vec<&'a ResourceState> {
+        let operation = current_operation(stack_name, states);
+        // failures of resources, rather than of the stack's own operation or of hooks
+        let mut failures = operation.iter().filter(|state| {
+            state.status.ends_with(FAILED) && !state.is_stack() && !is_hook(&state.status)
+        });
+        match self {
+            Trigger::Event => operation.iter().collect(),
+            Trigger::FirstFailure => failures.next().into_iter().collect(),
+            Trigger::Failure => failures.collect(),
+            Trigger::RollbackStart => operation
+                .iter()
+                .find(|state| state.is_rollback_pivot(stack_name))
+                .into_iter()
+                .collect(),
+            Trigger::Complete => operation
+                .last()
+                .filter(|state| {
+                    state.is_stack()
+                        && state.resource_id == stack_logical_id(stack_name)
+                        && state.complete_or_failed()
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn notification<'a>(
+        self,
+        stack_name: &str,
+        states: &'a [ResourceState],
+        state: &'a ResourceState,
+    ) -> Notification<'a> {
+        match self {
+            Trigger::Event => Notification::Event(state),
+            Trigger::FirstFailure | Trigger::Failure => Notification::Failure(state),
+            Trigger::RollbackStart => Notification::RollbackStart(state),
+            Trigger::Complete => Notification::Complete(state, Summary::of(stack_name, states)),
+        }
+    }
+}
+
+/// Notifications for trigger conditions met by a stack's latest events
+/// which weren't already met by its previous events
+pub fn triggered<'a>(
+    triggers: &[Trigger],
+    stack_name: &str,
+    previous: &[ResourceState],
+    current: &'a [ResourceState],
+) -> Vec<Notification<'a>> {
+    let key = |state: &ResourceState| (state.event_id.clone(), state.timestamp);
+    triggers
+        .iter()
+        .flat_map(|trigger| {
+            let met = trigger
+                .conditions(stack_name, previous)
+                .into_iter()
+                .map(key)
+                .collect::<Vec<_>>();
+            trigger
+                .conditions(stack_name, current)
+                .into_iter()
+                .filter(move |state| !met.contains(&key(state)))
+                .map(move |state| trigger.notification(stack_name, current, state))
+        })
+        .collect()
+}
+
+/// A channel notifications are delivered to
+pub trait Notifier {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error>;
+}
+
+/// Posts messages to a Slack incoming webhook
+pub struct Slack {
+    client: reqwest::Client,
+    url: String,
+    /// Text prepended to every message, for example to mark drills
+    prefix: Option<String>,
+}
+
+impl Slack {
+    pub fn new(url: String) -> Self {
+        Slack {
+            client: reqwest::Client::new(),
+            url,
+            prefix: None,
+        }
+    }
+
+    pub fn with_prefix(
+        mut self,
+        prefix: &str,
+    ) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    fn text(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> String {
+        let text = match notification {
+            Notification::Event(state) => format!(
+                "`{}` resource *{}* ({}) is `{}`: {}",
+                stack_name, state.resource_id, state.resource_type, state.status, state.reason
+            ),
+            Notification::Failure(state) => format!(
+                ":x: `{}` resource *{}* ({}) is `{}`: {}",
+                stack_name, state.resource_id, state.resource_type, state.status, state.reason
+            ),
+            Notification::RollbackStart(state) => format!(
+                ":rewind: `{}` began rolling back: {}",
+                stack_name, state.reason
+            ),
+            Notification::Complete(state, summary) => {
+                let mut text = if is_failure(&state.status) {
+                    format!(
+                        ":x: `{}` finished with status `{}` {}",
+                        stack_name, state.status, state.reason
+                    )
+                } else if state.status.contains("ROLLBACK") {
+                    format!(
+                        ":rewind: `{}` rolled back with status `{}`",
+                        stack_name, state.status
+                    )
+                } else {
+                    format!(
+                        ":white_check_mark: `{}` finished with status `{}`",
+                        stack_name, state.status
+                    )
+                };
+                if let Some(duration) = summary.duration {
+                    text.push_str(&format!(" after {}", humanize(duration)));
+                }
+                if let Some(cause) = summary.root_cause {
+                    text.push_str(&format!(
+                        "\n>root cause: *{}* ({}) is `{}`: {}",
+                        cause.resource_id, cause.resource_type, cause.status, cause.reason
+                    ));
+                }
+                text
+            }
+        };
+        match &self.prefix {
+            Some(prefix) => format!("{} {}", prefix, text),
+            None => text,
+        }
+    }
+}
+
+impl Notifier for Slack {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        self.client
+            .post(&self.url)
+            .json(&json!({ "text": self.text(stack_name, notification) }))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// A markdown message describing a notification, for chat services other than
+/// Slack, which has its own flavor of markdown
+fn markdown(
+    stack_name: &str,
+    notification: &Notification,
+) -> String {
+    match notification {
+        Notification::Event(state) => format!(
+            "`{}` resource **{}** ({}) is `{}`: {}",
+            stack_name, state.resource_id, state.resource_type, state.status, state.reason
+        ),
+        Notification::Failure(state) => format!(
+            "❌ `{}` resource **{}** ({}) is `{}`: {}",
+            stack_name, state.resource_id, state.resource_type, state.status, state.reason
+        ),
+        Notification::RollbackStart(state) => {
+            format!("⏪ `{}` began rolling back: {}", stack_name, state.reason)
+        }
+        Notification::Complete(state, summary) => {
+            let mut text = if is_failure(&state.status) {
+                format!(
+                    "❌ `{}` finished with status `{}` {}",
+                    stack_name, state.status, state.reason
+                )
+            } else if state.status.contains("ROLLBACK") {
+                format!(
+                    "⏪ `{}` rolled back with status `{}`",
+                    stack_name, state.status
+                )
+            } else {
+                format!(
+                    "✅ `{}` finished with status `{}`",
+                    stack_name, state.status
+                )
+            };
+            if let Some(duration) = summary.duration {
+                text.push_str(&format!(" after {}", humanize(duration)));
+            }
+            if let Some(cause) = summary.root_cause {
+                text.push_str(&format!(
+                    "\n\n> root cause: **{}** ({}) is `{}`: {}",
+                    cause.resource_id, cause.resource_type, cause.status, cause.reason
+                ));
+            }
+            text
+        }
+    }
+}
+
+/// Posts message cards to a Microsoft Teams incoming webhook
+pub struct Teams {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Teams {
+    pub fn new(url: String) -> Self {
+        Teams {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    fn card(
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Value {
+        let (title, _) = plain(stack_name, notification);
+        // the accent color of the card's edge
+        let color = match notification {
+            Notification::Event(_) => "808080",
+            Notification::Failure(_) => "d7000c",
+            Notification::RollbackStart(_) => "ffa500",
+            Notification::Complete(state, _) if is_failure(&state.status) => "d7000c",
+            Notification::Complete(state, _) if state.status.contains("ROLLBACK") => "ffa500",
+            Notification::Complete(..) => "2eb886",
+        };
+        json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": title,
+            "themeColor": color,
+            "title": title,
+            "text": markdown(stack_name, notification),
+        })
+    }
+}
+
+impl Notifier for Teams {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        self.client
+            .post(&self.url)
+            .json(&Teams::card(stack_name, notification))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Posts messages to a Discord webhook
+pub struct Discord {
+    client: reqwest::Client,
+    url: String,
+}
+
+/// The most characters Discord accepts in a message
+const DISCORD_LIMIT: usize = 2000;
+
+impl Discord {
+    pub fn new(url: String) -> Self {
+        Discord {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    fn message(
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Value {
+        let content = markdown(stack_name, notification);
+        json!({
+            "username": "stack-tail",
+            "content": if content.chars().count() > DISCORD_LIMIT {
+                content.chars().take(DISCORD_LIMIT - 1).chain(Some('…')).collect()
+            } else {
+                content
+            },
+        })
+    }
+}
+
+impl Notifier for Discord {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        self.client
+            .post(&self.url)
+            .json(&Discord::message(stack_name, notification))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// PagerDuty's Events API v2 endpoint
+const PAGERDUTY_EVENTS: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// The most characters PagerDuty accepts in an event's summary
+const PAGERDUTY_SUMMARY_LIMIT: usize = 1024;
+
+/// Raises PagerDuty events when operations fail or roll back
+pub struct PagerDuty {
+    client: reqwest::Client,
+    routing_key: String,
+}
+
+impl PagerDuty {
+    pub fn new(routing_key: String) -> Self {
+        PagerDuty {
+            client: reqwest::Client::new(),
+            routing_key,
+        }
+    }
+
+    /// An event to trigger for a notification, when it's worth waking someone for
+    fn event(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Option<Value> {
+        let (state, summary) = match notification {
+            Notification::Complete(state, summary)
+                if is_failure(&state.status) || state.status.contains("ROLLBACK") =>
+            {
+                (state, summary)
+            }
+            _ => return None,
+        };
+        let mut text = format!("{} finished with status {}", stack_name, state.status);
+        if let Some(cause) = summary.root_cause {
+            text.push_str(&format!(
+                ": {} ({}) is {}: {}",
+                cause.resource_id, cause.resource_type, cause.status, cause.reason
+            ));
+        }
+        Some(json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            // repeated failures of a stack fold into its open incident
+            "dedup_key": format!("stack-tail/{}", stack_name),
+            "payload": {
+                "summary": text.chars().take(PAGERDUTY_SUMMARY_LIMIT).collect::<String>(),
+                "source": stack_name,
+                "severity": "critical",
+                "component": stack_logical_id(stack_name),
+                "custom_details": document(stack_name, notification),
+            },
+        }))
+    }
+}
+
+impl Notifier for PagerDuty {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        if let Some(event) = self.event(stack_name, notification) {
+            self.client
+                .post(PAGERDUTY_EVENTS)
+                .json(&event)
+                .send()?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+}
+
+/// Emails a summary of each completed operation through SES
+pub struct Ses {
+    client: SesClient,
+    from: String,
+    to: Vec<String>,
+}
+
+impl Ses {
+    /// Sends from a verified address to any number of recipients
+    pub fn new(
+        from: String,
+        to: Vec<String>,
+    ) -> Self {
+        Ses {
+            client: SesClient::new_with(
+                HttpClient::new().expect("failed to create request dispatcher"),
+                credentials(),
+                Region::default(),
+            ),
+            from,
+            to,
+        }
+    }
+}
+
+/// The subject and body of an email summarizing a completed operation
+fn email(
+    stack_name: &str,
+    notification: &Notification,
+) -> Option<(String, String)> {
+    let (state, summary) = match notification {
+        Notification::Complete(state, summary) => (state, summary),
+        _ => return None,
+    };
+    let (title, outcome) = plain(stack_name, notification);
+    let mut body = format!("stack: {}\nstatus: {}\n", stack_name, state.status);
+    if let Some(duration) = summary.duration {
+        body.push_str(&format!("duration: {}\n", humanize(duration)));
+    }
+    if let Some(cause) = summary.root_cause {
+        body.push_str(&format!(
+            "root cause: {} ({}) is {}: {}\n",
+            cause.resource_id, cause.resource_type, cause.status, cause.reason
+        ));
+    }
+    if !summary.failures.is_empty() {
+        body.push_str("\nfailed resources:\n");
+        for failure in &summary.failures {
+            body.push_str(&format!(
+                "  {} ({}) is {}: {}\n",
+                failure.resource_id, failure.resource_type, failure.status, failure.reason
+            ));
+        }
+    }
+    if let Some(url) = links::console_url(&region_of(stack_name).unwrap_or_default(), state) {
+        body.push_str(&format!("\nconsole: {}\n", url));
+    }
+    Some((format!("{} {}", title, outcome), body))
+}
+
+impl Notifier for Ses {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        let (subject, body) = match email(stack_name, notification) {
+            Some(email) => email,
+            None => return Ok(()),
+        };
+        let content = |data: String| Content {
+            data,
+            charset: Some("UTF-8".into()),
+        };
+        self.client
+            .send_email(SendEmailRequest {
+                source: self.from.clone(),
+                destination: Destination {
+                    to_addresses: Some(self.to.clone()),
+                    ..Destination::default()
+                },
+                message: Message {
+                    subject: content(subject),
+                    body: Body {
+                        text: Some(content(body)),
+                        ..Body::default()
+                    },
+                },
+                ..SendEmailRequest::default()
+            })
+            .sync()?;
+        Ok(())
+    }
+}
+
+/// A JSON document describing a notification, for machines rather than people
+pub fn document(
+    stack_name: &str,
+    notification: &Notification,
+) -> Value {
+    match notification {
+        Notification::Event(state) => json!({
+            "stack": stack_name,
+            "event": "event",
+            "state": state,
+        }),
+        Notification::Failure(state) => json!({
+            "stack": stack_name,
+            "event": "failure",
+            "state": state,
+        }),
+        Notification::RollbackStart(state) => json!({
+            "stack": stack_name,
+            "event": "rollback-start",
+            "state": state,
+        }),
+        Notification::Complete(state, summary) => json!({
+            "stack": stack_name,
+            "event": "complete",
+            "state": state,
+            "succeeded": !is_failure(&state.status) && !state.status.contains("ROLLBACK"),
+            "duration_seconds": summary.duration.map(|duration| duration.num_seconds()),
+            "root_cause": summary.root_cause,
+        }),
+    }
+}
+
+/// Publishes JSON documents to an SNS topic, for fanning out to whatever
+/// subscribes to it
+pub struct Sns {
+    client: SnsClient,
+    topic_arn: String,
+}
+
+impl Sns {
+    /// Publishes to a topic from its own region
+    pub fn new(topic_arn: String) -> Self {
+        Sns {
+            client: SnsClient::new_with(
+                HttpClient::new().expect("failed to create request dispatcher"),
+                credentials(),
+                region_of(&topic_arn).unwrap_or_default(),
+            ),
+            topic_arn,
+        }
+    }
+}
+
+impl Notifier for Sns {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        let (subject, body) = plain(stack_name, notification);
+        self.client
+            .publish(PublishInput {
+                topic_arn: Some(self.topic_arn.clone()),
+                // email subscriptions use the subject, which sns limits to 100 characters
+                subject: Some(format!("{} {}", subject, body).chars().take(100).collect()),
+                message: document(stack_name, notification).to_string(),
+                ..PublishInput::default()
+            })
+            .sync()?;
+        Ok(())
+    }
+}
+
+/// Puts events onto an EventBridge bus, for other automations to react to
+pub struct EventBridge {
+    client: CloudWatchEventsClient,
+    bus: String,
+}
+
+impl EventBridge {
+    /// Puts events onto a bus, given by name or arn, from the bus's own region
+    pub fn new(bus: String) -> Self {
+        EventBridge {
+            client: CloudWatchEventsClient::new_with(
+                HttpClient::new().expect("failed to create request dispatcher"),
+                credentials(),
+                region_of(&bus).unwrap_or_default(),
+            ),
+            bus,
+        }
+    }
+
+    fn entry(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> PutEventsRequestEntry {
+        PutEventsRequestEntry {
+            event_bus_name: Some(self.bus.clone()),
+            source: Some("stack-tail".into()),
+            detail_type: Some("stack-status-change".into()),
+            detail: Some(document(stack_name, notification).to_string()),
+            resources: if stack_name.starts_with("arn:") {
+                Some(vec![stack_name.into()])
+            } else {
+                None
+            },
+            ..PutEventsRequestEntry::default()
+        }
+    }
+}
+
+impl Notifier for EventBridge {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        let response = self
+            .client
+            .put_events(PutEventsRequest {
+                entries: vec![self.entry(stack_name, notification)],
+            })
+            .sync()?;
+        // entries are rejected individually rather than failing the request
+        match response
+            .entries
+            .unwrap_or_default()
+            .into_iter()
+            .find_map(|entry| entry.error_message)
+        {
+            Some(message) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("eventbridge rejected event: {}", message),
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An HTTP header sent with webhook requests, like `Authorization: Bearer xxx`
+#[derive(Debug, PartialEq, Clone)]
+pub struct HttpHeader(String, String);
+
+impl FromStr for HttpHeader {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find(':') {
+            Some(index) if !s[..index].trim().is_empty() => Ok(HttpHeader(
+                s[..index].trim().into(),
+                s[index + 1..].trim().into(),
+            )),
+            _ => Err(format!("expected a header like Name: value, not {}", s)),
+        }
+    }
+}
+
+/// POSTs JSON documents of each new event and of the operation's completion
+/// to a URL, for deployment dashboards and the like
+pub struct Webhook {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<HttpHeader>,
+}
+
+impl Webhook {
+    pub fn new(
+        url: String,
+        headers: Vec<HttpHeader>,
+    ) -> Self {
+        Webhook {
+            client: reqwest::Client::new(),
+            url,
+            headers,
+        }
+    }
+}
+
+impl Notifier for Webhook {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        self.headers
+            .iter()
+            .fold(
+                self.client.post(&self.url),
+                |request, HttpHeader(name, value)| request.header(name.as_str(), value.as_str()),
+            )
+            .json(&document(stack_name, notification))
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Rings the terminal bell
+pub struct Bell;
+
+impl Notifier for Bell {
+    fn notify(
+        &self,
+        _: &str,
+        _: &Notification,
+    ) -> Result<(), Error> {
+        let mut stderr = io::stderr();
+        stderr.write_all(b"\x07")?;
+        stderr.flush()?;
+        Ok(())
+    }
+}
+
+/// A plain title and body describing a notification
+fn plain(
+    stack_name: &str,
+    notification: &Notification,
+) -> (String, String) {
+    let body = match notification {
+        Notification::Event(state) | Notification::Failure(state) => format!(
+            "{} ({}) is {}: {}",
+            state.resource_id, state.resource_type, state.status, state.reason
+        ),
+        Notification::RollbackStart(state) => format!("began rolling back: {}", state.reason),
+        Notification::Complete(state, _) if is_failure(&state.status) => {
+            format!("failed with status {}", state.status)
+        }
+        Notification::Complete(state, _) if state.status.contains("ROLLBACK") => {
+            format!("rolled back with status {}", state.status)
+        }
+        Notification::Complete(state, _) => format!("succeeded with status {}", state.status),
+    };
+    (format!("stack-tail: {}", stack_name), body)
+}
+
+/// Fires a native desktop notification, with osascript on macOS, PowerShell
+/// on Windows and notify-send elsewhere
+pub struct Desktop;
+
+impl Notifier for Desktop {
+    fn notify(
+        &self,
+        stack_name: &str,
+        notification: &Notification,
+    ) -> Result<(), Error> {
+        let (title, body) = plain(stack_name, notification);
+        let mut command = if cfg!(target_os = "macos") {
+            let quoted = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+            let mut command = Command::new("osascript");
+            command.arg("-e").arg(format!(
+                "display notification {} with title {}",
+                quoted(&body),
+                quoted(&title)
+            ));
+            command
+        } else if cfg!(windows) {
+            let quoted = |s: &str| format!("'{}'", s.replace('\'', "''"));
+            let mut command = Command::new("powershell");
+            command.arg("-NoProfile").arg("-Command").arg(format!(
+                "Add-Type -AssemblyName System.Windows.Forms; \
+                 $icon = New-Object System.Windows.Forms.NotifyIcon; \
+                 $icon.Icon = [System.Drawing.SystemIcons]::Information; \
+                 $icon.Visible = $true; \
+                 $icon.ShowBalloonTip(10000, {}, {}, 'Info'); \
+                 Start-Sleep -Seconds 10",
+                quoted(&title),
+                quoted(&body)
+            ));
+            command
+        } else {
+            let mut command = Command::new("notify-send");
+            command.arg(title).arg(body);
+            command
+        };
+        // desktop notifications are best effort, so a missing notifier
+        // doesn't interrupt tailing
+        drop(command.stdout(Stdio::null()).stderr(Stdio::null()).spawn());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn trigger_parses_names() {
+        assert_eq!("first-failure".parse(), Ok(Trigger::FirstFailure));
+        assert_eq!("failure".parse(), Ok(Trigger::Failure));
+        assert_eq!("rollback-start".parse(), Ok(Trigger::RollbackStart));
+        assert_eq!("complete".parse(), Ok(Trigger::Complete));
+        assert!("whenever".parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn http_header_parses_name_and_value() {
+        assert_eq!(
+            "Authorization: Bearer xxx".parse(),
+            Ok(HttpHeader("Authorization".into(), "Bearer xxx".into()))
+        );
+        assert!(": value".parse::<HttpHeader>().is_err());
+        assert!("Authorization".parse::<HttpHeader>().is_err());
+    }
+
+    #[test]
+    fn event_fires_for_each_new_event() {
+        let events = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE"),
+        ];
+        assert_eq!(
+            triggered(&[Trigger::Event], "stack", &events[..1], &events),
+            vec![
+                Notification::Event(&events[1]),
+                Notification::Event(&events[2])
+            ]
+        );
+    }
+
+    #[test]
+    fn triggered_fires_once_per_condition() {
+        let triggers = [
+            Trigger::FirstFailure,
+            Trigger::RollbackStart,
+            Trigger::Complete,
+        ];
+        let mut events = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_FAILED"),
+        ];
+        let failed = events.clone();
+        assert_eq!(
+            triggered(&triggers, "stack", &events[..1], &failed),
+            vec![Notification::Failure(&failed[1])]
+        );
+        events.push(state("AWS::SQS::Queue", "other", "UPDATE_FAILED"));
+        events.push(state(
+            STACK_RESOURCE,
+            "stack",
+            "UPDATE_ROLLBACK_IN_PROGRESS",
+        ));
+        assert_eq!(
+            triggered(&triggers, "stack", &failed, &events),
+            vec![Notification::RollbackStart(&events[3])]
+        );
+        let rolling_back = events.clone();
+        events.push(state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_COMPLETE"));
+        assert_eq!(
+            triggered(&triggers, "stack", &rolling_back, &events),
+            vec![Notification::Complete(
+                &events[4],
+                Summary {
+                    duration: Some(chrono::Duration::zero()),
+                    root_cause: Some(&events[1]),
+                    failures: vec![&events[1], &events[2]],
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn failure_fires_for_each_failed_resource() {
+        let events = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "UPDATE_FAILED"),
+            state("AWS::SQS::Queue", "other", "UPDATE_FAILED"),
+        ];
+        assert_eq!(
+            triggered(&[Trigger::Failure], "stack", &events[..2], &events),
+            vec![Notification::Failure(&events[2])]
+        );
+    }
+
+    #[test]
+    fn failure_ignores_stack_and_hook_failures() {
+        let events = vec![
+            state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "HOOK_COMPLETE_FAILED"),
+            state(STACK_RESOURCE, "stack", "UPDATE_FAILED"),
+        ];
+        assert!(triggered(
+            &[Trigger::FirstFailure, Trigger::Failure],
+            "stack",
+            &events[..1],
+            &events
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn slack_text_distinguishes_rollbacks() {
+        let slack = Slack::new("https://hooks.slack.com/services/xxx".into());
+        assert_eq!(
+            slack.text(
+                "stack",
+                &Notification::Complete(
+                    &state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_COMPLETE"),
+                    Summary::default()
+                )
+            ),
+            ":rewind: `stack` rolled back with status `UPDATE_ROLLBACK_COMPLETE`"
+        );
+    }
+
+    #[test]
+    fn slack_text_includes_prefix() {
+        let slack =
+            Slack::new("https://hooks.slack.com/services/xxx".into()).with_prefix("[DRILL]");
+        assert_eq!(
+            slack.text(
+                "stack",
+                &Notification::Complete(
+                    &state(STACK_RESOURCE, "stack", "UPDATE_COMPLETE"),
+                    Summary::default()
+                )
+            ),
+            "[DRILL] :white_check_mark: `stack` finished with status `UPDATE_COMPLETE`"
+        );
+    }
+
+    #[test]
+    fn slack_text_includes_duration_and_root_cause() {
+        let slack = Slack::new("https://hooks.slack.com/services/xxx".into());
+        let cause = ResourceState {
+            reason: "queue already exists".into(),
+            ..state("AWS::SQS::Queue", "queue", "CREATE_FAILED")
+        };
+        assert_eq!(
+            slack.text(
+                "stack",
+                &Notification::Complete(
+                    &state(STACK_RESOURCE, "stack", "ROLLBACK_COMPLETE"),
+                    Summary {
+                        duration: Some(chrono::Duration::seconds(192)),
+                        root_cause: Some(&cause),
+                        failures: vec![&cause],
+                    }
+                )
+            ),
+            ":rewind: `stack` rolled back with status `ROLLBACK_COMPLETE` after 3m12s\n\
+             >root cause: *queue* (AWS::SQS::Queue) is `CREATE_FAILED`: queue already exists"
+        );
+    }
+
+    #[test]
+    fn teams_card_colors_outcomes() {
+        let failed = state(STACK_RESOURCE, "stack", "UPDATE_FAILED");
+        let card = Teams::card(
+            "stack",
+            &Notification::Complete(&failed, Summary::default()),
+        );
+        assert_eq!(card["@type"], "MessageCard");
+        assert_eq!(card["themeColor"], "d7000c");
+        assert_eq!(card["title"], "stack-tail: stack");
+        assert_eq!(
+            card["text"],
+            "❌ `stack` finished with status `UPDATE_FAILED` "
+        );
+    }
+
+    #[test]
+    fn discord_message_fits_limit() {
+        let failed = ResourceState {
+            reason: "x".repeat(DISCORD_LIMIT),
+            ..state("AWS::SQS::Queue", "queue", "CREATE_FAILED")
+        };
+        let message = Discord::message("stack", &Notification::Failure(&failed));
+        let content = message["content"].as_str().unwrap_or_default();
+        assert_eq!(content.chars().count(), DISCORD_LIMIT);
+        assert!(content.starts_with("❌ `stack` resource **queue**"));
+    }
+
+    #[test]
+    fn pagerduty_only_triggers_for_failures_and_rollbacks() {
+        let pagerduty = PagerDuty::new("key".into());
+        let succeeded = state(STACK_RESOURCE, "stack", "UPDATE_COMPLETE");
+        assert_eq!(
+            pagerduty.event(
+                "stack",
+                &Notification::Complete(&succeeded, Summary::default())
+            ),
+            None
+        );
+        let rolled_back = state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_COMPLETE");
+        let cause = ResourceState {
+            reason: "queue already exists".into(),
+            ..state("AWS::SQS::Queue", "queue", "UPDATE_FAILED")
+        };
+        let event = pagerduty
+            .event(
+                "stack",
+                &Notification::Complete(
+                    &rolled_back,
+                    Summary {
+                        duration: None,
+                        root_cause: Some(&cause),
+                        failures: vec![&cause],
+                    },
+                ),
+            )
+            .unwrap_or_default();
+        assert_eq!(event["routing_key"], "key");
+        assert_eq!(event["event_action"], "trigger");
+        assert_eq!(
+            event["payload"]["summary"],
+            "stack finished with status UPDATE_ROLLBACK_COMPLETE: \
+             queue (AWS::SQS::Queue) is UPDATE_FAILED: queue already exists"
+        );
+    }
+
+    #[test]
+    fn email_summarizes_completion() {
+        let cause = ResourceState {
+            reason: "queue already exists".into(),
+            ..state("AWS::SQS::Queue", "queue", "CREATE_FAILED")
+        };
+        let complete = ResourceState {
+            physical_id: Some(
+                "arn:aws:cloudformation:us-east-1:123456789012:stack/stack/id".into(),
+            ),
+            ..state(STACK_RESOURCE, "stack", "ROLLBACK_COMPLETE")
+        };
+        let stack_name = "arn:aws:cloudformation:us-east-1:123456789012:stack/stack/id";
+        let (subject, body) = email(
+            stack_name,
+            &Notification::Complete(
+                &complete,
+                Summary {
+                    duration: Some(chrono::Duration::seconds(192)),
+                    root_cause: Some(&cause),
+                    failures: vec![&cause],
+                },
+            ),
+        )
+        .unwrap_or_default();
+        assert_eq!(
+            subject,
+            format!(
+                "stack-tail: {} rolled back with status ROLLBACK_COMPLETE",
+                stack_name
+            )
+        );
+        assert!(body.contains("duration: 3m12s\n"));
+        assert!(body.contains(
+            "failed resources:\n  queue (AWS::SQS::Queue) is CREATE_FAILED: queue already exists\n"
+        ));
+        assert!(body.contains("console: https://us-east-1.console.aws.amazon.com/cloudformation/"));
+        assert_eq!(email(stack_name, &Notification::Failure(&cause)), None);
+    }
+
+    #[test]
+    fn document_describes_completion() {
+        let cause = state("AWS::SQS::Queue", "queue", "CREATE_FAILED");
+        let complete = state(STACK_RESOURCE, "stack", "ROLLBACK_COMPLETE");
+        let document = document(
+            "stack",
+            &Notification::Complete(
+                &complete,
+                Summary {
+                    duration: Some(chrono::Duration::seconds(192)),
+                    root_cause: Some(&cause),
+                    failures: vec![&cause],
+                },
+            ),
+        );
+        assert_eq!(document["event"], "complete");
+        assert_eq!(document["succeeded"], false);
+        assert_eq!(document["duration_seconds"], 192);
+        assert_eq!(document["state"]["status"], "ROLLBACK_COMPLETE");
+        assert_eq!(document["root_cause"]["resource_id"], "queue");
+    }
+
+    #[test]
+    fn eventbridge_entries_are_stack_status_changes() {
+        let eventbridge = EventBridge::new("deployments".into());
+        let complete = state(STACK_RESOURCE, "stack", "UPDATE_COMPLETE");
+        let entry = eventbridge.entry(
+            "arn:aws:cloudformation:us-east-1:123456789012:stack/stack/id",
+            &Notification::Complete(&complete, Summary::default()),
+        );
+        assert_eq!(entry.source.as_deref(), Some("stack-tail"));
+        assert_eq!(entry.detail_type.as_deref(), Some("stack-status-change"));
+        assert_eq!(entry.event_bus_name.as_deref(), Some("deployments"));
+        assert_eq!(
+            entry.resources,
+            Some(vec![
+                "arn:aws:cloudformation:us-east-1:123456789012:stack/stack/id".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn plain_text_distinguishes_outcomes() {
+        let rolled_back = state(STACK_RESOURCE, "stack", "UPDATE_ROLLBACK_COMPLETE");
+        let succeeded = state(STACK_RESOURCE, "stack", "UPDATE_COMPLETE");
+        assert_eq!(
+            plain(
+                "stack",
+                &Notification::Complete(&rolled_back, Summary::default())
+            ),
+            (
+                "stack-tail: stack".to_string(),
+                "rolled back with status UPDATE_ROLLBACK_COMPLETE".to_string()
+            )
+        );
+        assert_eq!(
+            plain(
+                "stack",
+                &Notification::Complete(&succeeded, Summary::default())
+            )
+            .1,
+            "succeeded with status UPDATE_COMPLETE"
+        );
+    }
+}
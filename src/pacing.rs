@@ -0,0 +1,98 @@
+//! Adaptive delays between polls, backing off while a stack is quiet
+use crate::ResourceState;
+use rand::Rng;
+use std::time::Duration;
+
+/// Longest delay between polls of a quiet stack, unless the interval is longer
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Tracks whether polls are turning up changes to pace the next one
+pub struct Pacing {
+    interval: Duration,
+    /// Number of consecutive polls without changes
+    quiet: u32,
+    previous: Option<Vec<ResourceState>>,
+}
+
+impl Pacing {
+    pub fn new(interval: Duration) -> Self {
+        Pacing {
+            interval,
+            quiet: 0,
+            previous: None,
+        }
+    }
+
+    /// Records the states of a poll, returning to the interval as soon as they change
+    pub fn observe(
+        &mut self,
+        states: &[ResourceState],
+    ) {
+        if self.previous.as_deref() == Some(states) {
+            self.quiet = self.quiet.saturating_add(1);
+        } else {
+            self.quiet = 0;
+            self.previous = Some(states.to_vec());
+        }
+    }
+
+    /// The interval, doubled for each quiet poll up to a limit
+    fn backoff(&self) -> Duration {
+        (self.interval * (1 << self.quiet.min(5))).min(MAX_DELAY.max(self.interval))
+    }
+
+    /// Delay before the next poll, with up to a tenth more at random so that
+    /// many tailed stacks don't poll in lockstep
+    pub fn delay(&self) -> Duration {
+        let backoff = self.backoff();
+        let jitter = rand::thread_rng().gen_range(0, backoff.as_millis() as u64 / 10 + 1);
+        backoff + Duration::from_millis(jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn pacing_backs_off_while_quiet() {
+        let mut pacing = Pacing::new(Duration::from_secs(1));
+        let states = vec![state("AWS::ACM::Certificate", "cert", "CREATE_IN_PROGRESS")];
+        pacing.observe(&states);
+        assert_eq!(pacing.backoff(), Duration::from_secs(1));
+        pacing.observe(&states);
+        pacing.observe(&states);
+        assert_eq!(pacing.backoff(), Duration::from_secs(4));
+        for _ in 0..10 {
+            pacing.observe(&states);
+        }
+        assert_eq!(pacing.backoff(), MAX_DELAY);
+        pacing.observe(&[state("AWS::ACM::Certificate", "cert", "CREATE_COMPLETE")]);
+        assert_eq!(pacing.backoff(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn delay_adds_bounded_jitter() {
+        let pacing = Pacing::new(Duration::from_secs(10));
+        let delay = pacing.delay();
+        assert!(delay >= Duration::from_secs(10) && delay <= Duration::from_secs(11));
+    }
+}
@@ -0,0 +1,199 @@
+//! Stack policies, and the statements behind updates they deny
+use crate::{accounts::Accounts, filter::Pattern, Error, ResourceState, FAILED};
+use colored::Colorize;
+use rusoto_cloudformation::{CloudFormation, CloudFormationClient, GetStackPolicyInput};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+use structopt::StructOpt;
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    pub stack_name: String,
+}
+
+/// A stack's policy, if it has one
+fn fetch(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<Option<Value>, Error> {
+    match cf
+        .get_stack_policy(GetStackPolicyInput {
+            stack_name: stack_name.into(),
+        })
+        .sync()?
+        .stack_policy_body
+    {
+        Some(body) => Ok(Some(serde_json::from_str(&body)?)),
+        None => Ok(None),
+    }
+}
+
+/// A policy value of one or more strings, as lists of strings
+fn strings(value: Option<&Value>) -> Vec<&str> {
+    match value {
+        Some(Value::String(s)) => vec![s.as_str()],
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether any of a statement's resource patterns, like
+/// `LogicalResourceId/Database*`, matches a resource
+fn covers(
+    patterns: &[&str],
+    resource_id: &str,
+) -> bool {
+    let resource = format!("LogicalResourceId/{}", resource_id);
+    patterns
+        .iter()
+        .any(|pattern| Pattern::Glob((*pattern).into()).matches(&resource))
+}
+
+/// The statement of a policy which denies updates to a resource, if any
+fn blocking<'a>(
+    policy: &'a Value,
+    resource_id: &str,
+) -> Option<&'a Value> {
+    let statements = match policy.get("Statement")? {
+        Value::Array(statements) => statements.iter().collect::<Vec<_>>(),
+        statement => vec![statement],
+    };
+    statements.into_iter().find(|statement| {
+        statement.get("Effect").and_then(Value::as_str) == Some("Deny")
+            && strings(statement.get("Action"))
+                .iter()
+                .any(|action| action.starts_with("Update:") || *action == "*")
+            && match statement.get("NotResource") {
+                Some(excluded) => !covers(&strings(Some(excluded)), resource_id),
+                None => covers(&strings(statement.get("Resource")), resource_id),
+            }
+    })
+}
+
+/// Whether a state is an update failure a stack policy caused
+fn denied(state: &ResourceState) -> bool {
+    state.status.ends_with(FAILED) && state.reason.contains("stack policy")
+}
+
+/// Why a stack policy denied updating a resource
+pub enum Denial {
+    /// A statement denying the update
+    Statement(String),
+    /// No statement allowing the update
+    Unallowed,
+}
+
+impl fmt::Display for Denial {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "  {} ",
+            "warning: denied by stack policy".bold().yellow()
+        )?;
+        match self {
+            Denial::Statement(statement) => write!(f, "{}", statement.bright_black()),
+            Denial::Unallowed => write!(f, "{}", "no statement allows the update".bright_black()),
+        }
+    }
+}
+
+/// Policies of stacks with updates they denied, fetched once each
+#[derive(Default)]
+pub struct Denials {
+    policies: HashMap<String, Option<Value>>,
+}
+
+impl Denials {
+    /// Fetches a stack's policy when one of its updates failed on account of it
+    pub fn fetch(
+        &mut self,
+        accounts: &Accounts,
+        stack_name: &str,
+        states: &[ResourceState],
+    ) {
+        if self.policies.contains_key(stack_name) || !states.iter().any(denied) {
+            return;
+        }
+        let policy = fetch(&accounts.client_for(stack_name), stack_name).unwrap_or_else(|err| {
+            eprintln!("{} {}", "notice:".bold().yellow(), err);
+            None
+        });
+        self.policies.insert(stack_name.into(), policy);
+    }
+
+    /// Why a stack policy denied a state's update, if it did
+    pub fn of(
+        &self,
+        stack_name: &str,
+        state: &ResourceState,
+    ) -> Option<Denial> {
+        if !denied(state) {
+            return None;
+        }
+        let policy = self.policies.get(stack_name)?.as_ref()?;
+        Some(match blocking(policy, &state.resource_id) {
+            Some(statement) => Denial::Statement(statement.to_string()),
+            None => Denial::Unallowed,
+        })
+    }
+}
+
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+) -> Result<(), Error> {
+    let Options { stack_name } = options;
+    match fetch(&cf, &stack_name)? {
+        Some(policy) => println!("{}", serde_json::to_string_pretty(&policy)?),
+        None => println!("{} has no stack policy", stack_name.bold()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn blocking_finds_denying_statements() {
+        let policy = json!({
+            "Statement": [
+                {
+                    "Effect": "Allow",
+                    "Action": "Update:*",
+                    "Principal": "*",
+                    "Resource": "*"
+                },
+                {
+                    "Effect": "Deny",
+                    "Action": ["Update:Replace", "Update:Delete"],
+                    "Principal": "*",
+                    "Resource": "LogicalResourceId/Production*"
+                }
+            ]
+        });
+        assert_eq!(
+            blocking(&policy, "ProductionDatabase"),
+            Some(&policy["Statement"][1])
+        );
+        assert_eq!(blocking(&policy, "Queue"), None);
+    }
+
+    #[test]
+    fn blocking_honors_not_resource() {
+        let policy = json!({
+            "Statement": {
+                "Effect": "Deny",
+                "Action": "Update:*",
+                "Principal": "*",
+                "NotResource": "LogicalResourceId/Queue"
+            }
+        });
+        assert!(blocking(&policy, "Database").is_some());
+        assert_eq!(blocking(&policy, "Queue"), None);
+    }
+}
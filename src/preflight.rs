@@ -0,0 +1,380 @@
+//! Pre-deploy safety checks of a change set and the stack it changes
+use crate::Error;
+use colored::Colorize;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, DescribeChangeSetInput, DescribeChangeSetOutput,
+    DescribeStacksInput, GetStackPolicyInput, ListImportsInput, Stack,
+};
+use rusoto_core::RusotoError;
+use std::{
+    io::{self, Write},
+    process,
+};
+use structopt::StructOpt;
+use tabwriter::TabWriter;
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        short = "c",
+        long = "changeset",
+        help = "Name or id of the change set to check"
+    )]
+    pub changeset: String,
+    pub stack_name: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Level {
+    Ok,
+    Warn,
+    NoGo,
+}
+
+#[derive(Debug, PartialEq)]
+struct Finding {
+    level: Level,
+    check: &'static str,
+    message: String,
+}
+
+impl Finding {
+    fn new(
+        level: Level,
+        check: &'static str,
+        message: impl Into<String>,
+    ) -> Self {
+        Finding {
+            level,
+            check,
+            message: message.into(),
+        }
+    }
+}
+
+/// Findings for the resource changes of a change set
+fn change_set_findings(change_set: &DescribeChangeSetOutput) -> Vec<Finding> {
+    let status = change_set.status.as_deref().unwrap_or_default();
+    let execution = change_set.execution_status.as_deref().unwrap_or_default();
+    if status != "CREATE_COMPLETE" || execution != "AVAILABLE" {
+        return vec![Finding::new(
+            Level::NoGo,
+            "change set",
+            format!(
+                "change set is {} and {} {}",
+                status,
+                execution,
+                change_set.status_reason.as_deref().unwrap_or_default()
+            ),
+        )];
+    }
+    let mut findings = Vec::new();
+    for change in change_set.changes.iter().flatten() {
+        let resource = match &change.resource_change {
+            Some(resource) => resource,
+            None => continue,
+        };
+        let id = resource.logical_resource_id.as_deref().unwrap_or_default();
+        let resource_type = resource.resource_type.as_deref().unwrap_or_default();
+        match (resource.action.as_deref(), resource.replacement.as_deref()) {
+            (Some("Remove"), _) => findings.push(Finding::new(
+                Level::Warn,
+                "change set",
+                format!("{} ({}) will be removed", id, resource_type),
+            )),
+            (Some("Modify"), Some("True")) => findings.push(Finding::new(
+                Level::Warn,
+                "change set",
+                format!("{} ({}) will be replaced", id, resource_type),
+            )),
+            (Some("Modify"), Some("Conditional")) => findings.push(Finding::new(
+                Level::Warn,
+                "change set",
+                format!("{} ({}) may be replaced", id, resource_type),
+            )),
+            _ => (),
+        }
+    }
+    if findings.is_empty() {
+        findings.push(Finding::new(
+            Level::Ok,
+            "change set",
+            format!(
+                "{} changes without removals or replacements",
+                change_set.changes.iter().flatten().count()
+            ),
+        ));
+    }
+    findings
+}
+
+/// Findings for the drift, termination protection and exports of a stack
+fn stack_findings(
+    stack: &Stack,
+    has_policy: bool,
+    imports: &[(String, Vec<String>)],
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    findings.push(
+        match stack
+            .drift_information
+            .as_ref()
+            .map(|drift| drift.stack_drift_status.as_str())
+        {
+            Some("IN_SYNC") => {
+                Finding::new(Level::Ok, "drift", "stack is in sync with its template")
+            }
+            Some("DRIFTED") => Finding::new(
+                Level::NoGo,
+                "drift",
+                "stack has drifted from its template, run stack-tail drift to see how",
+            ),
+            _ => Finding::new(
+                Level::Warn,
+                "drift",
+                "drift hasn't been checked, run stack-tail drift to check",
+            ),
+        },
+    );
+    findings.push(if has_policy {
+        Finding::new(
+            Level::Ok,
+            "stack policy",
+            "updates are guarded by a stack policy",
+        )
+    } else {
+        Finding::new(
+            Level::Warn,
+            "stack policy",
+            "no stack policy guards updates",
+        )
+    });
+    findings.push(if stack.enable_termination_protection.unwrap_or_default() {
+        Finding::new(Level::Ok, "termination protection", "enabled")
+    } else {
+        Finding::new(Level::Warn, "termination protection", "disabled")
+    });
+    let imported = imports
+        .iter()
+        .filter(|(_, importers)| !importers.is_empty())
+        .collect::<Vec<_>>();
+    if imported.is_empty() {
+        findings.push(Finding::new(
+            Level::Ok,
+            "exports",
+            "no exports are imported by other stacks",
+        ));
+    }
+    for (export, importers) in imported {
+        findings.push(Finding::new(
+            Level::Warn,
+            "exports",
+            format!(
+                "{} is imported by {}, changes to it will fail",
+                export,
+                importers.join(", ")
+            ),
+        ));
+    }
+    findings
+}
+
+/// Stacks importing an export. CloudFormation reports exports which aren't
+/// imported by any stack as a validation error
+fn importers(
+    cf: &CloudFormationClient,
+    export_name: &str,
+) -> Result<Vec<String>, Error> {
+    let mut names = Vec::new();
+    let mut next_token = None;
+    loop {
+        let output = match cf
+            .list_imports(ListImportsInput {
+                export_name: export_name.into(),
+                next_token: next_token.clone(),
+            })
+            .sync()
+        {
+            Ok(output) => output,
+            Err(RusotoError::Unknown(ref response))
+                if String::from_utf8_lossy(&response.body).contains("is not imported") =>
+            {
+                return Ok(names)
+            }
+            Err(err) => return Err(err.into()),
+        };
+        names.extend(output.imports.unwrap_or_default());
+        match output.next_token {
+            token @ Some(_) => next_token = token,
+            None => return Ok(names),
+        }
+    }
+}
+
+/// Reports whether a change set is safe to execute, exiting with a non-zero
+/// status when it isn't
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+) -> Result<(), Error> {
+    let Options {
+        changeset,
+        stack_name,
+    } = options;
+    let change_set = cf
+        .describe_change_set(DescribeChangeSetInput {
+            change_set_name: changeset,
+            stack_name: Some(stack_name.clone()),
+            ..DescribeChangeSetInput::default()
+        })
+        .sync()?;
+    let stack = cf
+        .describe_stacks(DescribeStacksInput {
+            stack_name: Some(stack_name.clone()),
+            ..DescribeStacksInput::default()
+        })
+        .sync()?
+        .stacks
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let has_policy = cf
+        .get_stack_policy(GetStackPolicyInput {
+            stack_name: stack_name.clone(),
+        })
+        .sync()?
+        .stack_policy_body
+        .is_some();
+    let mut imports = Vec::new();
+    for export in stack
+        .outputs
+        .iter()
+        .flatten()
+        .filter_map(|output| output.export_name.as_ref())
+    {
+        imports.push((export.clone(), importers(&cf, export)?));
+    }
+
+    let findings = change_set_findings(&change_set)
+        .into_iter()
+        .chain(stack_findings(&stack, has_policy, &imports))
+        .collect::<Vec<_>>();
+    let mut writer = TabWriter::new(io::stdout());
+    for finding in &findings {
+        writeln!(
+            &mut writer,
+            "{}\t{}\t{}",
+            match finding.level {
+                Level::Ok => "✅",
+                Level::Warn => "⚠️ ",
+                Level::NoGo => "⛔",
+            },
+            finding.check.bold(),
+            finding.message
+        )?;
+    }
+    writer.flush()?;
+    println!();
+    let warnings = findings
+        .iter()
+        .filter(|finding| finding.level == Level::Warn)
+        .count();
+    if findings.iter().any(|finding| finding.level == Level::NoGo) {
+        println!("{}", "NO-GO".bold().bright_red());
+        process::exit(1);
+    }
+    println!(
+        "{} with {} warning{}",
+        "GO".bold().bright_green(),
+        warnings,
+        if warnings == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudformation::{Change, ResourceChange, StackDriftInformation};
+
+    fn change(
+        id: &str,
+        action: &str,
+        replacement: Option<&str>,
+    ) -> Change {
+        Change {
+            resource_change: Some(ResourceChange {
+                action: Some(action.into()),
+                logical_resource_id: Some(id.into()),
+                resource_type: Some("AWS::RDS::DBInstance".into()),
+                replacement: replacement.map(String::from),
+                ..ResourceChange::default()
+            }),
+            ..Change::default()
+        }
+    }
+
+    #[test]
+    fn change_set_findings_warn_of_removals_and_replacements() {
+        let findings = change_set_findings(&DescribeChangeSetOutput {
+            status: Some("CREATE_COMPLETE".into()),
+            execution_status: Some("AVAILABLE".into()),
+            changes: Some(vec![
+                change("Database", "Modify", Some("True")),
+                change("Replica", "Remove", None),
+                change("Cache", "Modify", Some("False")),
+            ]),
+            ..DescribeChangeSetOutput::default()
+        });
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| (finding.level, finding.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Level::Warn,
+                    "Database (AWS::RDS::DBInstance) will be replaced"
+                ),
+                (
+                    Level::Warn,
+                    "Replica (AWS::RDS::DBInstance) will be removed"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn change_set_findings_require_an_executable_change_set() {
+        let findings = change_set_findings(&DescribeChangeSetOutput {
+            status: Some("FAILED".into()),
+            execution_status: Some("UNAVAILABLE".into()),
+            ..DescribeChangeSetOutput::default()
+        });
+        assert_eq!(findings[0].level, Level::NoGo);
+    }
+
+    #[test]
+    fn stack_findings_stop_drifted_stacks() {
+        let findings = stack_findings(
+            &Stack {
+                drift_information: Some(StackDriftInformation {
+                    stack_drift_status: "DRIFTED".into(),
+                    ..StackDriftInformation::default()
+                }),
+                enable_termination_protection: Some(true),
+                ..Stack::default()
+            },
+            true,
+            &[("api-url".into(), vec!["frontend".into()])],
+        );
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| finding.level)
+                .collect::<Vec<_>>(),
+            vec![Level::NoGo, Level::Ok, Level::Ok, Level::Warn]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! How far along a stack's current operation is
+use crate::{
+    current_operation, humanize, latest, stack_logical_id, theme::Theme, Markers, ResourceState,
+};
+use colored::Colorize;
+use std::fmt;
+
+/// Width of the bar in characters
+const WIDTH: usize = 20;
+
+#[derive(Debug, PartialEq)]
+pub struct Progress {
+    /// Resources which reached a final state
+    pub done: usize,
+    pub total: usize,
+    /// Estimated time left, when previous runs were timed
+    pub remaining: Option<chrono::Duration>,
+}
+
+impl Progress {
+    /// Progress of a stack's current operation. Creations count every resource
+    /// of the stack's template, when known, since resources only report events
+    /// once they start. Other operations count the resources they've touched
+    pub fn of(
+        stack_name: &str,
+        states: &[ResourceState],
+        template_resources: Option<usize>,
+    ) -> Option<Progress> {
+        let operation = current_operation(stack_name, states);
+        let creating = operation
+            .first()
+            .map(|state| state.status.starts_with("CREATE"))
+            .unwrap_or_default();
+        let resources = latest(operation.to_vec())
+            .into_iter()
+            .filter(|state| {
+                !(state.is_stack() && state.resource_id == stack_logical_id(stack_name))
+            })
+            .collect::<Vec<_>>();
+        let done = resources
+            .iter()
+            .filter(|state| state.complete_or_failed())
+            .count();
+        let total = match template_resources {
+            Some(total) if creating => total.max(resources.len()),
+            _ => resources.len(),
+        };
+        if total == 0 {
+            return None;
+        }
+        Some(Progress {
+            done,
+            total,
+            remaining: None,
+        })
+    }
+}
+
+/// A progress bar drawn with a given set of markers and theme
+pub struct Bar<'a>(pub &'a Progress, pub Markers, pub Theme);
+
+impl fmt::Display for Bar<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Bar(
+            Progress {
+                done,
+                total,
+                remaining,
+            },
+            markers,
+            theme,
+        ) = self;
+        let (filled, empty) = match markers {
+            Markers::Ascii => ("#", "-"),
+            _ => ("█", "░"),
+        };
+        let width = WIDTH * done / total;
+        write!(
+            f,
+            "{}{} {}/{} resources complete",
+            filled.repeat(width).color(theme.success),
+            theme.muted(&empty.repeat(WIDTH - width)),
+            done,
+            total
+        )?;
+        if let Some(remaining) = remaining {
+            write!(f, ", about {} remaining", humanize(*remaining))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn progress_counts_template_resources_when_creating() {
+        let states = vec![
+            state(STACK_RESOURCE, "stack", "CREATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "CREATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "queue", "CREATE_COMPLETE"),
+            state("AWS::SNS::Topic", "topic", "CREATE_IN_PROGRESS"),
+        ];
+        assert_eq!(
+            Progress::of("stack", &states, Some(4)),
+            Some(Progress {
+                done: 1,
+                total: 4,
+                remaining: None
+            })
+        );
+        assert_eq!(
+            Progress::of("stack", &states, None),
+            Some(Progress {
+                done: 1,
+                total: 2,
+                remaining: None
+            })
+        );
+    }
+
+    #[test]
+    fn bar_fills_in_proportion() {
+        assert_eq!(
+            console::strip_ansi_codes(
+                &Bar(
+                    &Progress {
+                        done: 1,
+                        total: 4,
+                        remaining: Some(chrono::Duration::seconds(192))
+                    },
+                    Markers::Ascii,
+                    Theme::default()
+                )
+                .to_string()
+            ),
+            "#####--------------- 1/4 resources complete, about 3m12s remaining"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Replays recorded stack events, for rehearsing incident response
+use crate::{
+    notify::{Notification, Notifier, Slack, Summary},
+    theme::Theme,
+    Clock, Columns, Error, Formatted, Markers, ResourceState, FAILED,
+};
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    thread::sleep,
+    time::Duration,
+};
+use structopt::StructOpt;
+use tabwriter::TabWriter;
+
+/// Marks replayed notifications so they aren't mistaken for real deployments
+const DRILL: &str = "[DRILL]";
+
+/// A playback rate multiplier, written as `5x` or `5`
+#[derive(PartialEq, Debug)]
+pub struct Speed(f64);
+
+impl FromStr for Speed {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_end_matches('x').parse::<f64>() {
+            Ok(speed) if speed > 0.0 => Ok(Speed(speed)),
+            _ => Err(format!(
+                "invalid speed {}, expected a positive multiplier like 5x",
+                s
+            )),
+        }
+    }
+}
+
+impl Speed {
+    /// Scales the time between two events
+    fn scale(
+        &self,
+        elapsed: chrono::Duration,
+    ) -> Duration {
+        let Speed(speed) = self;
+        Duration::from_secs_f64(elapsed.to_std().unwrap_or_default().as_secs_f64() / speed)
+    }
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        long = "notify-slack",
+        help = "Slack incoming webhook url to deliver replayed notifications to"
+    )]
+    pub notify_slack: Option<String>,
+    #[structopt(
+        long = "speed",
+        default_value = "1x",
+        help = "Playback speed relative to the original timing of events"
+    )]
+    pub speed: Speed,
+    #[structopt(
+        help = "JSON file of recorded events, in the format of the json archive",
+        parse(from_os_str)
+    )]
+    pub file: PathBuf,
+}
+
+/// Replays recorded events with their original timing, scaled by a playback speed,
+/// delivering the notifications a live deployment would have triggered
+pub fn run(
+    options: Options,
+    markers: Markers,
+    theme: Theme,
+) -> Result<(), Error> {
+    let Options {
+        notify_slack,
+        speed,
+        file,
+    } = options;
+    let mut events: Vec<ResourceState> = serde_json::from_slice(&fs::read(&file)?)?;
+    events.sort_by_key(|event| event.timestamp);
+    let stack_name = events
+        .iter()
+        .find(|event| event.is_stack())
+        .map(|event| event.resource_id.clone())
+        .or_else(|| {
+            file.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    let notifiers: Vec<Box<dyn Notifier>> = notify_slack
+        .into_iter()
+        .map(|url| Box::new(Slack::new(url).with_prefix(DRILL)) as Box<dyn Notifier>)
+        .collect();
+
+    let mut writer = TabWriter::new(io::stdout());
+    let mut previous = None;
+    let mut replayed = Vec::new();
+    for event in events {
+        if let Some(previous) = previous {
+            sleep(speed.scale(event.timestamp - previous));
+        }
+        previous = Some(event.timestamp);
+        writeln!(
+            &mut writer,
+            "{}",
+            Formatted(
+                event.clone(),
+                &Clock::default(),
+                markers,
+                String::new(),
+                Columns::default(),
+                theme
+            )
+        )?;
+        writer.flush()?;
+        replayed.push(event.clone());
+
+        let notification = if event.is_stack() && event.complete_or_failed() {
+            Notification::Complete(&event, Summary::of(&stack_name, &replayed))
+        } else if event.status.ends_with(FAILED) {
+            Notification::Failure(&event)
+        } else {
+            continue;
+        };
+        // an unreachable notification channel is no reason to cut the drill short
+        for notifier in &notifiers {
+            if let Err(err) = notifier.notify(&stack_name, &notification) {
+                eprintln!(
+                    "{} failed to deliver notification: {}",
+                    theme.notice("notice:"),
+                    err
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_parses_multipliers() {
+        assert_eq!("5x".parse(), Ok(Speed(5.0)));
+        assert_eq!("0.5".parse(), Ok(Speed(0.5)));
+        assert!("0x".parse::<Speed>().is_err());
+        assert!("fast".parse::<Speed>().is_err());
+    }
+
+    #[test]
+    fn speed_scales_elapsed_time() {
+        assert_eq!(
+            Speed(5.0).scale(chrono::Duration::seconds(10)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            Speed(1.0).scale(chrono::Duration::seconds(-1)),
+            Duration::from_secs(0)
+        );
+    }
+}
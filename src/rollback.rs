@@ -0,0 +1,94 @@
+//! Rollback triggers, the CloudWatch alarms which roll back operations when they fire
+use colored::Colorize;
+use rusoto_cloudformation::RollbackConfiguration;
+use std::fmt;
+
+const ALARM_ARN: &str = "arn:aws:cloudwatch:";
+
+/// The name of an alarm given its arn, like `arn:aws:cloudwatch:{region}:{account}:alarm:{name}`
+fn alarm_name(arn: &str) -> &str {
+    arn.splitn(7, ':').nth(6).unwrap_or(arn)
+}
+
+/// Names of the alarms a rollback's reason mentions, when an alarm rather than
+/// a resource failure triggered it
+pub fn alarms(reason: &str) -> Vec<&str> {
+    reason
+        .match_indices(ALARM_ARN)
+        .map(|(start, _)| {
+            let arn = &reason[start..];
+            let end = arn
+                .find(|c: char| c == ',' || c == ']' || c == ')' || c.is_whitespace())
+                .unwrap_or_else(|| arn.len());
+            alarm_name(&arn[..end])
+        })
+        .collect()
+}
+
+/// Provides a means of displaying a stack's rollback alarms and how long
+/// they're monitored for after an operation
+pub struct Triggers<'a>(pub Option<&'a RollbackConfiguration>);
+
+impl fmt::Display for Triggers<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let Triggers(configuration) = self;
+        let names = configuration
+            .and_then(|configuration| configuration.rollback_triggers.as_ref())
+            .map(|triggers| {
+                triggers
+                    .iter()
+                    .map(|trigger| alarm_name(&trigger.arn))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if names.is_empty() {
+            return write!(f, "{}", "none".bright_black());
+        }
+        write!(f, "{}", names.join(", "))?;
+        match configuration.and_then(|configuration| configuration.monitoring_time_in_minutes) {
+            Some(minutes) if minutes > 0 => write!(
+                f,
+                " {}",
+                format!("monitored for {}m", minutes).bright_black()
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudformation::RollbackTrigger;
+
+    #[test]
+    fn alarms_are_named_in_reasons() {
+        assert_eq!(
+            alarms("Rollback triggered by alarm(s): [arn:aws:cloudwatch:us-east-1:111111111111:alarm:HighErrorRate, arn:aws:cloudwatch:us-east-1:111111111111:alarm:Latency]"),
+            vec!["HighErrorRate", "Latency"]
+        );
+        assert!(alarms("The following resource(s) failed to update: [Service]").is_empty());
+    }
+
+    #[test]
+    fn triggers_list_alarm_names() {
+        let configuration = RollbackConfiguration {
+            rollback_triggers: Some(vec![RollbackTrigger {
+                arn: "arn:aws:cloudwatch:us-east-1:111111111111:alarm:HighErrorRate".into(),
+                type_: "AWS::CloudWatch::Alarm".into(),
+            }]),
+            monitoring_time_in_minutes: Some(10),
+        };
+        assert_eq!(
+            console::strip_ansi_codes(&Triggers(Some(&configuration)).to_string()),
+            "HighErrorRate monitored for 10m"
+        );
+        assert_eq!(
+            console::strip_ansi_codes(&Triggers(None).to_string()),
+            "none"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Selecting the stacks to tail by their tags, names or regions
+use crate::{cache, filter::Pattern, missing_stack, Error};
+use colored::Colorize;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, DescribeStacksInput, ListStacksInput, Stack, StackSummary,
+};
+use rusoto_core::Region;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+/// A tag stacks must have, like `team=payments`
+#[derive(Debug, PartialEq, Clone)]
+pub struct TagFilter {
+    key: String,
+    value: String,
+}
+
+impl FromStr for TagFilter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find('=') {
+            Some(position) if position > 0 => Ok(TagFilter {
+                key: s[..position].into(),
+                value: s[position + 1..].into(),
+            }),
+            _ => Err(format!("invalid tag {}, expected key=value", s)),
+        }
+    }
+}
+
+impl TagFilter {
+    fn matches(
+        &self,
+        stack: &Stack,
+    ) -> bool {
+        stack
+            .tags
+            .iter()
+            .flatten()
+            .any(|tag| tag.key == self.key && tag.value == self.value)
+    }
+}
+
+/// Cache listing of the stacks with every one of the given tags
+fn tagged_listing(tags: &[TagFilter]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for tag in tags {
+        (&tag.key, &tag.value).hash(&mut hasher);
+    }
+    format!("tagged-{:016x}", hasher.finish())
+}
+
+/// Names of stacks with every one of the given tags, in a client's account
+/// and region, or those of the default credentials when not given. `refresh`
+/// bypasses names cached by recent runs
+pub fn tagged(
+    cf: &CloudFormationClient,
+    account: Option<&str>,
+    region: &Region,
+    tags: &[TagFilter],
+    refresh: bool,
+) -> Result<Vec<String>, Error> {
+    cache::stack_names(account, region, &tagged_listing(tags), refresh, || {
+        let mut names = Vec::new();
+        let mut next_token = None;
+        loop {
+            let output = cf
+                .describe_stacks(DescribeStacksInput {
+                    next_token: next_token.clone(),
+                    ..DescribeStacksInput::default()
+                })
+                .sync()?;
+            names.extend(
+                output
+                    .stacks
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|stack| tags.iter().all(|tag| tag.matches(stack)))
+                    .map(|stack| stack.stack_name),
+            );
+            match output.next_token {
+                token @ Some(_) => next_token = token,
+                None => return Ok(names),
+            }
+        }
+    })
+}
+
+/// Whether a stack still exists
+fn exists(summary: &StackSummary) -> bool {
+    summary.stack_status != "DELETE_COMPLETE"
+}
+
+/// Whether a stack name matches any of the given patterns
+fn matches(
+    stack_name: &str,
+    patterns: &[Pattern],
+) -> bool {
+    patterns.iter().any(|pattern| pattern.matches(stack_name))
+}
+
+/// Names of existing stacks matching any of the given patterns, in a
+/// client's account and region, or those of the default credentials when
+/// not given. `refresh` bypasses names cached by recent runs
+pub fn matching(
+    cf: &CloudFormationClient,
+    account: Option<&str>,
+    region: &Region,
+    patterns: &[Pattern],
+    refresh: bool,
+) -> Result<Vec<String>, Error> {
+    let existing = cache::stack_names(account, region, "existing", refresh, || {
+        let mut names = Vec::new();
+        let mut next_token = None;
+        loop {
+            let output = cf
+                .list_stacks(ListStacksInput {
+                    next_token: next_token.clone(),
+                    ..ListStacksInput::default()
+                })
+                .sync()?;
+            names.extend(
+                output
+                    .stack_summaries
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(exists)
+                    .map(|summary| summary.stack_name),
+            );
+            match output.next_token {
+                token @ Some(_) => next_token = token,
+                None => return Ok::<_, Error>(names),
+            }
+        }
+    })?;
+    Ok(existing
+        .into_iter()
+        .filter(|stack_name| matches(stack_name, patterns))
+        .collect())
+}
+
+/// Ids of the named stacks which exist in a client's region, skipping those
+/// which don't
+pub fn ids(
+    cf: &CloudFormationClient,
+    region: &Region,
+    stack_names: &[String],
+) -> Result<Vec<String>, Error> {
+    let mut ids = Vec::new();
+    for stack_name in stack_names {
+        match cf
+            .describe_stacks(DescribeStacksInput {
+                stack_name: Some(stack_name.clone()),
+                ..DescribeStacksInput::default()
+            })
+            .sync()
+        {
+            Ok(output) => ids.extend(
+                output
+                    .stacks
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|stack| stack.stack_id),
+            ),
+            Err(ref err) if missing_stack(err) => eprintln!(
+                "{} {} doesn't exist in {}",
+                "notice:".bold().yellow(),
+                stack_name,
+                region.name()
+            ),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusoto_cloudformation::Tag;
+
+    #[test]
+    fn tag_filter_parses_key_and_value() {
+        assert_eq!(
+            "team=payments".parse(),
+            Ok(TagFilter {
+                key: "team".into(),
+                value: "payments".into()
+            })
+        );
+        assert_eq!(
+            "env=".parse(),
+            Ok(TagFilter {
+                key: "env".into(),
+                value: "".into()
+            })
+        );
+        assert!("team".parse::<TagFilter>().is_err());
+        assert!("=payments".parse::<TagFilter>().is_err());
+    }
+
+    #[test]
+    fn tag_filter_matches_stack_tags() -> Result<(), String> {
+        let stack = Stack {
+            tags: Some(vec![Tag {
+                key: "team".into(),
+                value: "payments".into(),
+            }]),
+            ..Stack::default()
+        };
+        assert!("team=payments".parse::<TagFilter>()?.matches(&stack));
+        assert!(!"team=search".parse::<TagFilter>()?.matches(&stack));
+        Ok(())
+    }
+
+    #[test]
+    fn matches_existing_stacks_by_name() -> Result<(), String> {
+        let summary = |status: &str| StackSummary {
+            stack_status: status.into(),
+            ..StackSummary::default()
+        };
+        assert!(exists(&summary("UPDATE_COMPLETE")));
+        assert!(!exists(&summary("DELETE_COMPLETE")));
+        let patterns = vec!["prod-api-*".parse()?];
+        assert!(matches("prod-api-users", &patterns));
+        assert!(!matches("dev-api-users", &patterns));
+        Ok(())
+    }
+
+    #[test]
+    fn tagged_listings_differ_by_tags() -> Result<(), String> {
+        let payments = vec!["team=payments".parse()?];
+        assert_eq!(tagged_listing(&payments), tagged_listing(&payments));
+        assert_ne!(
+            tagged_listing(&payments),
+            tagged_listing(&["team=search".parse()?])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Snapshots of a stack's resources reconstructed from archived events,
+//! for comparison with their current state
+use crate::{latest, stack_logical_id, ResourceState};
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use std::fmt;
+
+/// The state of each of a stack's resources as of a point in time,
+/// excluding the stack itself and resources deleted by then
+pub fn at(
+    stack_name: &str,
+    events: Vec<ResourceState>,
+    moment: DateTime<FixedOffset>,
+) -> Vec<ResourceState> {
+    latest(
+        events
+            .into_iter()
+            .filter(|event| event.timestamp <= moment)
+            .collect(),
+    )
+    .into_iter()
+    .filter(|state| {
+        !(state.is_stack() && state.resource_id == stack_logical_id(stack_name))
+            && state.status != "DELETE_COMPLETE"
+    })
+    .collect()
+}
+
+/// How a resource's current state differs from a snapshot
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    Unchanged,
+    Appeared,
+    /// The resource's status changed from this one
+    Changed(&'a str),
+    Disappeared,
+}
+
+impl<'a> Change<'a> {
+    pub fn of(
+        baseline: &'a [ResourceState],
+        state: &ResourceState,
+    ) -> Self {
+        match baseline
+            .iter()
+            .find(|previous| previous.resource_id == state.resource_id)
+        {
+            None => Change::Appeared,
+            Some(previous) if previous.status != state.status => Change::Changed(&previous.status),
+            Some(_) => Change::Unchanged,
+        }
+    }
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match self {
+            Change::Unchanged => Ok(()),
+            Change::Appeared => write!(f, "{}", "+ appeared".bright_green()),
+            Change::Changed(status) => write!(f, "{}", format!("~ was {}", status).bright_yellow()),
+            Change::Disappeared => write!(f, "{}", "- disappeared".bright_red()),
+        }
+    }
+}
+
+/// Snapshot resources which no longer exist
+pub fn disappeared<'a>(
+    baseline: &'a [ResourceState],
+    current: &[ResourceState],
+) -> Vec<&'a ResourceState> {
+    baseline
+        .iter()
+        .filter(|previous| {
+            !current
+                .iter()
+                .any(|state| state.resource_id == previous.resource_id)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn at_time(
+        resource_id: &str,
+        status: &str,
+        timestamp: &str,
+    ) -> ResourceState {
+        ResourceState {
+            timestamp: DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp"),
+            ..state("AWS::SQS::Queue", resource_id, status)
+        }
+    }
+
+    #[test]
+    fn snapshot_reflects_events_until_moment() {
+        let events = vec![
+            ResourceState {
+                timestamp: DateTime::parse_from_rfc3339("2019-01-01T00:00:00Z")
+                    .expect("invalid timestamp"),
+                ..state(STACK_RESOURCE, "stack", "CREATE_IN_PROGRESS")
+            },
+            at_time("a", "CREATE_COMPLETE", "2019-01-01T00:01:00Z"),
+            at_time("b", "CREATE_COMPLETE", "2019-01-01T00:01:00Z"),
+            at_time("b", "DELETE_COMPLETE", "2019-01-01T00:02:00Z"),
+            at_time("a", "UPDATE_FAILED", "2019-01-02T00:00:00Z"),
+        ];
+        let snapshot = at(
+            "stack",
+            events,
+            DateTime::parse_from_rfc3339("2019-01-01T12:00:00Z").expect("invalid timestamp"),
+        );
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].status, "CREATE_COMPLETE");
+    }
+
+    #[test]
+    fn change_compares_with_baseline() {
+        let baseline = vec![
+            state("AWS::SQS::Queue", "a", "CREATE_COMPLETE"),
+            state("AWS::SQS::Queue", "b", "CREATE_COMPLETE"),
+        ];
+        let current = vec![
+            state("AWS::SQS::Queue", "a", "UPDATE_COMPLETE"),
+            state("AWS::SQS::Queue", "c", "CREATE_COMPLETE"),
+        ];
+        assert_eq!(
+            Change::of(&baseline, &current[0]),
+            Change::Changed("CREATE_COMPLETE")
+        );
+        assert_eq!(Change::of(&baseline, &current[1]), Change::Appeared);
+        assert_eq!(
+            disappeared(&baseline, &current)
+                .iter()
+                .map(|state| state.resource_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["b"]
+        );
+    }
+}
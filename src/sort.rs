@@ -0,0 +1,121 @@
+//! Ordering of resources in the resources view
+use crate::{ResourceState, FAILED, IN_PROGRESS};
+use std::{cmp::Ordering, str::FromStr};
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SortBy {
+    Status,
+    Type,
+    Name,
+    Timestamp,
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "status" => Ok(SortBy::Status),
+            "type" => Ok(SortBy::Type),
+            "name" => Ok(SortBy::Name),
+            "timestamp" => Ok(SortBy::Timestamp),
+            other => Err(format!("unsupported sort {}", other)),
+        }
+    }
+}
+
+/// Ranks statuses so failures sort first, then those in progress
+fn severity(status: &str) -> u8 {
+    match status {
+        failed if failed.ends_with(FAILED) => 0,
+        pending if pending.ends_with(IN_PROGRESS) => 1,
+        _ => 2,
+    }
+}
+
+impl SortBy {
+    fn compare(
+        self,
+        a: &ResourceState,
+        b: &ResourceState,
+    ) -> Ordering {
+        match self {
+            SortBy::Status => (severity(&a.status), &a.status, &a.resource_id).cmp(&(
+                severity(&b.status),
+                &b.status,
+                &b.resource_id,
+            )),
+            SortBy::Type => {
+                (&a.resource_type, &a.resource_id).cmp(&(&b.resource_type, &b.resource_id))
+            }
+            SortBy::Name => a.resource_id.cmp(&b.resource_id),
+            SortBy::Timestamp => a.timestamp.cmp(&b.timestamp),
+        }
+    }
+
+    /// Sorts resources, in reverse when requested
+    pub fn sort(
+        self,
+        states: &mut [ResourceState],
+        reverse: bool,
+    ) {
+        states.sort_by(|a, b| {
+            let ordering = self.compare(a, b);
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn ids(states: &[ResourceState]) -> Vec<&str> {
+        states.iter().map(|s| s.resource_id.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_by_status_puts_failures_first() {
+        let mut states = vec![
+            state("AWS::SQS::Queue", "a", "CREATE_COMPLETE"),
+            state("AWS::SQS::Queue", "b", "CREATE_IN_PROGRESS"),
+            state("AWS::SQS::Queue", "c", "CREATE_FAILED"),
+        ];
+        SortBy::Status.sort(&mut states, false);
+        assert_eq!(ids(&states), vec!["c", "b", "a"]);
+        SortBy::Status.sort(&mut states, true);
+        assert_eq!(ids(&states), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn sort_by_type_then_name() {
+        let mut states = vec![
+            state("AWS::SQS::Queue", "b", "CREATE_COMPLETE"),
+            state("AWS::SNS::Topic", "c", "CREATE_COMPLETE"),
+            state("AWS::SQS::Queue", "a", "CREATE_COMPLETE"),
+        ];
+        SortBy::Type.sort(&mut states, false);
+        assert_eq!(ids(&states), vec!["c", "a", "b"]);
+    }
+}
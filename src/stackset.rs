@@ -0,0 +1,361 @@
+//! Following StackSet operations across the accounts and regions of their instances
+use crate::{Error, Markers, Screen, FAILED_STATUS};
+use colored::Colorize;
+use console::Term;
+use rusoto_cloudformation::{
+    CloudFormation, CloudFormationClient, DescribeStackSetOperationInput, ListStackInstancesInput,
+    ListStackSetOperationResultsInput, ListStackSetOperationsInput, StackInstanceSummary,
+    StackSetOperation, StackSetOperationResultSummary,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    process,
+    thread::sleep,
+    time::Duration,
+};
+use structopt::StructOpt;
+use tabwriter::TabWriter;
+
+/// Operation statuses after which an operation no longer changes
+const FINISHED: &[&str] = &["SUCCEEDED", "FAILED", "STOPPED"];
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        long = "operation",
+        help = "Id of the operation to follow, defaulting to the stack set's most recent operation"
+    )]
+    pub operation: Option<String>,
+    #[structopt(
+        long = "interval",
+        default_value = "5s",
+        parse(try_from_str = "humantime::parse_duration"),
+        help = "Time between polls, for example 30s"
+    )]
+    pub interval: Duration,
+    #[structopt(
+        short = "r",
+        long = "resources",
+        help = "Show a matrix of instance statuses by account and region"
+    )]
+    pub resources: bool,
+    pub stack_set_name: String,
+}
+
+/// The marker of an instance's operation result or status, in terms of the
+/// stack statuses markers are drawn for
+fn marker(
+    markers: Markers,
+    status: &str,
+) -> &'static str {
+    markers.status(match status {
+        "SUCCEEDED" | "CURRENT" => "UPDATE_COMPLETE",
+        "FAILED" | "INOPERABLE" => "UPDATE_FAILED",
+        // cancelled instances are left as they were
+        "CANCELLED" => "DELETE_COMPLETE",
+        _ => "UPDATE_IN_PROGRESS",
+    })
+}
+
+fn colored(status: &str) -> colored::ColoredString {
+    match status {
+        "SUCCEEDED" | "CURRENT" => status.bold().bright_green(),
+        "FAILED" | "STOPPED" | "INOPERABLE" => status.bold().bright_red(),
+        _ => status.normal(),
+    }
+}
+
+/// A line naming a stack set and the action and status of its operation
+fn heading(
+    stack_set_name: &str,
+    operation: &StackSetOperation,
+) -> String {
+    format!(
+        "{} {} {}",
+        stack_set_name.bold(),
+        operation.action.as_deref().unwrap_or("operation"),
+        colored(operation.status.as_deref().unwrap_or("status unknown"))
+    )
+}
+
+fn lines(writer: TabWriter<Vec<u8>>) -> Vec<String> {
+    let output = writer.into_inner().unwrap_or_default();
+    String::from_utf8_lossy(&output)
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+/// Lines reporting an operation and the result of each of its instances
+fn report(
+    stack_set_name: &str,
+    operation: &StackSetOperation,
+    results: &[StackSetOperationResultSummary],
+    markers: Markers,
+) -> Vec<String> {
+    let mut writer = TabWriter::new(Vec::new());
+    drop(writeln!(writer, "{}", heading(stack_set_name, operation)));
+    for result in results {
+        let status = result.status.as_deref().unwrap_or_default();
+        drop(writeln!(
+            writer,
+            "{}\t{}\t{} {}\t{}",
+            result.account.as_deref().unwrap_or_default().bold(),
+            result.region.as_deref().unwrap_or_default(),
+            marker(markers, status),
+            colored(status),
+            result
+                .status_reason
+                .as_deref()
+                .unwrap_or_default()
+                .bright_black()
+        ));
+    }
+    lines(writer)
+}
+
+/// Lines of a matrix of every instance's status, with a row per account and
+/// a column per region. Instances taking part in the operation show their
+/// result in it, others their last known status
+fn matrix(
+    stack_set_name: &str,
+    operation: &StackSetOperation,
+    instances: &[StackInstanceSummary],
+    results: &[StackSetOperationResultSummary],
+    markers: Markers,
+) -> Vec<String> {
+    let mut statuses = BTreeMap::new();
+    for (account, region, status) in instances
+        .iter()
+        .map(|instance| (&instance.account, &instance.region, &instance.status))
+        .chain(
+            results
+                .iter()
+                .map(|result| (&result.account, &result.region, &result.status)),
+        )
+    {
+        if let (Some(account), Some(region)) = (account, region) {
+            statuses.insert(
+                (account.as_str(), region.as_str()),
+                status.as_deref().unwrap_or_default(),
+            );
+        }
+    }
+    let regions = statuses
+        .keys()
+        .map(|(_, region)| *region)
+        .collect::<BTreeSet<_>>();
+    let accounts = statuses
+        .keys()
+        .map(|(account, _)| *account)
+        .collect::<BTreeSet<_>>();
+    let mut writer = TabWriter::new(Vec::new());
+    drop(writeln!(writer, "{}", heading(stack_set_name, operation)));
+    drop(writeln!(
+        writer,
+        "\t{}",
+        regions.iter().cloned().collect::<Vec<_>>().join("\t")
+    ));
+    for account in accounts {
+        let cells = regions
+            .iter()
+            .map(|region| match statuses.get(&(account, region)) {
+                Some(status) => format!("{} {}", marker(markers, status), colored(status)),
+                None => "-".into(),
+            })
+            .collect::<Vec<_>>();
+        drop(writeln!(writer, "{}\t{}", account.bold(), cells.join("\t")));
+    }
+    lines(writer)
+}
+
+/// The id of a stack set's most recent operation
+fn latest_operation(
+    cf: &CloudFormationClient,
+    stack_set_name: &str,
+) -> Result<String, Error> {
+    cf.list_stack_set_operations(ListStackSetOperationsInput {
+        stack_set_name: stack_set_name.into(),
+        ..ListStackSetOperationsInput::default()
+    })
+    .sync()?
+    .summaries
+    .unwrap_or_default()
+    .into_iter()
+    .filter_map(|summary| Some((summary.creation_timestamp?, summary.operation_id?)))
+    .max()
+    .map(|(_, operation_id)| operation_id)
+    .ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} has no operations", stack_set_name),
+        )
+        .into()
+    })
+}
+
+/// Every instance of a stack set
+fn instances(
+    cf: &CloudFormationClient,
+    stack_set_name: &str,
+) -> Result<Vec<StackInstanceSummary>, Error> {
+    let mut instances = Vec::new();
+    let mut next_token = None;
+    loop {
+        let output = cf
+            .list_stack_instances(ListStackInstancesInput {
+                stack_set_name: stack_set_name.into(),
+                next_token: next_token.clone(),
+                ..ListStackInstancesInput::default()
+            })
+            .sync()?;
+        instances.extend(output.summaries.unwrap_or_default());
+        match output.next_token {
+            token @ Some(_) => next_token = token,
+            None => return Ok(instances),
+        }
+    }
+}
+
+/// The result of each of an operation's instances
+fn results(
+    cf: &CloudFormationClient,
+    stack_set_name: &str,
+    operation_id: &str,
+) -> Result<Vec<StackSetOperationResultSummary>, Error> {
+    let mut results = Vec::new();
+    let mut next_token = None;
+    loop {
+        let output = cf
+            .list_stack_set_operation_results(ListStackSetOperationResultsInput {
+                stack_set_name: stack_set_name.into(),
+                operation_id: operation_id.into(),
+                next_token: next_token.clone(),
+                ..ListStackSetOperationResultsInput::default()
+            })
+            .sync()?;
+        results.extend(output.summaries.unwrap_or_default());
+        match output.next_token {
+            token @ Some(_) => next_token = token,
+            None => return Ok(results),
+        }
+    }
+}
+
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+    markers: Markers,
+) -> Result<(), Error> {
+    let Options {
+        operation,
+        interval,
+        resources,
+        stack_set_name,
+    } = options;
+    let operation_id = match operation {
+        Some(operation_id) => operation_id,
+        None => latest_operation(&cf, &stack_set_name)?,
+    };
+    let mut screen = Screen::new(Term::stdout(), markers == Markers::Plain);
+    loop {
+        let operation = cf
+            .describe_stack_set_operation(DescribeStackSetOperationInput {
+                stack_set_name: stack_set_name.clone(),
+                operation_id: operation_id.clone(),
+            })
+            .sync()?
+            .stack_set_operation
+            .unwrap_or_default();
+        let results = results(&cf, &stack_set_name, &operation_id)?;
+        let lines = if resources {
+            let instances = instances(&cf, &stack_set_name)?;
+            matrix(&stack_set_name, &operation, &instances, &results, markers)
+        } else {
+            report(&stack_set_name, &operation, &results, markers)
+        };
+        screen.update(lines)?;
+        match operation.status.as_deref() {
+            Some(status) if FINISHED.contains(&status) => {
+                if status != "SUCCEEDED" {
+                    process::exit(FAILED_STATUS);
+                }
+                return Ok(());
+            }
+            _ => sleep(interval),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_lists_instance_results() {
+        let operation = StackSetOperation {
+            action: Some("UPDATE".into()),
+            status: Some("RUNNING".into()),
+            ..StackSetOperation::default()
+        };
+        let results = vec![StackSetOperationResultSummary {
+            account: Some("111111111111".into()),
+            region: Some("us-east-1".into()),
+            status: Some("FAILED".into()),
+            status_reason: Some("Resource limit exceeded".into()),
+            ..StackSetOperationResultSummary::default()
+        }];
+        let lines = report("baseline", &operation, &results, Markers::Ascii)
+            .iter()
+            .map(|line| crate::words(&console::strip_ansi_codes(line)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "baseline UPDATE RUNNING",
+                "111111111111 us-east-1 [FAIL] FAILED Resource limit exceeded"
+            ]
+        );
+    }
+
+    #[test]
+    fn matrix_prefers_operation_results() {
+        let instance = |account: &str, region: &str| StackInstanceSummary {
+            account: Some(account.into()),
+            region: Some(region.into()),
+            status: Some("CURRENT".into()),
+            ..StackInstanceSummary::default()
+        };
+        let instances = vec![
+            instance("111111111111", "us-east-1"),
+            instance("111111111111", "eu-west-1"),
+            instance("222222222222", "us-east-1"),
+        ];
+        let results = vec![StackSetOperationResultSummary {
+            account: Some("222222222222".into()),
+            region: Some("us-east-1".into()),
+            status: Some("RUNNING".into()),
+            ..StackSetOperationResultSummary::default()
+        }];
+        let lines = matrix(
+            "baseline",
+            &StackSetOperation::default(),
+            &instances,
+            &results,
+            Markers::Ascii,
+        )
+        .iter()
+        .map(|line| crate::words(&console::strip_ansi_codes(line)))
+        .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "baseline operation status unknown",
+                "eu-west-1 us-east-1",
+                "111111111111 [OK] CURRENT [OK] CURRENT",
+                "222222222222 - [..] RUNNING",
+            ]
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Resource duration statistics across archived stack operations
+use crate::{archive, config::Config, durations, humanize, operations, Error, ResourceState};
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    str::FromStr,
+};
+use structopt::StructOpt;
+use tabwriter::TabWriter;
+
+#[derive(PartialEq, Debug)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            other => Err(format!("unsupported output {}", other)),
+        }
+    }
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        short = "l",
+        long = "last",
+        default_value = "20",
+        help = "Number of most recent operations to include"
+    )]
+    pub last: usize,
+    #[structopt(
+        short = "o",
+        long = "output",
+        default_value = "table",
+        raw(possible_values = r#"&["table", "json"]"#),
+        help = "Output format"
+    )]
+    pub output: Format,
+    pub stack_name: String,
+}
+
+/// Distribution of a resource's durations, in seconds
+#[derive(Serialize, Debug, PartialEq)]
+struct ResourceStats {
+    resource_id: String,
+    resource_type: String,
+    samples: usize,
+    min: i64,
+    p50: i64,
+    p95: i64,
+    max: i64,
+}
+
+#[derive(Serialize)]
+struct Report {
+    stack_name: String,
+    operations: usize,
+    resources: Vec<ResourceStats>,
+}
+
+/// Nearest rank percentile of sorted samples
+fn percentile(
+    sorted: &[i64],
+    p: usize,
+) -> i64 {
+    let rank = (p * sorted.len()).div_ceil(100);
+    sorted[rank.max(1) - 1]
+}
+
+fn stats(
+    stack_name: &str,
+    events: &[ResourceState],
+    last: usize,
+) -> (usize, Vec<ResourceStats>) {
+    let operations = operations(stack_name, events);
+    let recent = &operations[operations.len().saturating_sub(last)..];
+    let mut samples: BTreeMap<&str, (&str, Vec<i64>)> = BTreeMap::new();
+    for operation in recent {
+        let types = operation
+            .iter()
+            .map(|state| (state.resource_id.as_str(), state.resource_type.as_str()))
+            .collect::<BTreeMap<_, _>>();
+        for (resource_id, duration) in durations(operation) {
+            samples
+                .entry(resource_id)
+                .or_insert_with(|| {
+                    (
+                        types.get(resource_id).cloned().unwrap_or_default(),
+                        Vec::new(),
+                    )
+                })
+                .1
+                .push(duration.num_seconds());
+        }
+    }
+    let resources = samples
+        .into_iter()
+        .map(|(resource_id, (resource_type, mut durations))| {
+            durations.sort();
+            ResourceStats {
+                resource_id: resource_id.into(),
+                resource_type: resource_type.into(),
+                samples: durations.len(),
+                min: durations[0],
+                p50: percentile(&durations, 50),
+                p95: percentile(&durations, 95),
+                max: durations[durations.len() - 1],
+            }
+        })
+        .collect();
+    (recent.len(), resources)
+}
+
+fn seconds(s: i64) -> String {
+    humanize(chrono::Duration::seconds(s))
+}
+
+/// Reports the distribution of per-resource durations across
+/// a stack's most recent archived operations
+pub fn run(options: Options) -> Result<(), Error> {
+    let Options {
+        last,
+        output,
+        stack_name,
+    } = options;
+    let store = match Config::load()?.archive {
+        Some(backend) => archive::open(backend)?,
+        None => {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no archive is configured, stats are computed from archived events",
+            )))
+        }
+    };
+    let (operations, resources) = stats(&stack_name, &store.load(&stack_name)?, last);
+    match output {
+        Format::Json => {
+            serde_json::to_writer_pretty(
+                io::stdout(),
+                &Report {
+                    stack_name,
+                    operations,
+                    resources,
+                },
+            )?;
+            println!();
+        }
+        Format::Table => {
+            let mut writer = TabWriter::new(io::stdout());
+            writeln!(
+                &mut writer,
+                "{}",
+                "resource\ttype\tsamples\tmin\tp50\tp95\tmax".bold()
+            )?;
+            for stats in resources {
+                writeln!(
+                    &mut writer,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    stats.resource_id.bold(),
+                    stats.resource_type.bright_black(),
+                    stats.samples,
+                    seconds(stats.min),
+                    seconds(stats.p50),
+                    seconds(stats.p95),
+                    seconds(stats.max)
+                )?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    fn at(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+        timestamp: &str,
+    ) -> ResourceState {
+        ResourceState {
+            timestamp: DateTime::parse_from_rfc3339(timestamp).expect("invalid timestamp"),
+            ..state(resource_type, resource_id, status)
+        }
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted = (1..=20).collect::<Vec<_>>();
+        assert_eq!(percentile(&sorted, 50), 10);
+        assert_eq!(percentile(&sorted, 95), 19);
+        assert_eq!(percentile(&[7], 95), 7);
+    }
+
+    #[test]
+    fn stats_aggregate_recent_operations() {
+        let events = vec![
+            at(
+                STACK_RESOURCE,
+                "stack",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-01T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-01T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_COMPLETE",
+                "2019-01-01T00:01:00Z",
+            ),
+            at(
+                STACK_RESOURCE,
+                "stack",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-02T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-02T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_COMPLETE",
+                "2019-01-02T00:00:30Z",
+            ),
+            at(
+                STACK_RESOURCE,
+                "stack",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-03T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_IN_PROGRESS",
+                "2019-01-03T00:00:00Z",
+            ),
+            at(
+                "AWS::SQS::Queue",
+                "queue",
+                "UPDATE_COMPLETE",
+                "2019-01-03T00:00:10Z",
+            ),
+        ];
+        let (operations, resources) = stats("stack", &events, 2);
+        assert_eq!(operations, 2);
+        assert_eq!(
+            resources,
+            vec![ResourceStats {
+                resource_id: "queue".into(),
+                resource_type: "AWS::SQS::Queue".into(),
+                samples: 2,
+                min: 10,
+                p50: 10,
+                p95: 30,
+                max: 30,
+            }]
+        );
+    }
+}
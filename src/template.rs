@@ -0,0 +1,129 @@
+//! Stack templates and the resource attributes declared in them
+use crate::Error;
+use rusoto_cloudformation::{CloudFormation, CloudFormationClient, GetTemplateInput};
+use serde_json::Value;
+use std::{collections::HashMap, fmt};
+
+/// A stack's processed template, and whether it was authored in YAML
+pub fn fetch(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<(Value, bool), Error> {
+    let body = cf
+        .get_template(GetTemplateInput {
+            stack_name: Some(stack_name.into()),
+            template_stage: Some("Processed".into()),
+            ..GetTemplateInput::default()
+        })
+        .sync()?
+        .template_body
+        .unwrap_or_default();
+    match serde_json::from_str(&body) {
+        Ok(template) => Ok((template, false)),
+        Err(_) => Ok((serde_yaml::from_str(&body)?, true)),
+    }
+}
+
+/// Number of resources a template declares
+pub fn resource_count(template: &Value) -> usize {
+    template
+        .get("Resources")
+        .and_then(Value::as_object)
+        .map(|resources| resources.len())
+        .unwrap_or_default()
+}
+
+/// Policies governing what happens to a resource's physical resource when it is
+/// removed from a stack or replaced. Unset policies default to Delete
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Policies {
+    pub deletion: Option<String>,
+    pub update_replace: Option<String>,
+}
+
+impl Policies {
+    /// Returns true when either policy keeps the physical resource around
+    pub fn protects(&self) -> bool {
+        self.deletion.is_some() || self.update_replace.is_some()
+    }
+}
+
+impl fmt::Display for Policies {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let mut policies = self
+            .deletion
+            .iter()
+            .map(|policy| (policy, "delete"))
+            .chain(self.update_replace.iter().map(|policy| (policy, "replace")));
+        if let Some((policy, on)) = policies.next() {
+            write!(f, "{} on {}", policy, on)?;
+        }
+        for (policy, on) in policies {
+            write!(f, ", {} on {}", policy, on)?;
+        }
+        Ok(())
+    }
+}
+
+/// Protective DeletionPolicy and UpdateReplacePolicy attributes declared for each
+/// resource of a template, keyed by logical id. Delete policies are omitted
+pub fn policies(template: &Value) -> HashMap<String, Policies> {
+    let protective = |resource: &Value, attribute: &str| {
+        resource
+            .get(attribute)
+            .and_then(Value::as_str)
+            .filter(|policy| *policy != "Delete")
+            .map(String::from)
+    };
+    template
+        .get("Resources")
+        .and_then(Value::as_object)
+        .map(|resources| {
+            resources
+                .iter()
+                .map(|(id, resource)| {
+                    (
+                        id.clone(),
+                        Policies {
+                            deletion: protective(resource, "DeletionPolicy"),
+                            update_replace: protective(resource, "UpdateReplacePolicy"),
+                        },
+                    )
+                })
+                .filter(|(_, policies)| policies.protects())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_include_protective_attributes() -> Result<(), serde_json::Error> {
+        let policies = policies(&serde_json::from_str(
+            r#"{
+                "Resources": {
+                    "Bucket": { "Type": "AWS::S3::Bucket", "DeletionPolicy": "Retain" },
+                    "Database": {
+                        "Type": "AWS::RDS::DBInstance",
+                        "DeletionPolicy": "Snapshot",
+                        "UpdateReplacePolicy": "Snapshot"
+                    },
+                    "Queue": { "Type": "AWS::SQS::Queue", "DeletionPolicy": "Delete" }
+                }
+            }"#,
+        )?);
+        assert_eq!(policies.len(), 2);
+        assert_eq!(policies["Bucket"].to_string(), "Retain on delete");
+        assert_eq!(
+            policies["Database"].to_string(),
+            "Snapshot on delete, Snapshot on replace"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+//! Colors statuses are displayed in, configurable for terminals and eyes
+//! the default palette doesn't suit
+use crate::{is_failure, COMPLETE, HOOK_COMPLETE_SUCCEEDED};
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+/// A named color, like `bright-blue`
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct ColorName(Color);
+
+impl TryFrom<String> for ColorName {
+    type Error = String;
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let color = match name.replace('_', "-").as_str() {
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "white" => Color::White,
+            "bright-black" => Color::BrightBlack,
+            "bright-red" => Color::BrightRed,
+            "bright-green" => Color::BrightGreen,
+            "bright-yellow" => Color::BrightYellow,
+            "bright-blue" => Color::BrightBlue,
+            "bright-magenta" => Color::BrightMagenta,
+            "bright-cyan" => Color::BrightCyan,
+            "bright-white" => Color::BrightWhite,
+            other => return Err(format!("unknown color {}", other)),
+        };
+        Ok(ColorName(color))
+    }
+}
+
+/// Built in sets of colors
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    Default,
+    /// Blue and yellow rather than green and red, which are hard to tell
+    /// apart with the most common forms of color blindness
+    ColorBlind,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::Default
+    }
+}
+
+/// A palette, with any of its colors overridden
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ThemeConfig {
+    pub palette: Palette,
+    pub success: Option<ColorName>,
+    pub failure: Option<ColorName>,
+    pub in_progress: Option<ColorName>,
+    pub muted: Option<ColorName>,
+    pub warning: Option<ColorName>,
+    pub accent: Option<ColorName>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Theme {
+    /// Color of complete statuses
+    pub success: Color,
+    /// Color of failed statuses
+    pub failure: Color,
+    /// Color of statuses still in progress, left uncolored when none
+    pub in_progress: Option<Color>,
+    /// Color of secondary text, like resource types and reasons
+    pub muted: Color,
+    /// Color of rollback pivots and AWS Health notices
+    pub warning: Color,
+    /// Color of the separators between operations
+    pub accent: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from(&ThemeConfig::default())
+    }
+}
+
+impl From<&ThemeConfig> for Theme {
+    fn from(config: &ThemeConfig) -> Self {
+        let (success, failure, warning) = match config.palette {
+            Palette::Default => (Color::BrightGreen, Color::BrightRed, Color::BrightYellow),
+            // yellow already marks failures
+            Palette::ColorBlind => (Color::BrightBlue, Color::BrightYellow, Color::BrightMagenta),
+        };
+        let color = |name: Option<ColorName>| name.map(|ColorName(color)| color);
+        Theme {
+            success: color(config.success).unwrap_or(success),
+            failure: color(config.failure).unwrap_or(failure),
+            in_progress: color(config.in_progress),
+            muted: color(config.muted).unwrap_or(Color::BrightBlack),
+            warning: color(config.warning).unwrap_or(warning),
+            accent: color(config.accent).unwrap_or(Color::BrightCyan),
+        }
+    }
+}
+
+impl Theme {
+    /// A status colored by whether it's complete, failed or in progress
+    pub fn status(
+        self,
+        status: &str,
+    ) -> ColoredString {
+        match status {
+            failed if is_failure(failed) => status.bold().color(self.failure),
+            complete if complete.ends_with(COMPLETE) || complete == HOOK_COMPLETE_SUCCEEDED => {
+                status.bold().color(self.success)
+            }
+            _ => match self.in_progress {
+                Some(color) => status.color(color),
+                None => status.normal(),
+            },
+        }
+    }
+
+    /// Secondary text, receding behind the rest of a line
+    pub fn muted(
+        self,
+        text: &str,
+    ) -> ColoredString {
+        text.color(self.muted)
+    }
+
+    /// A label drawing attention to what follows, like `notice:`
+    pub fn notice(
+        self,
+        label: &str,
+    ) -> ColoredString {
+        label.bold().color(self.warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_overrides_palette_colors() -> Result<(), toml::de::Error> {
+        let config = toml::from_str::<ThemeConfig>(
+            r#"
+            palette = "color-blind"
+            muted = "white"
+            "#,
+        )?;
+        assert_eq!(
+            Theme::from(&config),
+            Theme {
+                success: Color::BrightBlue,
+                failure: Color::BrightYellow,
+                in_progress: None,
+                muted: Color::White,
+                warning: Color::BrightMagenta,
+                accent: Color::BrightCyan,
+            }
+        );
+        assert!(toml::from_str::<ThemeConfig>(r#"success = "chartreuse""#).is_err());
+        Ok(())
+    }
+}
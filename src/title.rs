@@ -0,0 +1,94 @@
+//! Terminal window titles and iTerm2 badges summarizing followed stacks,
+//! for checking on a deploy from the tab bar
+use crate::{current_operation, progress::Progress, stack_status, ResourceState};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    io::{self, Write},
+};
+
+/// Summary of each stack like `my-stack: UPDATE_IN_PROGRESS 12/40`
+pub fn title(
+    stacks: &BTreeMap<String, Vec<ResourceState>>,
+    template_resources: Option<&HashMap<String, usize>>,
+) -> String {
+    stacks
+        .iter()
+        .map(|(stack_name, states)| {
+            let mut title = format!(
+                "{}: {}",
+                stack_name,
+                stack_status(stack_name, current_operation(stack_name, states))
+                    .unwrap_or("status unknown")
+            );
+            let total = template_resources.and_then(|counts| counts.get(stack_name).cloned());
+            if let Some(progress) = Progress::of(stack_name, states, total) {
+                title.push_str(&format!(" {}/{}", progress.done, progress.total));
+            }
+            title
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Sets the window title, and the badge when running in iTerm2
+pub fn set(
+    writer: &mut impl Write,
+    title: &str,
+) -> io::Result<()> {
+    write!(writer, "\x1b]0;{}\x07", title)?;
+    if env::var("TERM_PROGRAM").ok().as_deref() == Some("iTerm.app") {
+        write!(
+            writer,
+            "\x1b]1337;SetBadgeFormat={}\x07",
+            base64::encode(title)
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STACK_RESOURCE;
+    use chrono::DateTime;
+
+    fn state(
+        resource_type: &str,
+        resource_id: &str,
+        status: &str,
+    ) -> ResourceState {
+        ResourceState {
+            event_id: None,
+            resource_type: resource_type.into(),
+            timestamp: DateTime::parse_from_rfc3339("1996-12-19T16:39:57-08:00")
+                .expect("invalid timestamp"),
+            status: status.into(),
+            resource_id: resource_id.into(),
+            physical_id: None,
+            reason: "".into(),
+        }
+    }
+
+    #[test]
+    fn title_summarizes_stacks() {
+        let mut stacks = BTreeMap::new();
+        stacks.insert(
+            "stack".to_string(),
+            vec![
+                state(STACK_RESOURCE, "stack", "UPDATE_IN_PROGRESS"),
+                state("AWS::SQS::Queue", "queue", "UPDATE_COMPLETE"),
+                state("AWS::SNS::Topic", "topic", "UPDATE_IN_PROGRESS"),
+            ],
+        );
+        assert_eq!(title(&stacks, None), "stack: UPDATE_IN_PROGRESS 1/2");
+    }
+
+    #[test]
+    fn set_writes_title_escape() -> io::Result<()> {
+        let mut written = Vec::new();
+        set(&mut written, "stack: UPDATE_COMPLETE")?;
+        assert!(written.starts_with(b"\x1b]0;stack: UPDATE_COMPLETE\x07"));
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+//! Who initiated stack operations, according to CloudTrail
+use crate::{accounts::Accounts, Error, ResourceState};
+use colored::Colorize;
+use rusoto_cloudtrail::{
+    CloudTrail, CloudTrailClient, Event, LookupAttribute, LookupEventsRequest,
+};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Api calls which start stack operations
+const OPERATION_CALLS: &[&str] = &[
+    "CreateStack",
+    "UpdateStack",
+    "DeleteStack",
+    "ExecuteChangeSet",
+];
+
+/// How far around an operation's start its call is looked for, in seconds
+const WINDOW: f64 = 300.0;
+
+/// How long after an operation's start its call may be recorded, in seconds.
+/// Calls precede their operation's first event by moments, but clocks vary
+const SKEW: f64 = 60.0;
+
+/// Time between lookups of an operation's call while CloudTrail hasn't
+/// delivered it yet, which can take several minutes
+const RETRY: Duration = Duration::from_secs(30);
+
+/// The principal whose api call started an operation
+#[derive(Debug, PartialEq, Clone)]
+pub struct Initiator {
+    principal: String,
+    call: String,
+}
+
+impl fmt::Display for Initiator {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        write!(
+            f,
+            "  {} {} via {}",
+            "initiated by".bright_black(),
+            self.principal.bold(),
+            self.call
+        )
+    }
+}
+
+/// The initiator of a CloudTrail event, preferring the arn of its caller's
+/// identity over its username
+fn initiator(event: &Event) -> Option<Initiator> {
+    let arn = event
+        .cloud_trail_event
+        .as_deref()
+        .and_then(|record| serde_json::from_str::<Value>(record).ok())
+        .and_then(|record| {
+            record
+                .get("userIdentity")?
+                .get("arn")?
+                .as_str()
+                .map(String::from)
+        });
+    Some(Initiator {
+        principal: arn.or_else(|| event.username.clone())?,
+        call: event.event_name.clone()?,
+    })
+}
+
+/// The initiator of the call closest to, and no later than shortly after,
+/// an operation's start
+fn closest(
+    events: &[Event],
+    start: f64,
+) -> Option<Initiator> {
+    events
+        .iter()
+        .filter(|event| {
+            event
+                .event_name
+                .as_deref()
+                .map_or(false, |name| OPERATION_CALLS.contains(&name))
+        })
+        .filter_map(|event| Some((event.event_time?, event)))
+        .filter(|(time, _)| *time <= start + SKEW)
+        .min_by(|(a, _), (b, _)| {
+            (start - a)
+                .abs()
+                .partial_cmp(&(start - b).abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .and_then(|(_, event)| initiator(event))
+}
+
+/// Looks up the initiator of a stack operation starting at a given time
+fn lookup(
+    trail: &CloudTrailClient,
+    stack_id: &str,
+    start: f64,
+) -> Result<Option<Initiator>, Error> {
+    let events = trail
+        .lookup_events(LookupEventsRequest {
+            lookup_attributes: Some(vec![LookupAttribute {
+                attribute_key: "ResourceName".into(),
+                attribute_value: stack_id.into(),
+            }]),
+            start_time: Some(start - WINDOW),
+            end_time: Some(start + WINDOW),
+            ..LookupEventsRequest::default()
+        })
+        .sync()?
+        .events
+        .unwrap_or_default();
+    Ok(closest(&events, start))
+}
+
+/// Initiators of stack operations, keyed by the event which started them
+#[derive(Default)]
+pub struct Initiators {
+    found: HashMap<String, Initiator>,
+    /// When each operation's call was last looked for, or None once looking failed
+    attempted: HashMap<String, Option<Instant>>,
+}
+
+impl Initiators {
+    /// Looks up the initiator of the operation starting with a given stack
+    /// event, unless it's already known or was recently looked for. Failed
+    /// lookups are noted once rather than interrupting tailing
+    pub fn fetch(
+        &mut self,
+        accounts: &Accounts,
+        stack_name: &str,
+        start: &ResourceState,
+    ) {
+        let key = match &start.event_id {
+            Some(event_id) => event_id.clone(),
+            None => return,
+        };
+        if self.found.contains_key(&key)
+            || self.attempted.get(&key).map_or(false, |attempted| {
+                attempted.map_or(true, |attempted| attempted.elapsed() < RETRY)
+            })
+        {
+            return;
+        }
+        self.attempted.insert(key.clone(), Some(Instant::now()));
+        let stack_id = start.physical_id.as_deref().unwrap_or(stack_name);
+        let time = start.timestamp.timestamp() as f64;
+        match lookup(&accounts.trail_client_for(stack_name), stack_id, time) {
+            Ok(Some(initiator)) => {
+                self.found.insert(key, initiator);
+            }
+            Ok(None) => (),
+            Err(err) => {
+                eprintln!("{} {}", "notice:".bold().yellow(), err);
+                self.attempted.insert(key, None);
+            }
+        }
+    }
+
+    /// The initiator of the operation a stack event started, if known
+    pub fn of(
+        &self,
+        start: &ResourceState,
+    ) -> Option<&Initiator> {
+        start
+            .event_id
+            .as_ref()
+            .and_then(|event_id| self.found.get(event_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(
+        name: &str,
+        time: f64,
+        username: &str,
+    ) -> Event {
+        Event {
+            event_name: Some(name.into()),
+            event_time: Some(time),
+            username: Some(username.into()),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn initiator_prefers_identity_arn() {
+        let event = Event {
+            cloud_trail_event: Some(
+                r#"{"userIdentity":{"arn":"arn:aws:sts::111111111111:assumed-role/Deployer/ci"}}"#
+                    .into(),
+            ),
+            ..event("UpdateStack", 0.0, "ci")
+        };
+        assert_eq!(
+            initiator(&event),
+            Some(Initiator {
+                principal: "arn:aws:sts::111111111111:assumed-role/Deployer/ci".into(),
+                call: "UpdateStack".into()
+            })
+        );
+        assert_eq!(
+            initiator(&event("UpdateStack", 0.0, "alice")),
+            Some(Initiator {
+                principal: "alice".into(),
+                call: "UpdateStack".into()
+            })
+        );
+    }
+
+    #[test]
+    fn closest_picks_the_operation_call_nearest_its_start() {
+        let events = vec![
+            event("UpdateStack", 1000.0, "earlier"),
+            event("DescribeStacks", 1199.0, "reader"),
+            event("ExecuteChangeSet", 1198.0, "deployer"),
+            event("UpdateStack", 1600.0, "later"),
+        ];
+        assert_eq!(
+            closest(&events, 1200.0).map(|initiator| initiator.principal),
+            Some("deployer".into())
+        );
+        assert_eq!(closest(&[], 1200.0), None);
+    }
+}
@@ -0,0 +1,281 @@
+//! A drop-in replacement for `aws cloudformation wait`, blocking with minimal
+//! output until a stack reaches the status waited for
+use crate::{
+    humanize, is_failure, missing_stack, Error, FAILED_STATUS, ROLLED_BACK, TIMED_OUT, UNEXPECTED,
+};
+use colored::Colorize;
+use rusoto_cloudformation::{CloudFormation, CloudFormationClient, DescribeStacksInput};
+use std::{
+    process,
+    str::FromStr,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt, PartialEq, Debug)]
+pub struct Options {
+    #[structopt(
+        long = "for",
+        raw(
+            possible_values = r#"&["stack-exists", "stack-create-complete", "stack-update-complete", "stack-delete-complete", "stack-import-complete", "stack-rollback-complete"]"#
+        ),
+        help = "The awscli waiter to replace"
+    )]
+    pub waiter: Waiter,
+    #[structopt(
+        long = "interval",
+        default_value = "5s",
+        parse(try_from_str = "humantime::parse_duration"),
+        help = "Time between polls, for example 30s"
+    )]
+    pub interval: Duration,
+    #[structopt(
+        long = "heartbeat",
+        parse(try_from_str = "humantime::parse_duration"),
+        help = "Print the stack's status this often while waiting, for example 1m"
+    )]
+    pub heartbeat: Option<Duration>,
+    #[structopt(
+        long = "timeout",
+        parse(try_from_str = "humantime::parse_duration"),
+        help = "Give up with a non-zero exit code after this long, for example 1h"
+    )]
+    pub timeout: Option<Duration>,
+    pub stack_name: String,
+}
+
+/// The stack waiters of `aws cloudformation wait`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Waiter {
+    StackExists,
+    StackCreateComplete,
+    StackUpdateComplete,
+    StackDeleteComplete,
+    StackImportComplete,
+    StackRollbackComplete,
+}
+
+impl FromStr for Waiter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stack-exists" => Ok(Waiter::StackExists),
+            "stack-create-complete" => Ok(Waiter::StackCreateComplete),
+            "stack-update-complete" => Ok(Waiter::StackUpdateComplete),
+            "stack-delete-complete" => Ok(Waiter::StackDeleteComplete),
+            "stack-import-complete" => Ok(Waiter::StackImportComplete),
+            "stack-rollback-complete" => Ok(Waiter::StackRollbackComplete),
+            other => Err(format!("unsupported waiter {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Waiting,
+    Succeeded,
+    Failed,
+}
+
+impl Waiter {
+    /// Statuses which end the wait successfully
+    fn successes(self) -> &'static [&'static str] {
+        match self {
+            Waiter::StackExists => &[],
+            Waiter::StackCreateComplete => &["CREATE_COMPLETE"],
+            Waiter::StackUpdateComplete => &["UPDATE_COMPLETE"],
+            Waiter::StackDeleteComplete => &["DELETE_COMPLETE"],
+            Waiter::StackImportComplete => &["IMPORT_COMPLETE"],
+            Waiter::StackRollbackComplete => &["UPDATE_ROLLBACK_COMPLETE"],
+        }
+    }
+
+    /// Statuses from which the wait can't succeed, matching awscli's waiters
+    fn failures(self) -> &'static [&'static str] {
+        match self {
+            Waiter::StackExists => &[],
+            Waiter::StackCreateComplete => &[
+                "CREATE_FAILED",
+                "DELETE_COMPLETE",
+                "DELETE_FAILED",
+                "ROLLBACK_FAILED",
+                "ROLLBACK_COMPLETE",
+            ],
+            Waiter::StackUpdateComplete => &[
+                "UPDATE_FAILED",
+                "UPDATE_ROLLBACK_FAILED",
+                "UPDATE_ROLLBACK_COMPLETE",
+            ],
+            Waiter::StackDeleteComplete => &[
+                "DELETE_FAILED",
+                "CREATE_FAILED",
+                "ROLLBACK_FAILED",
+                "UPDATE_ROLLBACK_IN_PROGRESS",
+                "UPDATE_ROLLBACK_FAILED",
+                "UPDATE_ROLLBACK_COMPLETE",
+            ],
+            Waiter::StackImportComplete => &[
+                "ROLLBACK_COMPLETE",
+                "ROLLBACK_FAILED",
+                "IMPORT_ROLLBACK_IN_PROGRESS",
+                "IMPORT_ROLLBACK_FAILED",
+                "IMPORT_ROLLBACK_COMPLETE",
+            ],
+            Waiter::StackRollbackComplete => {
+                &["UPDATE_FAILED", "UPDATE_ROLLBACK_FAILED", "DELETE_FAILED"]
+            }
+        }
+    }
+
+    /// What a stack's status, or its absence, means for the wait
+    fn outcome(
+        self,
+        status: Option<&str>,
+    ) -> Outcome {
+        match (self, status) {
+            (Waiter::StackExists, Some(_)) | (Waiter::StackDeleteComplete, None) => {
+                Outcome::Succeeded
+            }
+            (Waiter::StackExists, None) => Outcome::Waiting,
+            (_, None) => Outcome::Failed,
+            (_, Some(status)) if self.successes().contains(&status) => Outcome::Succeeded,
+            (_, Some(status)) if self.failures().contains(&status) => Outcome::Failed,
+            _ => Outcome::Waiting,
+        }
+    }
+}
+
+/// The exit code for a wait which failed with a given stack status
+fn exit_code(status: Option<&str>) -> i32 {
+    match status {
+        Some(status) if is_failure(status) => FAILED_STATUS,
+        Some(status) if status.contains("ROLLBACK") => ROLLED_BACK,
+        _ => UNEXPECTED,
+    }
+}
+
+/// A stack's status, or None when it doesn't exist
+fn current_status(
+    cf: &CloudFormationClient,
+    stack_name: &str,
+) -> Result<Option<String>, Error> {
+    match cf
+        .describe_stacks(DescribeStacksInput {
+            stack_name: Some(stack_name.into()),
+            ..DescribeStacksInput::default()
+        })
+        .sync()
+    {
+        Ok(output) => Ok(output
+            .stacks
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .map(|stack| stack.stack_status)),
+        Err(ref err) if missing_stack(err) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn run(
+    cf: CloudFormationClient,
+    options: Options,
+) -> Result<(), Error> {
+    let Options {
+        waiter,
+        interval,
+        heartbeat,
+        timeout,
+        stack_name,
+    } = options;
+    let start = Instant::now();
+    let mut last_beat = start;
+    loop {
+        let status = current_status(&cf, &stack_name)?;
+        match waiter.outcome(status.as_deref()) {
+            Outcome::Succeeded => return Ok(()),
+            Outcome::Failed => {
+                eprintln!(
+                    "{} {} is {}",
+                    "failed:".bold().red(),
+                    stack_name,
+                    status.as_deref().unwrap_or("no longer present")
+                );
+                process::exit(exit_code(status.as_deref()))
+            }
+            Outcome::Waiting => (),
+        }
+        let elapsed = chrono::Duration::from_std(start.elapsed())
+            .unwrap_or_else(|_| chrono::Duration::zero());
+        if let Some(timeout) = timeout {
+            if start.elapsed() >= timeout {
+                eprintln!(
+                    "{} {} is still {} after {}",
+                    "timed out:".bold().yellow(),
+                    stack_name,
+                    status.as_deref().unwrap_or("missing"),
+                    humanize(elapsed)
+                );
+                process::exit(TIMED_OUT)
+            }
+        }
+        if let Some(heartbeat) = heartbeat {
+            if last_beat.elapsed() >= heartbeat {
+                last_beat = Instant::now();
+                eprintln!(
+                    "{} {} is {}, {} elapsed",
+                    "waiting:".bold(),
+                    stack_name,
+                    status.as_deref().unwrap_or("missing"),
+                    humanize(elapsed)
+                );
+            }
+        }
+        sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waiter_parses_awscli_names() {
+        assert_eq!(
+            "stack-update-complete".parse(),
+            Ok(Waiter::StackUpdateComplete)
+        );
+        assert!("change-set-create-complete".parse::<Waiter>().is_err());
+    }
+
+    #[test]
+    fn outcome_follows_awscli_waiters() {
+        let waiter = Waiter::StackUpdateComplete;
+        assert_eq!(waiter.outcome(Some("UPDATE_IN_PROGRESS")), Outcome::Waiting);
+        assert_eq!(waiter.outcome(Some("UPDATE_COMPLETE")), Outcome::Succeeded);
+        assert_eq!(
+            waiter.outcome(Some("UPDATE_ROLLBACK_COMPLETE")),
+            Outcome::Failed
+        );
+        assert_eq!(waiter.outcome(None), Outcome::Failed);
+        assert_eq!(
+            Waiter::StackDeleteComplete.outcome(None),
+            Outcome::Succeeded
+        );
+        assert_eq!(Waiter::StackExists.outcome(None), Outcome::Waiting);
+        assert_eq!(
+            Waiter::StackExists.outcome(Some("CREATE_IN_PROGRESS")),
+            Outcome::Succeeded
+        );
+    }
+
+    #[test]
+    fn exit_code_reflects_failed_status() {
+        assert_eq!(exit_code(Some("UPDATE_ROLLBACK_FAILED")), FAILED_STATUS);
+        assert_eq!(exit_code(Some("UPDATE_ROLLBACK_COMPLETE")), ROLLED_BACK);
+        assert_eq!(exit_code(Some("IMPORT_ROLLBACK_COMPLETE")), FAILED_STATUS);
+        assert_eq!(exit_code(Some("DELETE_COMPLETE")), UNEXPECTED);
+        assert_eq!(exit_code(None), UNEXPECTED);
+    }
+}